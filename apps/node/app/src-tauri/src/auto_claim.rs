@@ -0,0 +1,161 @@
+//! Background auto-claim of staking rewards
+//!
+//! Periodically checks pending compute rewards and claims them once they cross
+//! the configured threshold and the claim interval has elapsed. RPC failures are
+//! logged and retried on the next check. Timing follows `poll_schedule`.
+
+use alloy::primitives::U256;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+
+use crate::commands::staking::{pending_rewards, send_claim, FeeOverrides, StakingError};
+use crate::config::EarningsConfig;
+use crate::poll_schedule::next_poll_delay;
+use crate::state::AppStateInner;
+
+/// Handle to the running auto-claim task
+pub struct AutoClaimWorker {
+    cancel: Option<CancellationToken>,
+}
+
+impl AutoClaimWorker {
+    pub fn new() -> Self {
+        Self { cancel: None }
+    }
+
+    /// Spawn the worker, replacing any previously running one. It also stops
+    /// when `shutdown` is cancelled.
+    pub fn start(&mut self, state: Arc<RwLock<AppStateInner>>, shutdown: &CancellationToken) {
+        self.stop();
+
+        let cancel = shutdown.child_token();
+        self.cancel = Some(cancel.clone());
+
+        tauri::async_runtime::spawn(async move {
+            tracing::info!("Auto-claim worker started");
+
+            // Check once at startup, then as scheduled
+            let mut delay = Duration::ZERO;
+            let mut failures = 0;
+
+            loop {
+                let activity = state.read().await.poll_activity.clone();
+
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = activity.sleep(delay) => {
+                        match check_and_claim(&state).await {
+                            Ok(()) => failures = 0,
+                            Err(e) => {
+                                failures += 1;
+                                tracing::warn!("Auto-claim check failed: {}", e);
+                            }
+                        }
+                    }
+                }
+
+                // Re-read each round so interval changes apply without a restart
+                let inner = state.read().await;
+                delay = next_poll_delay(&inner.config.earnings, &inner.poll_activity, failures);
+            }
+
+            tracing::info!("Auto-claim worker stopped");
+        });
+    }
+
+    /// Signal the worker to exit; an in-flight claim is allowed to finish
+    pub fn stop(&mut self) {
+        if let Some(cancel) = self.cancel.take() {
+            cancel.cancel();
+        }
+    }
+}
+
+impl Default for AutoClaimWorker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// When the next auto-claim becomes due, or `None` if auto-claim is off
+pub fn next_claim_timestamp(config: &EarningsConfig, now: u64) -> Option<u64> {
+    if !config.auto_claim {
+        return None;
+    }
+
+    let interval_secs = u64::from(config.auto_claim_interval_hours) * 3600;
+    Some(
+        config
+            .last_auto_claim_timestamp
+            .map_or(now, |last| (last + interval_secs).max(now)),
+    )
+}
+
+async fn check_and_claim(state: &RwLock<AppStateInner>) -> Result<(), StakingError> {
+    let tx_hash = {
+        let inner = state.read().await;
+        let earnings = &inner.config.earnings;
+        let now = chrono::Utc::now().timestamp() as u64;
+
+        // Settings may have changed while this check waited for the lock
+        match next_claim_timestamp(earnings, now) {
+            Some(due) if due <= now => {}
+            _ => return Ok(()),
+        }
+
+        // Nothing to claim against until a wallet and contract are set up
+        if inner.wallet_manager.is_none()
+            || inner
+                .compute_staking_address(inner.config.network.chain_id)
+                .is_err()
+        {
+            return Ok(());
+        }
+
+        let threshold = U256::from_str(&earnings.auto_claim_threshold_wei)
+            .map_err(|e| StakingError::Config(format!("Invalid auto-claim threshold: {}", e)))?;
+        let pending = pending_rewards(&inner, "compute").await?;
+
+        if pending.is_zero() || pending < threshold {
+            return Ok(());
+        }
+
+        tracing::info!("Auto-claiming {} wei of pending rewards", pending);
+
+        let submitted =
+            match send_claim(&inner, "compute", &FeeOverrides::default(), true, None).await {
+                // Try again next round rather than pay spike fees or report a failure
+                Err(StakingError::GasPriceTooHigh { current, ceiling }) => {
+                    tracing::info!(
+                        "Skipping auto-claim: gas price {} wei is above the {} wei ceiling",
+                        current,
+                        ceiling
+                    );
+                    return Ok(());
+                }
+                result => result?,
+            };
+        if let Some(error) = submitted.error {
+            return Err(StakingError::TransactionReverted(format!(
+                "Claim transaction {} failed: {}",
+                submitted.tx_hash, error
+            )));
+        }
+
+        submitted.tx_hash
+    };
+
+    let mut inner = state.write().await;
+    let now = chrono::Utc::now().timestamp() as u64;
+    inner
+        .config
+        .update(|config| config.earnings.last_auto_claim_timestamp = Some(now))
+        .map_err(StakingError::Config)?;
+
+    tracing::info!("Auto-claimed rewards in tx {}", tx_hash);
+
+    Ok(())
+}
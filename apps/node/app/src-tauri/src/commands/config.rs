@@ -1,7 +1,17 @@
 //! Configuration management commands
 
-use crate::config::{BotConfig, EarningsConfig, NetworkConfig, ServiceConfig};
-use crate::state::AppState;
+use crate::commands::staking::{
+    parse_address, readiness_report, service_descriptors, ReadinessReport,
+};
+use crate::config::{
+    parse_contract_address, BotConfig, EarningsConfig, NetworkConfig, ServiceConfig,
+};
+use crate::log_buffer;
+use crate::redact::{mask_address, redact_text, redact_url};
+use crate::staking_log::StakingLogEntry;
+use crate::state::{AppState, DevOverrides};
+use alloy::eips::BlockNumberOrTag;
+use alloy::providers::Provider;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tauri::State;
@@ -51,12 +61,7 @@ pub struct NetworkOption {
 pub async fn get_config(state: State<'_, AppState>) -> Result<AppConfig, String> {
     let inner = state.inner.read().await;
 
-    let wallet_type = match inner.config.wallet.wallet_type {
-        crate::config::WalletType::None => "none",
-        crate::config::WalletType::Embedded => "embedded",
-        crate::config::WalletType::External => "external",
-        crate::config::WalletType::JejuWallet => "jeju_wallet",
-    };
+    let wallet_type = wallet_type_name(&inner.config.wallet.wallet_type);
 
     Ok(AppConfig {
         version: inner.config.version.clone(),
@@ -82,43 +87,34 @@ pub async fn update_config(
 ) -> Result<AppConfig, String> {
     let mut inner = state.inner.write().await;
 
-    if let Some(earnings) = request.earnings {
-        inner.config.earnings = earnings;
-    }
-
-    if let Some(services) = request.services {
-        for (id, config) in services {
-            inner.config.services.insert(id, config);
+    inner.config.update(|config| {
+        if let Some(earnings) = request.earnings {
+            config.earnings = earnings;
         }
-    }
 
-    if let Some(bots) = request.bots {
-        for (id, config) in bots {
-            inner.config.bots.insert(id, config);
+        if let Some(services) = request.services {
+            config.services.extend(services);
         }
-    }
 
-    if let Some(start_minimized) = request.start_minimized {
-        inner.config.start_minimized = start_minimized;
-    }
+        if let Some(bots) = request.bots {
+            config.bots.extend(bots);
+        }
 
-    if let Some(start_on_boot) = request.start_on_boot {
-        inner.config.start_on_boot = start_on_boot;
-    }
+        if let Some(start_minimized) = request.start_minimized {
+            config.start_minimized = start_minimized;
+        }
 
-    if let Some(notifications) = request.notifications_enabled {
-        inner.config.notifications_enabled = notifications;
-    }
+        if let Some(start_on_boot) = request.start_on_boot {
+            config.start_on_boot = start_on_boot;
+        }
 
-    inner.config.save().map_err(|e| e.to_string())?;
+        if let Some(notifications) = request.notifications_enabled {
+            config.notifications_enabled = notifications;
+        }
+    })?;
 
     // Return updated config
-    let wallet_type = match inner.config.wallet.wallet_type {
-        crate::config::WalletType::None => "none",
-        crate::config::WalletType::Embedded => "embedded",
-        crate::config::WalletType::External => "external",
-        crate::config::WalletType::JejuWallet => "jeju_wallet",
-    };
+    let wallet_type = wallet_type_name(&inner.config.wallet.wallet_type);
 
     Ok(AppConfig {
         version: inner.config.version.clone(),
@@ -143,6 +139,79 @@ pub async fn get_network_config(state: State<'_, AppState>) -> Result<NetworkCon
     Ok(inner.config.network.clone())
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcEndpointStatus {
+    /// Endpoint requests are currently sent to
+    pub active_url: String,
+    /// Every configured endpoint, in failover order
+    pub endpoints: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn get_active_rpc_endpoint(
+    state: State<'_, AppState>,
+) -> Result<RpcEndpointStatus, String> {
+    let inner = state.inner.read().await;
+
+    Ok(RpcEndpointStatus {
+        active_url: inner.active_rpc_url().await?,
+        endpoints: inner.rpc_urls(),
+    })
+}
+
+/// How far the latest block may trail the local clock before the endpoint is
+/// reported as behind. Comfortably above the block time, so a slow block
+/// doesn't trip it.
+const BEHIND_AFTER_SECS: u64 = 120;
+
+/// The chain as seen through the active RPC endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkStatus {
+    pub chain_id: u64,
+    pub block_number: u64,
+    /// Unix time of the latest block
+    pub block_timestamp: u64,
+    /// How long ago the latest block was made, by the local clock
+    pub block_age_secs: u64,
+    /// `None` on chains without EIP-1559
+    pub base_fee_per_gas: Option<String>,
+    pub active_rpc_url: String,
+    /// The latest block is over `BEHIND_AFTER_SECS` old, so the endpoint is
+    /// probably still syncing or stuck
+    pub behind: bool,
+}
+
+/// Block height, gas and sync state for a network panel. Costs one RPC call,
+/// since the chain ID is cached with the provider, so it's fine to poll.
+#[tauri::command]
+pub async fn get_network_status(state: State<'_, AppState>) -> Result<NetworkStatus, String> {
+    let inner = state.inner.read().await;
+    let provider = inner.provider().await?;
+
+    let chain_id = inner.chain_id().await?;
+    let block = provider
+        .get_block_by_number(BlockNumberOrTag::Latest, false)
+        .await
+        .map_err(|e| format!("Failed to get latest block: {}", e))?
+        .ok_or("Endpoint returned no latest block")?;
+    // Read after the request, which may have failed over to another endpoint
+    let active_rpc_url = inner
+        .connected_rpc_url()
+        .ok_or("RPC endpoints changed while connecting")?;
+
+    let block_timestamp = block.header.timestamp;
+    let block_age_secs = (chrono::Utc::now().timestamp() as u64).saturating_sub(block_timestamp);
+    Ok(NetworkStatus {
+        chain_id,
+        block_number: block.header.number.unwrap_or_default(),
+        block_timestamp,
+        block_age_secs,
+        base_fee_per_gas: block.header.base_fee_per_gas.map(|fee| fee.to_string()),
+        active_rpc_url,
+        behind: block_age_secs > BEHIND_AFTER_SECS,
+    })
+}
+
 #[tauri::command]
 pub async fn set_network(
     state: State<'_, AppState>,
@@ -157,6 +226,15 @@ pub async fn set_network(
             rpc_url: "https://rpc.jejunetwork.org".to_string(),
             ws_url: Some("wss://ws.jejunetwork.org".to_string()),
             explorer_url: "https://explorer.jejunetwork.org".to_string(),
+            expected_chain_id: None,
+            receipt_timeout_secs: inner.config.network.receipt_timeout_secs,
+            rpc_max_attempts: inner.config.network.rpc_max_attempts,
+            rpc_timeout_ms: inner.config.network.rpc_timeout_ms,
+            required_confirmations: inner.config.network.required_confirmations,
+            fallback_rpc_urls: Vec::new(),
+            reorg_watch_depth: inner.config.network.reorg_watch_depth,
+            ens_registry: None,
+            max_gas_price_wei: inner.config.network.max_gas_price_wei.clone(),
         },
         "testnet" => NetworkConfig {
             network: "testnet".to_string(),
@@ -164,6 +242,15 @@ pub async fn set_network(
             rpc_url: "https://testnet-rpc.jejunetwork.org".to_string(),
             ws_url: Some("wss://testnet-ws.jejunetwork.org".to_string()),
             explorer_url: "https://testnet-explorer.jejunetwork.org".to_string(),
+            expected_chain_id: None,
+            receipt_timeout_secs: inner.config.network.receipt_timeout_secs,
+            rpc_max_attempts: inner.config.network.rpc_max_attempts,
+            rpc_timeout_ms: inner.config.network.rpc_timeout_ms,
+            required_confirmations: inner.config.network.required_confirmations,
+            fallback_rpc_urls: Vec::new(),
+            reorg_watch_depth: inner.config.network.reorg_watch_depth,
+            ens_registry: None,
+            max_gas_price_wei: inner.config.network.max_gas_price_wei.clone(),
         },
         "localnet" => NetworkConfig {
             network: "localnet".to_string(),
@@ -171,12 +258,22 @@ pub async fn set_network(
             rpc_url: "http://localhost:6546".to_string(),
             ws_url: Some("ws://localhost:6547".to_string()),
             explorer_url: "http://localhost:4000".to_string(),
+            expected_chain_id: None,
+            receipt_timeout_secs: inner.config.network.receipt_timeout_secs,
+            rpc_max_attempts: inner.config.network.rpc_max_attempts,
+            rpc_timeout_ms: inner.config.network.rpc_timeout_ms,
+            required_confirmations: inner.config.network.required_confirmations,
+            fallback_rpc_urls: Vec::new(),
+            reorg_watch_depth: inner.config.network.reorg_watch_depth,
+            ens_registry: None,
+            max_gas_price_wei: inner.config.network.max_gas_price_wei.clone(),
         },
         _ => return Err(format!("Unknown network: {}", network)),
     };
 
-    inner.config.network = network_config.clone();
-    inner.config.save().map_err(|e| e.to_string())?;
+    inner
+        .config
+        .update(|config| config.network = network_config.clone())?;
 
     // Re-initialize services with new network - clone config to avoid borrow conflict
     let config_clone = inner.config.clone();
@@ -187,3 +284,191 @@ pub async fn set_network(
 
     Ok(network_config)
 }
+
+/// Point staking at other contracts and/or an RPC endpoint for this session,
+/// e.g. a modified contract on an Anvil fork. Debug builds only. Each call
+/// replaces all three overrides, so `None` clears one. Overrides are kept in
+/// memory and never written to the config file.
+#[tauri::command]
+pub async fn set_contract_addresses_dev(
+    state: State<'_, AppState>,
+    compute: Option<String>,
+    node: Option<String>,
+    rpc_url: Option<String>,
+) -> Result<DevOverrides, String> {
+    if !cfg!(debug_assertions) {
+        return Err("Contract overrides are only available in debug builds".to_string());
+    }
+
+    for address in [&compute, &node].into_iter().flatten() {
+        parse_contract_address(address)?;
+    }
+    if let Some(rpc_url) = &rpc_url {
+        rpc_url
+            .parse::<alloy::transports::http::reqwest::Url>()
+            .map_err(|e| format!("Invalid RPC URL: {}", e))?;
+    }
+
+    let mut inner = state.inner.write().await;
+    inner.dev_overrides = DevOverrides {
+        compute_staking: compute,
+        node_staking_manager: node,
+        rpc_url,
+        read_as: inner.dev_overrides.read_as,
+    };
+    inner.staking_info_cache.invalidate();
+    tracing::warn!("Dev overrides set: {:?}", inner.dev_overrides);
+
+    Ok(inner.dev_overrides.clone())
+}
+
+/// Have read commands report on `address` instead of the connected wallet,
+/// e.g. to view a staked account on an Anvil fork without its keys. Debug
+/// builds only; `None` goes back to the wallet. Commands given an explicit
+/// address still use it, and writes always act as the wallet.
+#[tauri::command]
+pub async fn read_as(
+    state: State<'_, AppState>,
+    address: Option<String>,
+) -> Result<DevOverrides, String> {
+    if !cfg!(debug_assertions) {
+        return Err("Reading as another address is only available in debug builds".to_string());
+    }
+
+    let address = address
+        .map(|address| parse_address(&address).map_err(|e| e.to_string()))
+        .transpose()?;
+
+    let mut inner = state.inner.write().await;
+    inner.dev_overrides.read_as = address;
+    tracing::warn!("Dev overrides set: {:?}", inner.dev_overrides);
+
+    Ok(inner.dev_overrides.clone())
+}
+
+/// Lines returned by `get_recent_logs` when no limit is given
+const DEFAULT_LOG_LIMIT: usize = 500;
+
+fn wallet_type_name(wallet_type: &crate::config::WalletType) -> &'static str {
+    match wallet_type {
+        crate::config::WalletType::None => "none",
+        crate::config::WalletType::Embedded => "embedded",
+        crate::config::WalletType::External => "external",
+        crate::config::WalletType::JejuWallet => "jeju_wallet",
+        crate::config::WalletType::Ledger => "ledger",
+        crate::config::WalletType::WatchOnly => "watch_only",
+    }
+}
+
+/// Recent log output held in memory, oldest first, for support bundles
+#[tauri::command]
+pub async fn get_recent_logs(limit: Option<usize>) -> Result<Vec<String>, String> {
+    Ok(log_buffer::recent(limit.unwrap_or(DEFAULT_LOG_LIMIT)))
+}
+
+/// Local staking log entries included in a diagnostic bundle
+const DIAGNOSTIC_STAKING_LOG_LIMIT: usize = 50;
+
+/// App state for a support request, safe to attach to a public issue: URLs
+/// are cut down to their host, wallet addresses masked, and key-length hex
+/// strings removed from free text. Contract addresses are public and kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticBundle {
+    pub generated_at: i64,
+    pub app_version: String,
+    pub os: String,
+    pub network: String,
+    pub chain_id: u64,
+    /// Endpoint the app is connected through, if any
+    pub active_rpc_url: Option<String>,
+    /// Configured endpoints, in failover order
+    pub rpc_urls: Vec<String>,
+    pub contracts: Vec<DiagnosticContract>,
+    pub wallet_type: String,
+    pub wallet_address: Option<String>,
+    pub writes_disabled: bool,
+    /// Most recent first
+    pub staking_log: Vec<StakingLogEntry>,
+    /// Set when the staking log couldn't be read
+    pub staking_log_error: Option<String>,
+    /// Oldest first
+    pub recent_logs: Vec<String>,
+    pub readiness: ReadinessReport,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticContract {
+    pub name: String,
+    pub address: String,
+}
+
+/// Gather configuration, recent activity and readiness checks for a support
+/// request, with secrets redacted
+#[tauri::command]
+pub async fn export_diagnostics(state: State<'_, AppState>) -> Result<DiagnosticBundle, String> {
+    let inner = state.inner.read().await;
+    let chain_id = inner.config.network.chain_id;
+
+    let mut contracts: Vec<_> = service_descriptors(&inner)
+        .await
+        .into_iter()
+        .map(|service| DiagnosticContract {
+            name: service.id,
+            address: service.contract_address,
+        })
+        .collect();
+    if let Ok(multicall) = inner.multicall_address(chain_id) {
+        contracts.push(DiagnosticContract {
+            name: "multicall3".to_string(),
+            address: multicall.to_string(),
+        });
+    }
+
+    let (staking_log, staking_log_error) = match inner
+        .staking_log
+        .entries(&inner.config.staking, Some(DIAGNOSTIC_STAKING_LOG_LIMIT))
+    {
+        Ok(entries) => (entries.into_iter().map(redact_log_entry).collect(), None),
+        Err(e) => (Vec::new(), Some(redact_text(&e.to_string()))),
+    };
+
+    let mut readiness = readiness_report(&inner).await;
+    for check in &mut readiness.checks {
+        check.detail = redact_text(&check.detail);
+    }
+
+    Ok(DiagnosticBundle {
+        generated_at: chrono::Utc::now().timestamp(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        network: inner.config.network.network.clone(),
+        chain_id,
+        active_rpc_url: inner.connected_rpc_url().as_deref().map(redact_url),
+        rpc_urls: inner.rpc_urls().iter().map(|url| redact_url(url)).collect(),
+        contracts,
+        wallet_type: wallet_type_name(&inner.config.wallet.wallet_type).to_string(),
+        wallet_address: inner
+            .wallet_manager
+            .as_ref()
+            .and_then(|wallet| wallet.address())
+            .map(mask_address),
+        writes_disabled: inner.config.safety.writes_disabled,
+        staking_log,
+        staking_log_error,
+        recent_logs: log_buffer::recent(DEFAULT_LOG_LIMIT)
+            .iter()
+            .map(|line| redact_text(line))
+            .collect(),
+        readiness,
+    })
+}
+
+/// A staking log entry with its transaction hash dropped, since the hash
+/// leads straight to the wallet on an explorer
+fn redact_log_entry(entry: StakingLogEntry) -> StakingLogEntry {
+    StakingLogEntry {
+        tx_hash: entry.tx_hash.as_deref().map(redact_text),
+        error: entry.error.as_deref().map(redact_text),
+        ..entry
+    }
+}
@@ -2,7 +2,9 @@
 
 use crate::earnings::EarningsEventType;
 use crate::state::AppState;
+use alloy::primitives::U256;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use tauri::State;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -187,10 +189,9 @@ pub async fn get_earnings_summary(state: State<'_, AppState>) -> Result<Earnings
 
 /// Convert wei to USD (placeholder conversion rate)
 fn wei_to_usd(wei_str: &str) -> f64 {
-    let wei: u128 = wei_str.parse().unwrap_or(0);
-    // Assuming 1 ETH = $2000, 1 ETH = 10^18 wei
-    let eth = wei as f64 / 1e18;
-    eth * 2000.0
+    let wei = U256::from_str(wei_str).unwrap_or_default();
+    // Assuming 1 ETH = $2000
+    crate::price::wei_to_usd(wei, 2000.0).unwrap_or(0.0)
 }
 
 #[tauri::command]
@@ -1,18 +1,133 @@
-use crate::state::AppState;
-use alloy::primitives::Address;
+use crate::auto_claim;
+use crate::commands::wallet::prompt_hardware_confirmation;
+use crate::config::{parse_contract_address, EarningsConfig, ServiceDescriptor};
+use crate::contracts::ContractClient;
+use crate::ens;
+use crate::price::wei_to_tokens;
+use crate::redact::mask_address;
+use crate::staking_log::{StakingAction, StakingLogEntry, StakingOutcome};
+use crate::state::{AppState, AppStateInner, ReadProvider};
+use crate::wallet::{WalletSigner, SIGNING_REJECTED};
+use alloy::consensus::{Transaction as _, TxEnvelope};
+use alloy::contract::{CallBuilder, CallDecoder};
+use alloy::dyn_abi::TypedData;
+use alloy::eips::eip2718::{Decodable2718, Encodable2718};
+use alloy::eips::{BlockId, BlockNumberOrTag};
+use alloy::network::{Ethereum, EthereumWallet, TransactionBuilder};
+use alloy::primitives::utils::{format_units, parse_units};
+use alloy::primitives::{hex, Address, Bytes, Signature, TxHash, B256, U256};
+use alloy::providers::{PendingTransactionBuilder, Provider, ProviderBuilder};
+use alloy::rpc::types::{Filter, Log, Transaction, TransactionReceipt, TransactionRequest};
 use alloy::sol;
+use alloy::sol_types::{decode_revert_reason, Eip712Domain, SolCall, SolEvent, SolStruct};
+use alloy::transports::http::reqwest::Url;
+use alloy::transports::{RpcError, Transport, TransportErrorKind};
+use futures::future::join_all;
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::str::FromStr;
-use tauri::State;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, State};
+
+mod chain;
+
+use chain::{call_tx, AlloyChain, StakingChain};
+
+/// Shortest stake age an APR is extrapolated from
+const MIN_RATE_SAMPLE_SECS: u64 = 3600;
+
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0;
+
+const SECONDS_PER_DAY: u64 = 24 * 3600;
+
+const WEI_PER_ETH: u128 = 1_000_000_000_000_000_000;
 
 sol! {
     #[sol(rpc)]
     interface IComputeStaking {
         function stakeAsProvider() external payable;
+        function stakeAsProviderFor(address beneficiary) external payable;
+        function stakeTokenAsProvider(address token, uint256 amount) external;
+        function increaseStake() external payable;
+        function increaseTokenStake(address token, uint256 amount) external;
+        function stakeWithPermit(address token, uint256 amount, uint256 deadline, uint8 v, bytes32 r, bytes32 s) external;
         function getStake(address staker) external view returns (uint256 amount, uint8 stakeType, uint256 stakedAt);
+        /// Token `staker`'s stake is held in; the zero address for native ETH
+        function stakeToken(address staker) external view returns (address);
         function unstake() external;
+        function unstake(uint256 amount) external;
+        function unstakePenalty(address staker, uint256 amount) external view returns (uint256);
         function pendingRewards(address staker) external view returns (uint256);
         function claimRewards() external returns (uint256);
+        function claimRewardsTo(address recipient) external returns (uint256);
+        function rewardRate() external view returns (uint256);
+        function minStake() external view returns (uint256);
+        function unstakeCooldown() external view returns (uint256);
+        function earlyUnstakePenaltyBps() external view returns (uint256);
+        function nonces(address owner) external view returns (uint256);
+        function eip712Domain() external view returns (
+            bytes1 fields,
+            string name,
+            string version,
+            uint256 chainId,
+            address verifyingContract,
+            bytes32 salt,
+            uint256[] extensions
+        );
+
+        event Staked(address indexed staker, uint256 amount, uint8 stakeType);
+        event Unstaked(address indexed staker, uint256 amount);
+        event RewardsClaimed(address indexed staker, uint256 amount);
+    }
+
+    /// Token stake opened for `staker` by a relayer paying the gas, signed
+    /// as EIP-712 typed data
+    #[derive(Serialize)]
+    struct StakeAuthorization {
+        address staker;
+        address token;
+        uint256 amount;
+        uint256 nonce;
+        uint256 deadline;
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    interface IERC20 {
+        function approve(address spender, uint256 amount) external returns (bool);
+        function allowance(address owner, address spender) external view returns (uint256);
+        function balanceOf(address account) external view returns (uint256);
+        function decimals() external view returns (uint8);
+        function symbol() external view returns (string);
+    }
+}
+
+sol! {
+    /// OpenZeppelin `Pausable`, implemented by some of the staking contracts
+    #[sol(rpc)]
+    interface IPausable {
+        function paused() external view returns (bool);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    interface IERC20Permit {
+        function name() external view returns (string);
+        function nonces(address owner) external view returns (uint256);
+        function DOMAIN_SEPARATOR() external view returns (bytes32);
+    }
+
+    /// EIP-2612 allowance, signed as EIP-712 typed data
+    struct Permit {
+        address owner;
+        address spender;
+        uint256 value;
+        uint256 nonce;
+        uint256 deadline;
     }
 }
 
@@ -31,13 +146,44 @@ sol! {
         );
         function nodePendingRewards(address operator) external view returns (uint256);
         function nodeClaimRewards() external returns (uint256);
+        function updateNodeInfo(string rpcUrl, string region) external;
+
+        event NodeStaked(address indexed operator, address stakeToken, uint256 amount);
+        event NodeUnstaked(address indexed operator, uint256 amount);
+        event NodeRewardsClaimed(address indexed operator, uint256 amount);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    interface IMulticall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        struct Call3Result {
+            bool success;
+            bytes returnData;
+        }
+
+        function aggregate3(Call3[] calls) external payable returns (Call3Result[] returnData);
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StakingInfo {
+    /// Native ETH staked across services, as shown before per-token totals;
+    /// stakes in other tokens are only in `staked_by_token`
     pub total_staked_wei: String,
+    /// Stake summed per token, since amounts in different tokens don't add up
+    pub staked_by_token: Vec<TokenTotal>,
+    /// Value of every stake combined
     pub total_staked_usd: f64,
+    /// Whether the compute staking contract is paused, during which stakes,
+    /// unstakes and claims are refused. False for contracts that can't pause.
+    pub is_paused: bool,
     pub staked_by_service: Vec<ServiceStakeInfo>,
     pub pending_rewards_wei: String,
     pub pending_rewards_usd: f64,
@@ -45,6 +191,62 @@ pub struct StakingInfo {
     pub unstake_cooldown_seconds: u64,
     pub auto_claim_enabled: bool,
     pub next_auto_claim_timestamp: Option<u64>,
+    /// True when a price lookup failed and some USD values fell back to 0.0
+    pub prices_stale: bool,
+    /// Compute stake APR as a fraction (0.05 = 5%), extrapolated from rewards
+    /// accrued since `stakedAt`. Assumes a constant reward rate and that nothing
+    /// was claimed since staking, so it reads low after a claim. `None` until the
+    /// stake is at least an hour old, and for reads at a past block.
+    pub apr: Option<f64>,
+    /// `apr` compounded daily, assuming rewards are claimed and restaked each day
+    pub apy: Option<f64>,
+    /// Contract reads that failed. When non-empty, zeros above may mean "unknown"
+    /// rather than "nothing staked" for the listed services.
+    pub read_errors: Vec<StakeReadError>,
+    /// True when every read succeeded, so zeros above really mean nothing is
+    /// staked. False whenever `read_errors` is non-empty.
+    pub data_complete: bool,
+}
+
+/// Stake held in one token across all services
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenTotal {
+    pub token: String,
+    pub amount_wei: String,
+    pub amount_usd: f64,
+}
+
+/// Staking across several addresses, e.g. a fleet of node wallets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateStakingInfo {
+    /// One entry per requested address, in request order
+    pub addresses: Vec<AddressStakingInfo>,
+    /// Stake of every address that could be read, summed per token
+    pub staked_by_token: Vec<TokenTotal>,
+    pub total_staked_usd: f64,
+    pub pending_rewards_usd: f64,
+    /// True when a price lookup failed for any address
+    pub prices_stale: bool,
+    /// True when every address was read in full
+    pub data_complete: bool,
+}
+
+/// Staking of one address in an aggregate. `error` is set instead of `info`
+/// when the address was invalid, a duplicate, or couldn't be read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressStakingInfo {
+    /// The address or ENS name as requested
+    pub address: String,
+    pub info: Option<StakingInfo>,
+    pub error: Option<String>,
+}
+
+/// A stake or rewards read that reverted or failed, with the decoded reason
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakeReadError {
+    pub service_id: String,
+    pub call: String,
+    pub error: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,253 +257,8998 @@ pub struct ServiceStakeInfo {
     pub staked_usd: f64,
     pub pending_rewards_wei: String,
     pub stake_token: String,
+    /// Role the stake was made in, as reported by `getStake`. `None` for
+    /// contracts that don't report one, such as the node staking manager.
+    pub stake_kind: Option<StakeKind>,
     pub min_stake_wei: String,
+    /// Node-only: RPC endpoint the node registered with
+    pub rpc_url: Option<String>,
+    /// Node-only: region the node registered in
+    pub region: Option<String>,
+    /// Node-only: requests served as tracked by the node staking manager
+    pub requests_served: Option<u64>,
+    /// The positions making up `staked_wei`. Current contracts hold one
+    /// position per staker; tiered staking will allow several. Empty when the
+    /// contract doesn't report positions.
+    pub positions: Vec<StakePosition>,
+}
+
+/// The `stakeType` byte from `getStake`, matching the contract's `StakeType`:
+/// 0 none, 1 user, 2 provider, 3 guardian. Values added by later contract
+/// versions come through as `Unknown` rather than failing the read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StakeKind {
+    /// No stake held
+    None,
+    /// Staked as a user of compute services
+    User,
+    /// Staked as a compute provider, which is how this app stakes
+    Provider,
+    /// Staked as a guardian that moderates providers
+    Guardian,
+    Unknown(u8),
+}
+
+impl From<u8> for StakeKind {
+    fn from(stake_type: u8) -> Self {
+        match stake_type {
+            0 => StakeKind::None,
+            1 => StakeKind::User,
+            2 => StakeKind::Provider,
+            3 => StakeKind::Guardian,
+            other => StakeKind::Unknown(other),
+        }
+    }
+}
+
+/// One position within a service stake
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakePosition {
+    /// Index of the position within the service; 0 on single-position contracts
+    pub position_id: u64,
+    pub staked_wei: String,
+    /// Unix time the position was opened
+    pub staked_at: u64,
+    pub cooldown_remaining_seconds: u64,
+    /// Whether this position is past its cooldown and can be withdrawn
+    pub can_unstake: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StakeRequest {
     pub service_id: String,
+    /// May be left empty when `amount_usd` is given; wins when both are set
+    #[serde(default)]
     pub amount_wei: String,
+    /// Stake this many US dollars' worth, converted at a price fetched at
+    /// send time. The price can move between that quote and execution, so
+    /// the value actually staked may differ slightly from the amount asked for.
+    pub amount_usd: Option<f64>,
     pub token_address: Option<String>, // None = ETH
+    /// Reject up front if the balance can't cover amount + gas (default true)
+    pub check_balance: Option<bool>,
+    /// Run the call with `eth_call` first and refuse to broadcast if it reverts (default true)
+    pub simulate: Option<bool>,
+    /// Stake on behalf of this address, which then owns the stake and earns
+    /// its rewards. Native ETH only; `None` stakes for the connected wallet.
+    pub beneficiary: Option<String>,
+    /// For a new token stake, sign an EIP-2612 permit and stake with it in a
+    /// single transaction instead of approving first. Tokens without permit
+    /// support, and top-ups, fall back to approving.
+    #[serde(default)]
+    pub use_permit: bool,
+    /// Unix time the permit expires; defaults to `PERMIT_VALIDITY` from now,
+    /// or the transaction's `deadline_unix` when that comes sooner
+    pub permit_deadline: Option<u64>,
+    #[serde(flatten)]
+    pub fees: FeeOverrides,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnstakeRequest {
     pub service_id: String,
     pub amount_wei: String,
+    /// Run the call with `eth_call` first and refuse to broadcast if it reverts (default true)
+    pub simulate: Option<bool>,
+    #[serde(flatten)]
+    pub fees: FeeOverrides,
+}
+
+/// EIP-1559 fee caps that replace the provider's estimates when set, and the
+/// time after which the transaction must no longer be broadcast
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeeOverrides {
+    pub max_fee_per_gas: Option<String>,
+    pub max_priority_fee_per_gas: Option<String>,
+    /// Unix time after which the transaction is refused rather than sent, so
+    /// a write held up by a sleeping machine doesn't go out late at stale fees
+    pub deadline_unix: Option<u64>,
+}
+
+impl FeeOverrides {
+    /// Fail once `now` is past the deadline
+    fn check_deadline(&self, now: u64) -> Result<(), StakingError> {
+        match self.deadline_unix {
+            Some(deadline_unix) if now > deadline_unix => {
+                Err(StakingError::DeadlineExceeded { deadline_unix, now })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Set the overridden fees on a call, leaving the rest to the fillers
+    fn apply<T, P, D>(
+        &self,
+        call: CallBuilder<T, P, D>,
+    ) -> Result<CallBuilder<T, P, D>, StakingError>
+    where
+        T: Transport + Clone,
+        P: Provider<T>,
+        D: CallDecoder,
+    {
+        let max_fee = parse_fee("max_fee_per_gas", self.max_fee_per_gas.as_deref())?;
+        let priority_fee = parse_fee(
+            "max_priority_fee_per_gas",
+            self.max_priority_fee_per_gas.as_deref(),
+        )?;
+
+        if let (Some(max_fee), Some(priority_fee)) = (max_fee, priority_fee) {
+            if priority_fee > max_fee {
+                return Err(StakingError::InvalidAmount(format!(
+                    "max_priority_fee_per_gas {} exceeds max_fee_per_gas {}",
+                    priority_fee, max_fee
+                )));
+            }
+        }
+
+        let call = match max_fee {
+            Some(max_fee) => call.max_fee_per_gas(max_fee),
+            None => call,
+        };
+        Ok(match priority_fee {
+            Some(priority_fee) => call.max_priority_fee_per_gas(priority_fee),
+            None => call,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnstakePreview {
+    pub principal_wei: String,
+    pub penalty_wei: String,
+    pub net_receive_wei: String,
+    pub cooldown_remaining_seconds: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StakeResult {
     pub success: bool,
     pub tx_hash: Option<String>,
+    /// ERC20 stakes only: the approve transaction sent ahead of the stake
+    pub approval_tx_hash: Option<String>,
     pub new_stake_wei: String,
+    /// Stakes only: the amount sent, after converting any `amount_usd`
+    pub amount_wei: Option<String>,
+    /// USD price `amount_usd` was converted at, when it was used
+    pub usd_price: Option<f64>,
     pub error: Option<String>,
+    /// Stakes only: the address the stake and its rewards belong to
+    pub beneficiary: Option<String>,
+    /// ENS name of `beneficiary`, when it has one
+    pub beneficiary_name: Option<String>,
+    /// Gas paid for the mined transactions behind this result, including any
+    /// approval; `None` if nothing was mined
+    pub gas_cost_wei: Option<String>,
+    /// `None` when the ETH price is unavailable
+    pub gas_cost_usd: Option<f64>,
+    /// Everything the transaction logged; empty unless it was confirmed
+    pub events: Vec<DecodedEvent>,
 }
 
+/// A log from a confirmed transaction. Staking events are decoded into named
+/// fields; anything else is passed through as raw topics and data.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ClaimResult {
-    pub success: bool,
+pub struct DecodedEvent {
+    /// Contract that emitted the log
+    pub address: String,
+    /// Event name, e.g. `Staked`; `None` when the log isn't a known event
+    pub name: Option<String>,
+    /// Decoded parameters in declaration order; empty for unknown logs
+    pub fields: Vec<EventField>,
+    /// Unknown logs only: the raw topics, hex encoded
+    pub topics: Vec<String>,
+    /// Unknown logs only: the raw data, hex encoded
+    pub data: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventField {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StakingEventKind {
+    Stake,
+    Unstake,
+    Claim,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakingEvent {
+    pub kind: StakingEventKind,
+    pub service_id: String,
+    pub amount_wei: String,
     pub tx_hash: Option<String>,
-    pub amount_claimed_wei: String,
-    pub error: Option<String>,
+    pub block_number: u64,
+    pub timestamp: Option<u64>,
 }
 
-#[tauri::command]
-pub async fn get_staking_info(state: State<'_, AppState>) -> Result<StakingInfo, String> {
-    let inner = state.inner.read().await;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub checks: Vec<ReadinessCheck>,
+}
 
-    // Get contract client and wallet
-    let contract_client = inner
-        .contract_client
-        .as_ref()
-        .ok_or("Contract client not initialized. Connect wallet first.")?;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessCheck {
+    pub id: String,
+    pub passed: bool,
+    pub detail: String,
+}
 
-    let wallet = inner
-        .wallet_manager
-        .as_ref()
-        .ok_or("Wallet not connected")?;
+impl ReadinessCheck {
+    fn new(id: &str, result: Result<String, String>) -> Self {
+        let passed = result.is_ok();
+        Self {
+            id: id.to_string(),
+            passed,
+            detail: result.unwrap_or_else(|e| e),
+        }
+    }
+}
 
-    let wallet_info = wallet.get_info().ok_or("Failed to get wallet info")?;
-    let operator = Address::from_str(&wallet_info.address)
-        .map_err(|e| format!("Invalid wallet address: {}", e))?;
+/// Event carrying `TxStatusEvent` as a staking transaction moves through the mempool
+pub const TX_STATUS_EVENT: &str = "staking://tx-status";
 
-    // Query staking contracts for current stake amounts
-    let stakes = contract_client
-        .get_staking_info(operator)
-        .await
-        .unwrap_or_default();
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxStatus {
+    /// Broadcast and waiting in the mempool
+    Submitted,
+    /// Mined, waiting for `required_confirmations`
+    Confirming,
+    /// Mined successfully with the required confirmations
+    Confirmed,
+    /// Mined but reverted
+    Reverted,
+    /// Not mined, or not confirmed enough, before the timeout; it may still land
+    Unconfirmed,
+    /// Reorged out of the chain before reaching the required confirmations,
+    /// or no longer known to the node while waiting to be mined
+    Dropped,
+}
 
-    // Aggregate stake info
-    let mut total_staked: u128 = 0;
-    let mut total_staked_usd: f64 = 0.0;
-    let mut total_pending: u128 = 0;
-    let mut service_stakes = Vec::new();
+/// Event emitted with a `WalletReconnectEvent` payload when a write was
+/// refused because the wallet has no signer
+pub const WALLET_RECONNECT_EVENT: &str = "staking://wallet-reconnect-needed";
 
-    for stake in stakes {
-        let staked_amount: u128 = stake.staked_amount.parse().unwrap_or(0);
-        let staked_usd: f64 = stake.staked_value_usd.parse().unwrap_or(0.0) / 1e18;
-        let pending: u128 = stake.pending_rewards.parse().unwrap_or(0);
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletReconnectEvent {
+    /// Why the write couldn't be signed
+    pub reason: String,
+    /// The write `retry_pending_write` will send once the wallet is back
+    pub intent: WriteIntent,
+}
 
-        total_staked += staked_amount;
-        total_staked_usd += staked_usd;
-        total_pending += pending;
+/// A write command with the parameters it was called with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", content = "request", rename_all = "snake_case")]
+pub enum WriteIntent {
+    Stake(StakeRequest),
+    IncreaseStake(StakeRequest),
+    Unstake(UnstakeRequest),
+    ClaimRewards {
+        service_id: Option<String>,
+        fees: Option<FeeOverrides>,
+        simulate: Option<bool>,
+        min_amount_out_wei: Option<String>,
+        recipient: Option<String>,
+    },
+}
 
-        service_stakes.push(ServiceStakeInfo {
-            service_id: stake.node_id.clone(),
-            service_name: format!("Node {}", &stake.node_id[..10]),
-            staked_wei: stake.staked_amount,
-            staked_usd,
-            pending_rewards_wei: stake.pending_rewards,
-            stake_token: stake.staking_token,
-            min_stake_wei: "1000000000000000000000".to_string(), // 1000 JEJU minimum
-        });
+/// Result of a retried write, shaped like the original command's
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RetriedWrite {
+    Stake(StakeResult),
+    Claim(ClaimResult),
+}
+
+/// How long a write refused for lack of a signer stays retryable. Older
+/// intents are dropped rather than sent at fees and prices nobody looked at.
+const PENDING_WRITE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// The last write refused because the wallet had no signer, kept so it can
+/// be sent unchanged once the wallet is reconnected
+#[derive(Default)]
+pub struct PendingWrite {
+    intent: parking_lot::Mutex<Option<(Instant, WriteIntent)>>,
+}
+
+impl PendingWrite {
+    fn hold(&self, intent: WriteIntent) {
+        *self.intent.lock() = Some((Instant::now(), intent));
     }
 
-    Ok(StakingInfo {
-        total_staked_wei: total_staked.to_string(),
-        total_staked_usd,
-        staked_by_service: service_stakes,
-        pending_rewards_wei: total_pending.to_string(),
-        pending_rewards_usd: (total_pending as f64) / 1e18,
-        can_unstake: total_staked > 0,
-        unstake_cooldown_seconds: 7 * 24 * 60 * 60, // 7 days
-        auto_claim_enabled: inner.config.earnings.auto_claim,
-        next_auto_claim_timestamp: None,
-    })
+    /// Remove the held intent, unless it is older than `ttl`
+    fn take(&self, ttl: Duration) -> Option<WriteIntent> {
+        self.intent
+            .lock()
+            .take()
+            .filter(|(held_at, _)| held_at.elapsed() < ttl)
+            .map(|(_, intent)| intent)
+    }
 }
 
-#[tauri::command]
-pub async fn stake(
-    state: State<'_, AppState>,
-    request: StakeRequest,
-) -> Result<StakeResult, String> {
-    let inner = state.inner.read().await;
+/// Write operations currently running, keyed by (action, service), so a
+/// double-clicked button can't broadcast the same transaction twice
+#[derive(Default)]
+pub struct InFlightWrites {
+    active: parking_lot::Mutex<HashSet<(String, String)>>,
+}
 
-    let _wallet_manager = inner
-        .wallet_manager
-        .as_ref()
-        .ok_or("Wallet not connected")?;
+impl InFlightWrites {
+    /// Claim the (action, service) slot, failing if it is already taken
+    fn begin(&self, action: &str, service_id: &str) -> Result<InFlightWrite<'_>, StakingError> {
+        let key = (action.to_string(), service_id.to_string());
+        if !self.active.lock().insert(key.clone()) {
+            return Err(StakingError::OperationInFlight {
+                action: key.0,
+                service_id: key.1,
+            });
+        }
 
-    // Verify contract client
-    if inner.contract_client.is_none() {
-        return Err("Contract client not initialized".to_string());
+        Ok(InFlightWrite { writes: self, key })
     }
+}
 
-    // Staking requires a signed transaction
-    Err(format!(
-        "To stake {} wei to service {}: Use the wallet interface to sign the staking transaction. \
-         Token: {}",
-        request.amount_wei,
-        request.service_id,
-        request.token_address.unwrap_or_else(|| "JEJU".to_string())
-    ))
+/// Frees its slot in `InFlightWrites` when dropped, however the write ended
+struct InFlightWrite<'a> {
+    writes: &'a InFlightWrites,
+    key: (String, String),
 }
 
-#[tauri::command]
-pub async fn unstake(
-    state: State<'_, AppState>,
-    request: UnstakeRequest,
-) -> Result<StakeResult, String> {
-    let inner = state.inner.read().await;
+impl Drop for InFlightWrite<'_> {
+    fn drop(&mut self) {
+        self.writes.active.lock().remove(&self.key);
+    }
+}
 
-    let _wallet_manager = inner
-        .wallet_manager
-        .as_ref()
-        .ok_or("Wallet not connected")?;
+/// Current state of a previously submitted transaction, looked up on demand
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionStatus {
+    pub status: TransactionState,
+    /// Blocks on top of (and including) the one it was mined in; 0 until mined
+    pub confirmations: u64,
+    pub block_number: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionState {
+    /// Known to the node but not yet mined
+    Pending,
+    Success,
+    Reverted,
+    /// The node has no record of it: never broadcast, dropped from the
+    /// mempool, or pruned from the node's history
+    NotFound,
+}
 
-    // Verify contract client
-    if inner.contract_client.is_none() {
-        return Err("Contract client not initialized".to_string());
+/// Recent `get_staking_info` results per wallet, so dashboard polling doesn't
+/// re-read every contract each time
+#[derive(Default)]
+pub struct StakingInfoCache {
+    entries: parking_lot::Mutex<HashMap<Address, (Instant, StakingInfo)>>,
+}
+
+impl StakingInfoCache {
+    fn get(&self, operator: Address, ttl: Duration) -> Option<StakingInfo> {
+        self.entries
+            .lock()
+            .get(&operator)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < ttl)
+            .map(|(_, info)| info.clone())
     }
 
-    // Unstaking requires a signed transaction
-    Err(format!(
-        "To unstake {} wei from service {}: Use the wallet interface to sign the unstake transaction.",
-        request.amount_wei, request.service_id
-    ))
+    fn insert(&self, operator: Address, info: StakingInfo) {
+        self.entries.lock().insert(operator, (Instant::now(), info));
+    }
+
+    /// Drop every cached entry, e.g. after a transaction changed stake data
+    pub fn invalidate(&self) {
+        self.entries.lock().clear();
+    }
 }
 
-#[tauri::command]
-pub async fn claim_rewards(
-    state: State<'_, AppState>,
-    service_id: Option<String>,
-) -> Result<ClaimResult, String> {
-    let inner = state.inner.read().await;
+/// How long contract parameters are reused before being read again
+const STAKING_PARAMETERS_TTL: Duration = Duration::from_secs(3600);
 
-    let _wallet_manager = inner
-        .wallet_manager
-        .as_ref()
-        .ok_or("Wallet not connected")?;
+/// Parameters read from the compute staking contract; `None` for any the
+/// contract doesn't expose, in which case the configured value applies
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StakingParameters {
+    /// Rewards paid per staked ETH per second, in wei
+    pub reward_rate_wei: Option<String>,
+    pub min_stake_wei: Option<String>,
+    pub unstake_cooldown_secs: Option<u64>,
+    /// Penalty for unstaking before the cooldown ends, in basis points
+    pub early_unstake_penalty_bps: Option<u64>,
+}
+
+/// `StakingParameters` per staking contract, which change rarely enough to
+/// cache for `STAKING_PARAMETERS_TTL`
+#[derive(Default)]
+pub struct StakingParametersCache {
+    entries: parking_lot::Mutex<HashMap<Address, (Instant, StakingParameters)>>,
+}
 
-    // Verify contract client
-    if inner.contract_client.is_none() {
-        return Err("Contract client not initialized".to_string());
+impl StakingParametersCache {
+    fn get(&self, contract: Address) -> Option<StakingParameters> {
+        self.entries
+            .lock()
+            .get(&contract)
+            .filter(|(fetched_at, _)| fetched_at.elapsed() < STAKING_PARAMETERS_TTL)
+            .map(|(_, parameters)| parameters.clone())
     }
 
-    // Claiming requires a signed transaction
-    match service_id {
-        Some(id) => Err(format!(
-            "To claim rewards from service {}: Use the wallet interface to sign the claim transaction.",
-            id
-        )),
-        None => Err(
-            "To claim all rewards: Use the wallet interface to sign the claim transaction."
-                .to_string(),
-        ),
+    fn insert(&self, contract: Address, parameters: StakingParameters) {
+        self.entries
+            .lock()
+            .insert(contract, (Instant::now(), parameters));
     }
 }
 
-#[tauri::command]
-pub async fn enable_auto_claim(
-    state: State<'_, AppState>,
-    enabled: bool,
-    threshold_wei: Option<String>,
-    interval_hours: Option<u32>,
-) -> Result<(), String> {
-    let mut inner = state.inner.write().await;
+/// How long a previewed action's gas estimate is reused, so previews can be
+/// requested on every keystroke
+const SIMULATION_GAS_TTL: Duration = Duration::from_secs(30);
+
+/// Sender, contract and function selector of an estimated call
+type GasEstimateKey = (Address, Address, [u8; 4]);
 
-    inner.config.earnings.auto_claim = enabled;
+/// Gas cost estimates for `simulate_staking_action`, per sender, contract and
+/// function. The amount barely changes gas use, so it isn't part of the key.
+#[derive(Default)]
+pub struct SimulationGasCache {
+    entries: parking_lot::Mutex<HashMap<GasEstimateKey, (Instant, U256)>>,
+}
 
-    if let Some(threshold) = threshold_wei {
-        inner.config.earnings.auto_claim_threshold_wei = threshold;
+impl SimulationGasCache {
+    fn get(&self, key: GasEstimateKey) -> Option<U256> {
+        self.entries
+            .lock()
+            .get(&key)
+            .filter(|(estimated_at, _)| estimated_at.elapsed() < SIMULATION_GAS_TTL)
+            .map(|(_, gas_cost)| *gas_cost)
     }
 
-    if let Some(interval) = interval_hours {
-        inner.config.earnings.auto_claim_interval_hours = interval;
+    fn insert(&self, key: GasEstimateKey, gas_cost: U256) {
+        self.entries.lock().insert(key, (Instant::now(), gas_cost));
     }
+}
+
+/// ERC20 `decimals()` per token, which never changes once deployed
+#[derive(Default)]
+pub struct TokenDecimalsCache {
+    entries: parking_lot::Mutex<HashMap<Address, u8>>,
+}
 
-    inner.config.save().map_err(|e| e.to_string())?;
+impl TokenDecimalsCache {
+    fn get(&self, token: Address) -> Option<u8> {
+        self.entries.lock().get(&token).copied()
+    }
 
-    Ok(())
+    fn insert(&self, token: Address, decimals: u8) {
+        self.entries.lock().insert(token, decimals);
+    }
 }
 
-#[tauri::command]
-pub async fn get_pending_rewards(
-    state: State<'_, AppState>,
-) -> Result<Vec<ServiceStakeInfo>, String> {
-    let inner = state.inner.read().await;
+/// Lifecycle update for one transaction; concurrent operations are told apart
+/// by `tx_hash`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxStatusEvent {
+    pub tx_hash: String,
+    pub status: TxStatus,
+    pub block_number: Option<u64>,
+    pub confirmations: u64,
+    pub required_confirmations: u64,
+    pub error: Option<String>,
+}
 
-    // Get contract client and wallet
-    let contract_client = match inner.contract_client.as_ref() {
-        Some(client) => client,
-        None => return Ok(vec![]),
-    };
+/// Node performance as tracked by the node staking manager
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeMetrics {
+    pub uptime_seconds: u64,
+    pub requests_served: u64,
+    /// Unix time the node registered
+    pub registered_at: u64,
+    pub rpc_url: String,
+    pub region: String,
+}
 
-    let wallet = match inner.wallet_manager.as_ref() {
-        Some(w) => w,
-        None => return Ok(vec![]),
-    };
+/// Funds the connected wallet has available to stake
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletBalance {
+    pub balance_wei: String,
+    pub balance_usd: f64,
+    /// "ETH" or the ERC20 token address
+    pub token: String,
+    /// True when the price lookup failed and `balance_usd` fell back to 0.0
+    pub prices_stale: bool,
+}
 
-    let wallet_info = match wallet.get_info() {
-        Some(info) => info,
-        None => return Ok(vec![]),
-    };
+/// An ERC20 a staking contract accepts, with the wallet's holdings of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakeTokenInfo {
+    pub token_address: String,
+    /// Empty when the token doesn't implement `symbol()`
+    pub symbol: String,
+    pub decimals: u8,
+    /// Service whose contract takes stakes in this token
+    pub service_id: String,
+    /// Staking contract the allowance is granted to
+    pub spender: String,
+    pub balance_wei: String,
+    pub allowance_wei: String,
+}
 
-    let operator =
-        Address::from_str(&wallet_info.address).map_err(|e| format!("Invalid address: {}", e))?;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasEstimate {
+    pub gas_limit: u64,
+    pub max_fee_per_gas: String,
+    pub estimated_cost_wei: String,
+}
 
-    // Query staking contracts for pending rewards
-    let stakes = contract_client
-        .get_staking_info(operator)
-        .await
-        .unwrap_or_default();
+impl GasEstimate {
+    fn cost(&self) -> U256 {
+        U256::from_str(&self.estimated_cost_wei).unwrap_or_default()
+    }
 
-    let mut result = Vec::new();
-    for stake in stakes {
-        let pending: u128 = stake.pending_rewards.parse().unwrap_or(0);
-        if pending > 0 {
-            result.push(ServiceStakeInfo {
-                service_id: stake.node_id.clone(),
-                service_name: format!("Node {}", &stake.node_id[..10]),
-                staked_wei: stake.staked_amount,
-                staked_usd: stake.staked_value_usd.parse().unwrap_or(0.0) / 1e18,
-                pending_rewards_wei: stake.pending_rewards,
-                stake_token: stake.staking_token,
-                min_stake_wei: "1000000000000000000000".to_string(),
-            });
+    /// This estimate with `gas` more gas, priced at the same max fee
+    fn plus_gas(self, gas: u64) -> Self {
+        let max_fee = U256::from_str(&self.max_fee_per_gas).unwrap_or_default();
+        let gas_limit = self.gas_limit.saturating_add(gas);
+        Self {
+            gas_limit,
+            estimated_cost_wei: U256::from(gas_limit).saturating_mul(max_fee).to_string(),
+            ..self
         }
     }
+}
 
-    Ok(result)
+/// Projected effect of a stake on the staker's `StakingInfo`, previewed
+/// without broadcasting. Reward projections assume the reward rate holds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakingInfoDiff {
+    /// Amount the stake adds, resolved from `amount_usd` when that was given
+    pub amount_wei: String,
+    pub amount_usd: f64,
+    /// Whether the stake adds to an existing position rather than opening one
+    pub top_up: bool,
+    pub staked_by_token_before: Vec<TokenTotal>,
+    pub staked_by_token_after: Vec<TokenTotal>,
+    pub total_staked_usd_before: f64,
+    pub total_staked_usd_after: f64,
+    /// Current compute stake APR; rewards accrue per staked token, so the
+    /// stake doesn't change it
+    pub apr: Option<f64>,
+    /// Compute rewards per day before and after the stake. `None` while the
+    /// stake is too new to measure a rate and the contract doesn't expose one.
+    pub daily_rewards_wei_before: Option<String>,
+    pub daily_rewards_wei_after: Option<String>,
+    pub daily_rewards_usd_after: Option<f64>,
+    pub gas_cost_wei: Option<String>,
+    pub gas_cost_usd: Option<f64>,
+    /// Why gas couldn't be estimated, e.g. a token stake not yet approved
+    pub gas_error: Option<String>,
+    pub prices_stale: bool,
+}
+
+/// Current gas prices and what a typical claim would cost at them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasConditions {
+    /// `None` on chains without EIP-1559
+    pub base_fee_per_gas: Option<String>,
+    pub priority_fee_per_gas: Option<String>,
+    /// Base fee plus priority fee, or the legacy gas price
+    pub effective_gas_price: String,
+    pub claim_gas_limit: u64,
+    pub claim_cost_wei: String,
+    pub claim_cost_usd: f64,
+    pub prices_stale: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimResult {
+    pub service_id: String,
+    pub success: bool,
+    pub tx_hash: Option<String>,
+    pub amount_claimed_wei: String,
+    pub error: Option<String>,
+    /// Where the rewards were paid, when not to the connected wallet
+    pub recipient: Option<String>,
+    /// ENS name of `recipient`, when it has one
+    pub recipient_name: Option<String>,
+    /// Gas paid for the claim; `None` if it wasn't mined
+    pub gas_cost_wei: Option<String>,
+    /// `None` when the ETH price is unavailable
+    pub gas_cost_usd: Option<f64>,
+    /// Everything the claim transaction logged; empty unless it was confirmed
+    pub events: Vec<DecodedEvent>,
+    /// Why nothing was sent, when `claim_if_worthwhile` judged the rewards
+    /// not worth the gas
+    pub skip_reason: Option<String>,
+}
+
+/// Staking command failures, serialized as `{ "kind": ..., "message": ... }`
+/// so the frontend can branch on `kind` and localize
+#[derive(Debug, Clone, thiserror::Error, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum StakingError {
+    #[error("Wallet not connected")]
+    WalletNotConnected,
+
+    #[error("Wallet not initialized")]
+    WalletNotInitialized,
+
+    /// A wallet is connected by address only, so it can be read but not sign
+    #[error("Wallet is watch-only; unlock or import its keys to sign transactions")]
+    WatchOnlyWallet,
+
+    #[error("{0}")]
+    InvalidAmount(String),
+
+    #[error("{0}")]
+    InvalidAddress(String),
+
+    /// `token` is "ETH" for the native balance (stake plus gas) or the ERC20 address
+    #[error("Insufficient {token} balance: need {required_wei} wei but only {available_wei} wei available")]
+    InsufficientBalance {
+        token: String,
+        required_wei: String,
+        available_wei: String,
+    },
+
+    /// Gas is paid in `token` on this chain, and the wallet holds too little
+    /// of it; the stake itself may well be covered
+    #[error("Insufficient {token} for gas: need {required} but only {available} available")]
+    InsufficientGasToken {
+        token: String,
+        required: String,
+        available: String,
+    },
+
+    #[error("No active stake")]
+    NoActiveStake,
+
+    #[error("Already staking {staked_wei} wei; use increase_stake to add to it")]
+    AlreadyStaked { staked_wei: String },
+
+    #[error("Connected wallet is not a registered node operator")]
+    NodeNotRegistered,
+
+    #[error("Invalid node registration: {0}")]
+    InvalidRegistration(String),
+
+    #[error("Transaction deadline {deadline_unix} passed at {now}; not broadcasting")]
+    DeadlineExceeded { deadline_unix: u64, now: u64 },
+
+    #[error("Unstake cooldown active: {remaining_seconds} seconds remaining")]
+    CooldownActive { remaining_seconds: u64 },
+
+    #[error("A {action} for {service_id} is already in progress")]
+    OperationInFlight { action: String, service_id: String },
+
+    #[error("{0}")]
+    ContractNotConfigured(String),
+
+    #[error("Unknown service: {0}")]
+    UnknownService(String),
+
+    #[error("Connected to chain {actual} but expected {expected}")]
+    WrongChain { actual: u64, expected: u64 },
+
+    #[error("{0}")]
+    RpcError(String),
+
+    /// The endpoint has pruned the state a historical read asked for
+    #[error("State at block {block} isn't available from this RPC endpoint; historical queries need an archive node")]
+    HistoricalStateUnavailable { block: u64 },
+
+    #[error("Gas price of {current} wei is above the {ceiling} wei ceiling")]
+    GasPriceTooHigh { current: String, ceiling: String },
+
+    #[error("{0}")]
+    TransactionReverted(String),
+
+    #[error("Claim would return {actual_wei} wei, below the minimum of {min_wei} wei")]
+    BelowMinimumOutput { min_wei: String, actual_wei: String },
+
+    #[error("{0}")]
+    SigningRejected(String),
+
+    #[error("{0}")]
+    InvalidPermitDeadline(String),
+
+    #[error("{0}")]
+    InvalidTransaction(String),
+
+    #[error("{0}")]
+    Config(String),
+
+    #[error("Staking writes are disabled in settings")]
+    WritesDisabled,
+
+    #[error("No staking write is waiting for the wallet to reconnect")]
+    NoPendingWrite,
+
+    #[error("Staking is temporarily paused by the contract; try again later")]
+    ContractPaused,
+
+    #[error("The app is shutting down")]
+    ShuttingDown,
+
+    /// No fresh enough price to convert a USD amount
+    #[error("{0}")]
+    PriceUnavailable(String),
+}
+
+/// Stakes and rewards of `address`, or the connected wallet. With `at_block`
+/// they are read as of that block, which beyond the most recent blocks needs
+/// an archive-capable RPC endpoint.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn get_staking_info(
+    state: State<'_, AppState>,
+    force_refresh: Option<bool>,
+    address: Option<String>,
+    at_block: Option<u64>,
+) -> Result<StakingInfo, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    let operator = query_address(&inner, address.as_deref()).await?;
+    if let Some(block) = at_block {
+        ensure_state_available(&inner, operator, block).await?;
+    }
+    staking_info(
+        &inner,
+        &AlloyChain::at_block(&inner, at_block),
+        operator,
+        force_refresh.unwrap_or(false),
+    )
+    .await
+}
+
+/// Stakes and pending rewards of `operator` across every staking contract on
+/// the configured chain, served from the cache unless `force_refresh`.
+/// Reads pinned to a past block skip the cache; their USD values still use
+/// current prices.
+async fn staking_info(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    operator: Address,
+    force_refresh: bool,
+) -> Result<StakingInfo, StakingError> {
+    let cache_ttl = Duration::from_secs(inner.config.staking.info_cache_ttl_secs);
+    let historical = chain.at_block().is_some();
+    if !force_refresh && !historical {
+        if let Some(info) = inner.staking_info_cache.get(operator, cache_ttl) {
+            return Ok(info);
+        }
+    }
+
+    // Any staking contract may be absent on a chain, but not all of them
+    let chain_id = inner.config.network.chain_id;
+    let compute_staking_address = inner.compute_staking_address(chain_id).ok();
+    let node_staking_address = inner.node_staking_address(chain_id).ok();
+    let registered_services = registered_service_contracts(inner);
+    if compute_staking_address.is_none()
+        && node_staking_address.is_none()
+        && registered_services.is_empty()
+    {
+        return Err(StakingError::ContractNotConfigured(format!(
+            "No staking contracts configured for chain {}",
+            chain_id
+        )));
+    }
+
+    // Query the legacy staking contracts alongside the direct reads below
+    let paused = async {
+        match compute_staking_address {
+            Some(contract) => chain.paused(contract).await,
+            None => Ok(None),
+        }
+    };
+    let (stakes, reads, paused) = tokio::join!(
+        chain.legacy_stakes(operator),
+        chain.stake_reads(
+            operator,
+            compute_staking_address,
+            node_staking_address,
+            &registered_services,
+        ),
+        paused,
+    );
+    let mut reads = reads?;
+    let stakes = reads
+        .note(
+            "legacy",
+            "getOperatorNodes",
+            stakes.map_err(|e| e.to_string()),
+        )
+        .unwrap_or_default();
+    let is_paused = reads
+        .note("compute", "paused", paused.map_err(|e| e.to_string()))
+        .flatten()
+        .unwrap_or(false);
+
+    // Aggregate stake info
+    let mut total_staked_usd: f64 = 0.0;
+    let mut total_pending: u128 = 0;
+    let mut service_stakes = Vec::new();
+    let mut prices_stale = false;
+    let mut compute_positions = Vec::new();
+    let mut rates = None;
+    let cooldown_secs = unstake_cooldown_secs(inner).await;
+
+    for stake in stakes {
+        let staked_usd = wei_to_tokens(U256::from_str(&stake.staked_value_usd).unwrap_or_default());
+        let pending: u128 = stake.pending_rewards.parse().unwrap_or(0);
+
+        total_staked_usd += staked_usd;
+        total_pending += pending;
+
+        service_stakes.push(ServiceStakeInfo {
+            service_id: stake.node_id.clone(),
+            service_name: format!("Node {}", &stake.node_id[..10]),
+            staked_wei: stake.staked_amount,
+            staked_usd,
+            pending_rewards_wei: stake.pending_rewards,
+            stake_token: stake.staking_token,
+            stake_kind: None,
+            min_stake_wei: "1000000000000000000000".to_string(), // 1000 JEJU minimum
+            rpc_url: None,
+            region: None,
+            requests_served: None,
+            positions: Vec::new(),
+        });
+    }
+
+    // The compute staking contract holds the provider stake
+    if let Some(stake_result) = reads.compute_stake {
+        let pending = reads.compute_pending;
+        // Elapsed time is measured to now, which doesn't fit a past block
+        if !historical {
+            rates = annualized_rates(
+                stake_result.amount,
+                pending,
+                stake_elapsed_secs(stake_result.stakedAt),
+            );
+        }
+
+        if stake_result.amount > U256::ZERO || pending > U256::ZERO {
+            total_pending += pending.saturating_to::<u128>();
+
+            let min_stake = min_compute_stake(inner).await?.to_string();
+            let stake_token = match compute_staking_address {
+                Some(address) => chain.stake_token(address, operator).await?,
+                None => None,
+            };
+            let mut compute_stake = compute_stake_info(
+                &stake_result,
+                stake_token,
+                pending,
+                &min_stake,
+                cooldown_secs,
+            );
+            compute_positions = compute_stake.positions.clone();
+            compute_stake.staked_usd = usd_or_stale(
+                token_usd(
+                    inner,
+                    chain,
+                    stake_result.amount,
+                    &compute_stake.stake_token,
+                )
+                .await,
+                &mut prices_stale,
+            );
+            total_staked_usd += compute_stake.staked_usd;
+            service_stakes.push(compute_stake);
+        }
+    }
+
+    // The node staking manager holds the operator's node stake
+    if let Some(node_info) = reads.node_info {
+        let pending = reads.node_pending;
+        if node_info.stakeAmount > U256::ZERO || pending > U256::ZERO {
+            total_pending += pending.saturating_to::<u128>();
+
+            let staked = node_info.stakeAmount;
+            let mut node_stake = node_stake_info(node_info, pending);
+            node_stake.staked_usd = usd_or_stale(
+                token_usd(inner, chain, staked, &node_stake.stake_token).await,
+                &mut prices_stale,
+            );
+            total_staked_usd += node_stake.staked_usd;
+            service_stakes.push(node_stake);
+        }
+    }
+
+    // Registered services share the compute staking interface
+    for (service, stake_result, pending) in reads.registered {
+        if stake_result.amount.is_zero() && pending.is_zero() {
+            continue;
+        }
+
+        total_pending += pending.saturating_to::<u128>();
+
+        let staked_usd = usd_or_stale(
+            token_usd(inner, chain, stake_result.amount, &service.stake_token).await,
+            &mut prices_stale,
+        );
+        total_staked_usd += staked_usd;
+
+        service_stakes.push(ServiceStakeInfo {
+            service_id: service.id,
+            service_name: service.name,
+            staked_wei: stake_result.amount.to_string(),
+            staked_usd,
+            pending_rewards_wei: pending.to_string(),
+            stake_token: service.stake_token,
+            stake_kind: Some(StakeKind::from(stake_result.stakeType)),
+            min_stake_wei: service.min_stake_wei,
+            rpc_url: None,
+            region: None,
+            requests_served: None,
+            positions: stake_positions(&stake_result, cooldown_secs),
+        });
+    }
+
+    // Rewards are valued in the token each service is staked in
+    let mut pending_rewards_usd = 0.0;
+    for service in &service_stakes {
+        let pending = U256::from_str(&service.pending_rewards_wei).unwrap_or_default();
+        pending_rewards_usd += usd_or_stale(
+            token_usd(inner, chain, pending, &service.stake_token).await,
+            &mut prices_stale,
+        );
+    }
+
+    let staked_by_token = token_totals(&service_stakes);
+    let info = StakingInfo {
+        total_staked_wei: native_total(&staked_by_token),
+        staked_by_token,
+        total_staked_usd,
+        is_paused,
+        staked_by_service: service_stakes,
+        pending_rewards_wei: total_pending.to_string(),
+        pending_rewards_usd,
+        // Unstaking from the app only covers compute, so these summarize its positions
+        can_unstake: compute_positions
+            .iter()
+            .any(|position| position.can_unstake),
+        unstake_cooldown_seconds: compute_positions
+            .iter()
+            .map(|position| position.cooldown_remaining_seconds)
+            .min()
+            .unwrap_or(0),
+        auto_claim_enabled: inner.config.earnings.auto_claim,
+        next_auto_claim_timestamp: auto_claim::next_claim_timestamp(
+            &inner.config.earnings,
+            chrono::Utc::now().timestamp() as u64,
+        ),
+        prices_stale,
+        apr: rates.map(|(apr, _)| apr),
+        apy: rates.map(|(_, apy)| apy),
+        data_complete: reads.errors.is_empty(),
+        read_errors: reads.errors,
+    };
+    // Don't keep serving a failed read once the problem is fixed
+    if info.data_complete && !historical {
+        inner.staking_info_cache.insert(operator, info.clone());
+    }
+
+    Ok(info)
+}
+
+/// Most addresses accepted by `get_aggregate_staking_info` in one call
+const MAX_AGGREGATE_ADDRESSES: usize = 200;
+
+/// Addresses read at once by `get_aggregate_staking_info`, to keep a large
+/// fleet from flooding the RPC endpoint
+const AGGREGATE_CONCURRENCY: usize = 4;
+
+/// Combined staking picture for several addresses or ENS names. Addresses
+/// that are invalid or can't be read are flagged in their entry rather than
+/// failing the whole call.
+#[tauri::command]
+#[tracing::instrument(
+    level = "debug",
+    skip_all,
+    fields(wallet, chain_id, endpoint, addresses = addresses.len())
+)]
+pub async fn get_aggregate_staking_info(
+    state: State<'_, AppState>,
+    addresses: Vec<String>,
+    force_refresh: Option<bool>,
+) -> Result<AggregateStakingInfo, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    if addresses.len() > MAX_AGGREGATE_ADDRESSES {
+        return Err(StakingError::InvalidAddress(format!(
+            "At most {} addresses can be aggregated at once",
+            MAX_AGGREGATE_ADDRESSES
+        )));
+    }
+
+    Ok(aggregate_staking_info(
+        &inner,
+        &AlloyChain::new(&inner),
+        &addresses,
+        force_refresh.unwrap_or(false),
+    )
+    .await)
+}
+
+async fn aggregate_staking_info(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    addresses: &[String],
+    force_refresh: bool,
+) -> AggregateStakingInfo {
+    // Resolve everything first so the same address given twice, possibly
+    // once by ENS name, isn't counted twice
+    let resolved: Vec<_> = stream::iter(addresses.to_vec())
+        .map(|address| async move { query_address(inner, Some(&address)).await })
+        .buffered(AGGREGATE_CONCURRENCY)
+        .collect()
+        .await;
+    let mut seen = HashSet::new();
+    let resolved: Vec<_> = resolved
+        .into_iter()
+        .map(|result| match result {
+            Ok(address) if !seen.insert(address) => {
+                Err(format!("Duplicate of an earlier entry for {:?}", address))
+            }
+            result => result.map_err(|e| e.to_string()),
+        })
+        .collect();
+
+    let fetched: Vec<_> = stream::iter(resolved)
+        .map(|address| async move {
+            match address {
+                Ok(address) => staking_info(inner, chain, address, force_refresh)
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(error) => Err(error),
+            }
+        })
+        .buffered(AGGREGATE_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut entries = Vec::with_capacity(addresses.len());
+    for (address, result) in addresses.iter().zip(fetched) {
+        if let Err(error) = &result {
+            tracing::warn!("Skipping {} in aggregate: {}", address, error);
+        }
+        let (info, error) = match result {
+            Ok(info) => (Some(info), None),
+            Err(error) => (None, Some(error)),
+        };
+        entries.push(AddressStakingInfo {
+            address: address.clone(),
+            info,
+            error,
+        });
+    }
+
+    let infos = || entries.iter().filter_map(|entry| entry.info.as_ref());
+    AggregateStakingInfo {
+        staked_by_token: sum_by_token(infos().flat_map(|info| {
+            info.staked_by_token.iter().map(|total| {
+                (
+                    total.token.as_str(),
+                    U256::from_str(&total.amount_wei).unwrap_or_default(),
+                    total.amount_usd,
+                )
+            })
+        })),
+        total_staked_usd: infos().map(|info| info.total_staked_usd).sum(),
+        pending_rewards_usd: infos().map(|info| info.pending_rewards_usd).sum(),
+        prices_stale: infos().any(|info| info.prices_stale),
+        data_complete: entries
+            .iter()
+            .all(|entry| entry.info.as_ref().is_some_and(|info| info.data_complete)),
+        addresses: entries,
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        service_id = %request.service_id,
+        amount_wei = %request.amount_wei,
+        tx_hash,
+        outcome,
+    )
+)]
+pub async fn stake(
+    state: State<'_, AppState>,
+    request: StakeRequest,
+) -> Result<StakeResult, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+    let result = run_stake(&inner, &AlloyChain::new(&inner), &request, false).await;
+    hold_for_reconnect(&inner, result, || WriteIntent::Stake(request))
+}
+
+/// Add to the connected wallet's existing stake. Fails with `NoActiveStake`
+/// when there is nothing to top up; use `stake` for a first deposit. The
+/// minimum stake doesn't apply to the added amount, and `new_stake_wei` is
+/// the total re-read after confirmation.
+#[tauri::command]
+#[tracing::instrument(
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        service_id = %request.service_id,
+        amount_wei = %request.amount_wei,
+        tx_hash,
+        outcome,
+    )
+)]
+pub async fn increase_stake(
+    state: State<'_, AppState>,
+    request: StakeRequest,
+) -> Result<StakeResult, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+    let result = run_stake(&inner, &AlloyChain::new(&inner), &request, true).await;
+    hold_for_reconnect(&inner, result, || WriteIntent::IncreaseStake(request))
+}
+
+/// Send the write last refused because the wallet had no signer, with the
+/// parameters it was first called with. Call after reconnecting the wallet
+/// in response to `staking://wallet-reconnect-needed`.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(wallet, chain_id, endpoint, tx_hash, outcome))]
+pub async fn retry_pending_write(state: State<'_, AppState>) -> Result<RetriedWrite, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+
+    let intent = inner
+        .pending_write
+        .take(PENDING_WRITE_TTL)
+        .ok_or(StakingError::NoPendingWrite)?;
+    tracing::info!("Retrying write held for wallet reconnect: {:?}", intent);
+    retry_write(&inner, &AlloyChain::new(&inner), intent).await
+}
+
+async fn retry_write(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    intent: WriteIntent,
+) -> Result<RetriedWrite, StakingError> {
+    match intent {
+        WriteIntent::Stake(request) => {
+            let result = run_stake(inner, chain, &request, false).await;
+            hold_for_reconnect(inner, result, || WriteIntent::Stake(request))
+                .map(RetriedWrite::Stake)
+        }
+        WriteIntent::IncreaseStake(request) => {
+            let result = run_stake(inner, chain, &request, true).await;
+            hold_for_reconnect(inner, result, || WriteIntent::IncreaseStake(request))
+                .map(RetriedWrite::Stake)
+        }
+        WriteIntent::Unstake(request) => {
+            let result = run_unstake(inner, chain, &request).await;
+            hold_for_reconnect(inner, result, || WriteIntent::Unstake(request))
+                .map(RetriedWrite::Stake)
+        }
+        WriteIntent::ClaimRewards {
+            service_id,
+            fees,
+            simulate,
+            min_amount_out_wei,
+            recipient,
+        } => {
+            let result = run_claim(
+                inner,
+                service_id.as_deref(),
+                fees.as_ref(),
+                simulate,
+                min_amount_out_wei.as_deref(),
+                recipient.as_deref(),
+            )
+            .await;
+            hold_for_reconnect(inner, result, || WriteIntent::ClaimRewards {
+                service_id,
+                fees,
+                simulate,
+                min_amount_out_wei,
+                recipient,
+            })
+            .map(RetriedWrite::Claim)
+        }
+    }
+}
+
+/// When `result` failed for lack of a signer, keep the write for
+/// `retry_pending_write` and ask the frontend to reconnect the wallet.
+/// Watch-only wallets are left alone, since reconnecting won't give them keys.
+fn hold_for_reconnect<T>(
+    inner: &AppStateInner,
+    result: Result<T, StakingError>,
+    intent: impl FnOnce() -> WriteIntent,
+) -> Result<T, StakingError> {
+    let Err(error @ (StakingError::WalletNotConnected | StakingError::WalletNotInitialized)) =
+        &result
+    else {
+        return result;
+    };
+
+    let intent = intent();
+    tracing::info!("Holding write until the wallet reconnects: {}", error);
+    inner.pending_write.hold(intent.clone());
+    if let Some(handle) = inner.app_handle.as_ref() {
+        let event = WalletReconnectEvent {
+            reason: error.to_string(),
+            intent,
+        };
+        if let Err(e) = handle.emit(WALLET_RECONNECT_EVENT, event) {
+            tracing::warn!("Failed to emit wallet reconnect request: {}", e);
+        }
+    }
+    result
+}
+
+/// Stake unless the same stake is already in flight, logging the attempt
+async fn run_stake(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    request: &StakeRequest,
+    top_up: bool,
+) -> Result<StakeResult, StakingError> {
+    // Shares the key with fresh stakes so the two can't race each other
+    let _in_flight = inner.in_flight_writes.begin("stake", &request.service_id)?;
+
+    let result = submit_stake(inner, chain, request, top_up).await;
+    let action = if top_up {
+        StakingAction::IncreaseStake
+    } else {
+        StakingAction::Stake
+    };
+    let amount_wei = result
+        .as_ref()
+        .ok()
+        .and_then(|result| result.amount_wei.clone())
+        .unwrap_or_else(|| request.amount_wei.clone());
+    log_stake_attempt(inner, action, &request.service_id, &amount_wei, &result);
+    result
+}
+
+/// Send a stake, or with `top_up` add to an existing one
+async fn submit_stake(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    request: &StakeRequest,
+    top_up: bool,
+) -> Result<StakeResult, StakingError> {
+    let (amount, usd_price) = stake_amount(inner, chain, request).await?;
+    // The minimum applies to the position, which a top-up already meets
+    let min_stake = if top_up {
+        U256::ZERO
+    } else {
+        min_compute_stake(inner).await?
+    };
+    check_stake_amount(amount, min_stake, request.token_address.is_none())?;
+    let beneficiary = parse_beneficiary(inner, request).await?;
+    if beneficiary.is_some() && request.token_address.is_some() {
+        return Err(StakingError::InvalidAddress(
+            "Staking for a beneficiary is only supported with native ETH".to_string(),
+        ));
+    }
+    if beneficiary.is_some() && top_up {
+        return Err(StakingError::InvalidAddress(
+            "Only the connected wallet's own stake can be increased".to_string(),
+        ));
+    }
+    let now = chrono::Utc::now().timestamp() as u64;
+    request.fees.check_deadline(now)?;
+    // The contract only takes permits when opening a stake
+    let permit_deadline = match (request.use_permit, top_up) {
+        (true, false) => Some(permit_deadline(
+            request.permit_deadline.or_else(|| {
+                // A permit outliving the transaction would only widen its window
+                request
+                    .fees
+                    .deadline_unix
+                    .map(|deadline| deadline.min(now + PERMIT_VALIDITY.as_secs()))
+            }),
+            now,
+        )?),
+        (true, true) => {
+            tracing::info!("Permits only apply to new stakes; approving the top-up instead");
+            None
+        }
+        (false, _) => None,
+    };
+
+    let operator = wallet_signer(inner)?.address();
+    let staker = beneficiary.unwrap_or(operator);
+
+    let compute_staking_address = inner
+        .compute_staking_address(inner.config.network.chain_id)
+        .map_err(StakingError::ContractNotConfigured)?;
+    check_chain_id(inner, chain.chain_id().await?)?;
+    ensure_not_paused(chain, compute_staking_address).await?;
+
+    let check_balance = request.check_balance.unwrap_or(true);
+    let simulate = request.simulate.unwrap_or(true);
+
+    // The contract keeps a single position per staker: the stake methods only
+    // open one and the increase methods only add to one, so check which
+    // applies here instead of sending a transaction that would revert
+    let existing = chain.get_stake(compute_staking_address, staker).await?;
+    if top_up {
+        check_existing_stake(existing.amount)?;
+    } else {
+        check_no_existing_stake(existing.amount)?;
+    }
+
+    let mut approval_tx_hash = None;
+    let mut approval_gas_cost = None;
+    let submitted = match (request.token_address.as_deref(), beneficiary) {
+        // The wallet pays; a contract without the delegated variant fails simulation
+        (None, Some(beneficiary)) => {
+            let tx = call_tx(
+                operator,
+                compute_staking_address,
+                IComputeStaking::stakeAsProviderForCall { beneficiary },
+                amount,
+            );
+
+            if check_balance {
+                ensure_funds_cover(inner, chain, &tx, amount).await?;
+            }
+
+            chain.send(tx, &request.fees, simulate).await?
+        }
+        (None, None) => {
+            let tx = if top_up {
+                call_tx(
+                    operator,
+                    compute_staking_address,
+                    IComputeStaking::increaseStakeCall {},
+                    amount,
+                )
+            } else {
+                call_tx(
+                    operator,
+                    compute_staking_address,
+                    IComputeStaking::stakeAsProviderCall {},
+                    amount,
+                )
+            };
+
+            if check_balance {
+                ensure_funds_cover(inner, chain, &tx, amount).await?;
+            }
+
+            chain.send(tx, &request.fees, simulate).await?
+        }
+        (Some(token_address), _) => {
+            let token_address = parse_address(token_address).map_err(|e| {
+                StakingError::InvalidAddress(format!("Invalid token address: {}", e))
+            })?;
+
+            if check_balance {
+                let token_balance = chain.token_balance(token_address, operator).await?;
+                if amount > token_balance {
+                    return Err(StakingError::InsufficientBalance {
+                        token: format!("{:?}", token_address),
+                        required_wei: amount.to_string(),
+                        available_wei: token_balance.to_string(),
+                    });
+                }
+            }
+
+            let permit = match permit_deadline {
+                Some(deadline) => {
+                    signed_permit(
+                        inner,
+                        chain,
+                        token_address,
+                        compute_staking_address,
+                        amount,
+                        deadline,
+                    )
+                    .await?
+                }
+                None => None,
+            };
+
+            // Only approve when neither a permit nor the existing allowance covers the stake
+            if permit.is_none() {
+                let allowance = chain
+                    .allowance(token_address, operator, compute_staking_address)
+                    .await?;
+
+                if allowance < amount {
+                    let approve = call_tx(
+                        operator,
+                        token_address,
+                        IERC20::approveCall {
+                            spender: compute_staking_address,
+                            amount,
+                        },
+                        U256::ZERO,
+                    );
+
+                    if check_balance {
+                        ensure_funds_cover(inner, chain, &approve, U256::ZERO).await?;
+                    }
+
+                    let approval = chain.send(approve, &request.fees, simulate).await?;
+                    approval_tx_hash = Some(approval.tx_hash.to_string());
+                    approval_gas_cost = approval.gas_cost;
+
+                    if let Some(error) = approval.error {
+                        let (gas_cost_wei, gas_cost_usd) =
+                            gas_cost_fields(inner, approval.gas_cost).await;
+                        return Ok(StakeResult {
+                            success: false,
+                            tx_hash: None,
+                            approval_tx_hash,
+                            new_stake_wei: "0".to_string(),
+                            amount_wei: Some(amount.to_string()),
+                            usd_price,
+                            error: Some(format!("Token approval failed: {}", error)),
+                            beneficiary: Some(format!("{:?}", staker)),
+                            beneficiary_name: ens::lookup_address(inner, staker).await,
+                            gas_cost_wei,
+                            gas_cost_usd,
+                            events: Vec::new(),
+                        });
+                    }
+                }
+            }
+
+            let tx = if let Some((deadline, signature)) = permit {
+                call_tx(
+                    operator,
+                    compute_staking_address,
+                    IComputeStaking::stakeWithPermitCall {
+                        token: token_address,
+                        amount,
+                        deadline: U256::from(deadline),
+                        v: 27 + signature.v().y_parity_byte(),
+                        r: signature.r().into(),
+                        s: signature.s().into(),
+                    },
+                    U256::ZERO,
+                )
+            } else if top_up {
+                call_tx(
+                    operator,
+                    compute_staking_address,
+                    IComputeStaking::increaseTokenStakeCall {
+                        token: token_address,
+                        amount,
+                    },
+                    U256::ZERO,
+                )
+            } else {
+                call_tx(
+                    operator,
+                    compute_staking_address,
+                    IComputeStaking::stakeTokenAsProviderCall {
+                        token: token_address,
+                        amount,
+                    },
+                    U256::ZERO,
+                )
+            };
+
+            if check_balance {
+                ensure_funds_cover(inner, chain, &tx, U256::ZERO).await?;
+            }
+
+            chain.send(tx, &request.fees, simulate).await?
+        }
+    };
+
+    let new_stake = if submitted.error.is_none() {
+        chain
+            .get_stake(compute_staking_address, staker)
+            .await
+            .map(|r| r.amount)
+            .unwrap_or(amount)
+    } else {
+        U256::ZERO
+    };
+
+    let gas_cost = [approval_gas_cost, submitted.gas_cost]
+        .into_iter()
+        .flatten()
+        .reduce(|total, cost| total + cost);
+    let (gas_cost_wei, gas_cost_usd) = gas_cost_fields(inner, gas_cost).await;
+    Ok(StakeResult {
+        success: submitted.error.is_none(),
+        tx_hash: Some(submitted.tx_hash.to_string()),
+        approval_tx_hash,
+        new_stake_wei: new_stake.to_string(),
+        amount_wei: Some(amount.to_string()),
+        usd_price,
+        error: submitted.error,
+        beneficiary: Some(format!("{:?}", staker)),
+        beneficiary_name: ens::lookup_address(inner, staker).await,
+        gas_cost_wei,
+        gas_cost_usd,
+        events: decode_receipt_events(&submitted.logs),
+    })
+}
+
+/// Permits expire this long after signing unless the request sets a deadline
+const PERMIT_VALIDITY: Duration = Duration::from_secs(20 * 60);
+
+/// Shortest time left on a requested deadline, so the stake can still be mined
+const PERMIT_MIN_VALIDITY: Duration = Duration::from_secs(60);
+
+/// Longest a permit may stay valid; a leaked one is usable until it expires
+const PERMIT_MAX_VALIDITY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// EIP-712 domain versions tried when matching a token's domain separator
+const PERMIT_DOMAIN_VERSIONS: [&str; 2] = ["1", "2"];
+
+/// Permit deadline for a stake, checking a requested one is neither about to
+/// pass nor unreasonably far off
+fn permit_deadline(requested: Option<u64>, now: u64) -> Result<u64, StakingError> {
+    let Some(deadline) = requested else {
+        return Ok(now + PERMIT_VALIDITY.as_secs());
+    };
+
+    if deadline < now + PERMIT_MIN_VALIDITY.as_secs() {
+        return Err(StakingError::InvalidPermitDeadline(format!(
+            "Permit deadline must be at least {} seconds from now",
+            PERMIT_MIN_VALIDITY.as_secs()
+        )));
+    }
+    if deadline > now + PERMIT_MAX_VALIDITY.as_secs() {
+        return Err(StakingError::InvalidPermitDeadline(format!(
+            "Permit deadline must be within {} hours from now",
+            PERMIT_MAX_VALIDITY.as_secs() / 3600
+        )));
+    }
+
+    Ok(deadline)
+}
+
+/// Permit support read from a token: its name and the owner's permit nonce,
+/// along with the domain separator signatures must match
+#[derive(Debug, Clone)]
+struct TokenPermit {
+    name: String,
+    nonce: U256,
+    domain_separator: B256,
+}
+
+/// The token's EIP-712 domain. Tokens only expose the separator, so rebuild
+/// the domain with each common version and keep the one that reproduces it.
+fn permit_domain(permit: &TokenPermit, chain_id: u64, token: Address) -> Option<Eip712Domain> {
+    PERMIT_DOMAIN_VERSIONS
+        .iter()
+        .map(|version| {
+            Eip712Domain::new(
+                Some(permit.name.clone().into()),
+                Some((*version).into()),
+                Some(U256::from(chain_id)),
+                Some(token),
+                None,
+            )
+        })
+        .find(|domain| domain.separator() == permit.domain_separator)
+}
+
+/// Sign a permit letting `spender` take `amount` of `token` from the
+/// connected wallet until `deadline`. `None` if the token doesn't support
+/// permits, so the caller can approve instead.
+async fn signed_permit(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    token: Address,
+    spender: Address,
+    amount: U256,
+    deadline: u64,
+) -> Result<Option<(u64, Signature)>, StakingError> {
+    let signer = wallet_signer(inner)?;
+    let owner = signer.address();
+
+    let Some(token_permit) = chain.permit_info(token, owner).await? else {
+        tracing::info!("{:?} doesn't support permits; approving instead", token);
+        return Ok(None);
+    };
+    let Some(domain) = permit_domain(&token_permit, inner.config.network.chain_id, token) else {
+        tracing::info!(
+            "Couldn't reproduce the permit domain of {:?}; approving instead",
+            token
+        );
+        return Ok(None);
+    };
+
+    let permit = Permit {
+        owner,
+        spender,
+        value: amount,
+        nonce: token_permit.nonce,
+        deadline: U256::from(deadline),
+    };
+    prompt_hardware_confirmation(inner);
+    let signature = signer
+        .sign_typed_data(&permit, &domain)
+        .await
+        .map_err(|e| signing_error("Failed to sign permit", &e))?;
+
+    Ok(Some((deadline, signature)))
+}
+
+/// EIP-5267 `eip712Domain()` of a staking contract that takes relayed
+/// stakes, with the staker's next authorization nonce
+#[derive(Debug, Clone)]
+struct AuthorizationInfo {
+    /// Bitmap of the domain fields in use, in EIP-5267 order
+    fields: u8,
+    name: String,
+    version: String,
+    chain_id: U256,
+    verifying_contract: Address,
+    salt: B256,
+    nonce: U256,
+}
+
+const DOMAIN_NAME: u8 = 1 << 0;
+const DOMAIN_VERSION: u8 = 1 << 1;
+const DOMAIN_CHAIN_ID: u8 = 1 << 2;
+const DOMAIN_VERIFYING_CONTRACT: u8 = 1 << 3;
+const DOMAIN_SALT: u8 = 1 << 4;
+
+/// The staking contract's signing domain, refusing one without a chain ID
+/// (the signature could be replayed on any chain running the contract) or
+/// for a different chain or contract than the one being staked on
+fn authorization_domain(
+    info: &AuthorizationInfo,
+    chain_id: u64,
+    contract: Address,
+) -> Result<Eip712Domain, StakingError> {
+    if info.fields & DOMAIN_CHAIN_ID == 0 {
+        return Err(StakingError::InvalidTransaction(
+            "The staking contract's signing domain has no chain ID".to_string(),
+        ));
+    }
+    if info.chain_id != U256::from(chain_id) {
+        return Err(StakingError::WrongChain {
+            actual: info.chain_id.saturating_to(),
+            expected: chain_id,
+        });
+    }
+    if info.fields & DOMAIN_VERIFYING_CONTRACT != 0 && info.verifying_contract != contract {
+        return Err(StakingError::InvalidTransaction(format!(
+            "The staking contract's signing domain names {:?} instead",
+            info.verifying_contract
+        )));
+    }
+
+    let has = |field: u8| info.fields & field != 0;
+    Ok(Eip712Domain::new(
+        has(DOMAIN_NAME).then(|| info.name.clone().into()),
+        has(DOMAIN_VERSION).then(|| info.version.clone().into()),
+        Some(info.chain_id),
+        has(DOMAIN_VERIFYING_CONTRACT).then_some(info.verifying_contract),
+        has(DOMAIN_SALT).then_some(info.salt),
+    ))
+}
+
+/// The request's beneficiary, which may be given as an ENS name
+async fn parse_beneficiary(
+    inner: &AppStateInner,
+    request: &StakeRequest,
+) -> Result<Option<Address>, StakingError> {
+    let Some(beneficiary) = request.beneficiary.as_deref().map(str::trim) else {
+        return Ok(None);
+    };
+    if ens::is_name(beneficiary) {
+        return resolve_ens_name(inner, beneficiary).await.map(Some);
+    }
+
+    parse_address(beneficiary)
+        .map(Some)
+        .map_err(|e| StakingError::InvalidAddress(format!("Invalid beneficiary address: {}", e)))
+}
+
+/// Build and sign a native ETH stake without broadcasting it, for signing on
+/// an air-gapped machine. Returns the raw signed transaction as hex for
+/// `broadcast_raw_tx`.
+///
+/// The transaction is bound to the wallet's next nonce: it becomes invalid as
+/// soon as any other transaction from the wallet is mined, so refuses to build
+/// while one is pending. Its fees are the ones current at build time, so
+/// broadcast it promptly.
+#[tauri::command]
+#[tracing::instrument(
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        service_id = %request.service_id,
+        amount_wei = %request.amount_wei,
+    )
+)]
+pub async fn build_signed_stake_tx(
+    state: State<'_, AppState>,
+    request: StakeRequest,
+) -> Result<String, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+
+    if request.token_address.is_some() {
+        return Err(StakingError::InvalidTransaction(
+            "Offline signing only supports native ETH stakes".to_string(),
+        ));
+    }
+    let (amount, _) = stake_amount(&inner, &AlloyChain::new(&inner), &request).await?;
+    let min_stake = min_compute_stake(&inner).await?;
+    check_stake_amount(amount, min_stake, true)?;
+    let beneficiary = parse_beneficiary(&inner, &request).await?;
+
+    let signer = wallet_signer(&inner)?;
+    let operator = signer.address();
+    let staker = beneficiary.unwrap_or(operator);
+
+    let compute_staking_address = inner
+        .compute_staking_address(inner.config.network.chain_id)
+        .map_err(StakingError::ContractNotConfigured)?;
+    ensure_expected_chain(&inner).await?;
+    ensure_not_paused(&AlloyChain::new(&inner), compute_staking_address).await?;
+    let chain_id = inner.chain_id().await.map_err(StakingError::RpcError)?;
+
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+    let existing = with_retry(inner.config.network.rpc_max_attempts, || async {
+        IComputeStaking::new(compute_staking_address, &*provider)
+            .getStake(staker)
+            .call()
+            .await
+    })
+    .await
+    .map_err(|e| call_error("Failed to read existing stake", &e))?;
+    check_no_existing_stake(existing.amount)?;
+
+    let input = match beneficiary {
+        Some(beneficiary) => IComputeStaking::stakeAsProviderForCall { beneficiary }.abi_encode(),
+        None => IComputeStaking::stakeAsProviderCall {}.abi_encode(),
+    };
+    let call = CallBuilder::new_raw(&*provider, input.into())
+        .to(compute_staking_address)
+        .value(amount)
+        .from(operator);
+    let call = request.fees.apply(call)?;
+
+    if request.check_balance.unwrap_or(true) {
+        let tx = stake_tx(
+            operator,
+            compute_staking_address,
+            None,
+            beneficiary,
+            amount,
+            false,
+        );
+        ensure_funds_cover(&inner, &AlloyChain::new(&inner), &tx, amount).await?;
+    }
+    if request.simulate.unwrap_or(true) {
+        call.call_raw()
+            .await
+            .map_err(|e| call_error("Transaction would revert", &e))?;
+    }
+
+    let latest_nonce = provider
+        .get_transaction_count(operator)
+        .latest()
+        .await
+        .map_err(|e| StakingError::RpcError(format!("Failed to get nonce: {}", e)))?;
+    let pending_nonce = provider
+        .get_transaction_count(operator)
+        .pending()
+        .await
+        .map_err(|e| StakingError::RpcError(format!("Failed to get nonce: {}", e)))?;
+    if pending_nonce > latest_nonce {
+        return Err(StakingError::InvalidTransaction(format!(
+            "Wallet has {} pending transaction(s); wait for them to be mined before signing",
+            pending_nonce - latest_nonce
+        )));
+    }
+
+    let gas = call
+        .estimate_gas()
+        .await
+        .map_err(|e| call_error("Failed to estimate gas", &e))?;
+    let mut tx = call.into_transaction_request();
+    if tx.max_fee_per_gas.is_none() || tx.max_priority_fee_per_gas.is_none() {
+        let estimate = provider
+            .estimate_eip1559_fees(None)
+            .await
+            .map_err(|e| StakingError::RpcError(format!("Failed to estimate fees: {}", e)))?;
+        let max_fee = *tx.max_fee_per_gas.get_or_insert(estimate.max_fee_per_gas);
+        tx.max_priority_fee_per_gas
+            .get_or_insert(estimate.max_priority_fee_per_gas.min(max_fee));
+    }
+    tx.nonce = Some(latest_nonce);
+    tx.chain_id = Some(chain_id);
+    tx.gas = Some(gas);
+
+    prompt_hardware_confirmation(&inner);
+    let signed = tx
+        .build(&EthereumWallet::from(signer.clone()))
+        .await
+        .map_err(|e| signing_error("Failed to sign transaction", &e))?;
+
+    tracing::info!(
+        "Signed stake transaction {} (nonce {}) for offline broadcast",
+        signed.tx_hash(),
+        latest_nonce
+    );
+    Ok(hex::encode_prefixed(signed.encoded_2718()))
+}
+
+/// EIP-712 signature over a stake authorization, with everything a relayer
+/// needs to submit it
+#[derive(Debug, Clone, Serialize)]
+pub struct TypedSignature {
+    /// 65-byte `r || s || v` signature as hex
+    pub signature: String,
+    pub signer: String,
+    /// The signed payload in the `eth_signTypedData_v4` format
+    pub typed_data: TypedData,
+    pub signing_hash: String,
+    pub nonce: String,
+    /// Unix time after which the contract rejects the authorization
+    pub deadline: u64,
+}
+
+/// Sign an authorization for a relayer to open a token stake for the
+/// connected wallet and pay its gas, so wallets without ETH can stake.
+/// Nothing is broadcast; the returned payload goes to the relayer.
+///
+/// The signature is bound to the compute staking contract, the chain and the
+/// wallet's next authorization nonce, and expires at `permit_deadline`
+/// (`PERMIT_VALIDITY` from now by default).
+#[tauri::command]
+#[tracing::instrument(
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        service_id = %request.service_id,
+        amount_wei = %request.amount_wei,
+    )
+)]
+pub async fn sign_stake_authorization(
+    state: State<'_, AppState>,
+    request: StakeRequest,
+) -> Result<TypedSignature, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+    authorize_stake(&inner, &AlloyChain::new(&inner), &request).await
+}
+
+async fn authorize_stake(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    request: &StakeRequest,
+) -> Result<TypedSignature, StakingError> {
+    // A relayer can't spend the staker's ETH, only tokens they authorize
+    let Some(token) = request.token_address.as_deref() else {
+        return Err(StakingError::InvalidTransaction(
+            "Relayed stakes only support tokens".to_string(),
+        ));
+    };
+    let token = parse_address(token)
+        .map_err(|e| StakingError::InvalidAddress(format!("Invalid token address: {}", e)))?;
+    if request.beneficiary.is_some() {
+        return Err(StakingError::InvalidAddress(
+            "Relayed stakes are always for the signing wallet".to_string(),
+        ));
+    }
+    let (amount, _) = stake_amount(inner, chain, request).await?;
+    check_stake_amount(amount, min_compute_stake(inner).await?, false)?;
+    let deadline = permit_deadline(
+        request.permit_deadline,
+        chrono::Utc::now().timestamp() as u64,
+    )?;
+
+    let signer = wallet_signer(inner)?;
+    let staker = signer.address();
+    let chain_id = inner.config.network.chain_id;
+    let compute_staking_address = inner
+        .compute_staking_address(chain_id)
+        .map_err(StakingError::ContractNotConfigured)?;
+    check_chain_id(inner, chain.chain_id().await?)?;
+    ensure_not_paused(chain, compute_staking_address).await?;
+    check_no_existing_stake(
+        chain
+            .get_stake(compute_staking_address, staker)
+            .await?
+            .amount,
+    )?;
+
+    let Some(info) = chain
+        .authorization_info(compute_staking_address, staker)
+        .await?
+    else {
+        return Err(StakingError::InvalidTransaction(
+            "The staking contract doesn't accept relayed stakes".to_string(),
+        ));
+    };
+    let domain = authorization_domain(&info, chain_id, compute_staking_address)?;
+    let authorization = StakeAuthorization {
+        staker,
+        token,
+        amount,
+        nonce: info.nonce,
+        deadline: U256::from(deadline),
+    };
+
+    prompt_hardware_confirmation(inner);
+    let signature = signer
+        .sign_typed_data(&authorization, &domain)
+        .await
+        .map_err(|e| signing_error("Failed to sign stake authorization", &e))?;
+
+    tracing::info!(
+        "Signed stake authorization (nonce {}) for relaying",
+        info.nonce
+    );
+    Ok(TypedSignature {
+        signature: hex::encode_prefixed(signature.as_bytes()),
+        signer: staker.to_string(),
+        signing_hash: authorization.eip712_signing_hash(&domain).to_string(),
+        typed_data: TypedData::from_struct(&authorization, Some(domain)),
+        nonce: info.nonce.to_string(),
+        deadline,
+    })
+}
+
+/// Broadcast a transaction signed elsewhere, e.g. by `build_signed_stake_tx`,
+/// and wait for it to confirm. The transaction must be for the configured
+/// chain and its nonce must not have been used yet.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(wallet, chain_id, endpoint, tx_hash, outcome))]
+pub async fn broadcast_raw_tx(
+    state: State<'_, AppState>,
+    raw: String,
+) -> Result<StakeResult, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+
+    let raw = hex::decode(raw.trim())
+        .map_err(|e| StakingError::InvalidTransaction(format!("Invalid transaction hex: {}", e)))?;
+    let envelope = TxEnvelope::decode_2718(&mut raw.as_slice()).map_err(|e| {
+        StakingError::InvalidTransaction(format!("Invalid signed transaction: {}", e))
+    })?;
+
+    let network = &inner.config.network;
+    let expected = network.expected_chain_id.unwrap_or(network.chain_id);
+    match envelope.chain_id() {
+        Some(actual) if actual != expected => {
+            return Err(StakingError::WrongChain { actual, expected })
+        }
+        Some(_) => {}
+        None => {
+            return Err(StakingError::InvalidTransaction(
+                "Transaction isn't bound to a chain ID".to_string(),
+            ))
+        }
+    }
+    ensure_expected_chain(&inner).await?;
+
+    let from = envelope.recover_signer().map_err(|e| {
+        StakingError::InvalidTransaction(format!("Invalid transaction signature: {}", e))
+    })?;
+    let nonce = envelope.nonce();
+    let replay: TransactionRequest = envelope.into();
+    // The fee was fixed at signing, so hold it to the ceiling instead of the current price
+    if let Some(ceiling) = gas_price_ceiling(&inner)? {
+        check_signed_fee(&replay, ceiling)?;
+    }
+
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+    let next_nonce = provider
+        .get_transaction_count(from)
+        .latest()
+        .await
+        .map_err(|e| StakingError::RpcError(format!("Failed to get nonce: {}", e)))?;
+    if nonce < next_nonce {
+        return Err(StakingError::InvalidTransaction(format!(
+            "Nonce {} has already been used by {:?}; build and sign a new transaction",
+            nonce, from
+        )));
+    }
+
+    let pending = provider.send_raw_transaction(&raw).await.map_err(|e| {
+        call_error(
+            "Failed to send transaction",
+            &alloy::contract::Error::TransportError(e),
+        )
+    })?;
+    let submitted = confirm_sent(&inner, pending, replay.from(from)).await?;
+
+    let (gas_cost_wei, gas_cost_usd) = gas_cost_fields(&inner, submitted.gas_cost).await;
+    Ok(StakeResult {
+        success: submitted.error.is_none(),
+        tx_hash: Some(submitted.tx_hash.to_string()),
+        approval_tx_hash: None,
+        new_stake_wei: current_compute_stake(&inner, from).await.to_string(),
+        amount_wei: None,
+        usd_price: None,
+        error: submitted.error,
+        beneficiary: None,
+        beneficiary_name: None,
+        gas_cost_wei,
+        gas_cost_usd,
+        events: decode_receipt_events(&submitted.logs),
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(
+    level = "debug",
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        service_id = %request.service_id,
+        amount_wei = %request.amount_wei,
+    )
+)]
+pub async fn estimate_stake_gas(
+    state: State<'_, AppState>,
+    request: StakeRequest,
+) -> Result<GasEstimate, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    estimate_stake(&inner, &AlloyChain::new(&inner), &request).await
+}
+
+/// Gas a token stake is taken to need while it can't be estimated: until its
+/// approval is mined, or its permit signed, the call would revert
+const TOKEN_STAKE_GAS_FALLBACK: u64 = 200_000;
+
+/// Gas for every transaction `run_stake` would send for `request`: the
+/// stake, plus an approval when neither the allowance nor a permit covers
+/// a token stake
+async fn estimate_stake(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    request: &StakeRequest,
+) -> Result<GasEstimate, StakingError> {
+    let (amount, _) = stake_amount(inner, chain, request).await?;
+    let operator = wallet_address(inner)?;
+    let beneficiary = parse_beneficiary(inner, request).await?;
+    let token_address = request
+        .token_address
+        .as_deref()
+        .map(|token| {
+            parse_address(token)
+                .map_err(|e| StakingError::InvalidAddress(format!("Invalid token address: {}", e)))
+        })
+        .transpose()?;
+    if beneficiary.is_some() && token_address.is_some() {
+        return Err(StakingError::InvalidAddress(
+            "Staking for a beneficiary is only supported with native ETH".to_string(),
+        ));
+    }
+    let compute_staking_address = inner
+        .compute_staking_address(inner.config.network.chain_id)
+        .map_err(StakingError::ContractNotConfigured)?;
+
+    let staker = beneficiary.unwrap_or(operator);
+    let top_up = !chain
+        .get_stake(compute_staking_address, staker)
+        .await?
+        .amount
+        .is_zero();
+    let tx = stake_tx(
+        operator,
+        compute_staking_address,
+        token_address,
+        beneficiary,
+        amount,
+        top_up,
+    );
+    let Some(token) = token_address else {
+        return chain.estimate_gas_cost(&tx).await;
+    };
+    if chain
+        .allowance(token, operator, compute_staking_address)
+        .await?
+        >= amount
+    {
+        return chain.estimate_gas_cost(&tx).await;
+    }
+
+    let approval = chain
+        .estimate_gas_cost(&call_tx(
+            operator,
+            token,
+            IERC20::approveCall {
+                spender: compute_staking_address,
+                amount,
+            },
+            U256::ZERO,
+        ))
+        .await?;
+    let stake_gas = match chain.estimate_gas_cost(&tx).await {
+        Ok(estimate) => estimate.gas_limit,
+        Err(e) => {
+            tracing::debug!("Token stake can't be estimated before approval: {}", e);
+            TOKEN_STAKE_GAS_FALLBACK
+        }
+    };
+
+    // As in `submit_stake`, a permit replaces the approval on new stakes
+    let permit =
+        request.use_permit && !top_up && chain.permit_info(token, operator).await?.is_some();
+    if permit {
+        Ok(GasEstimate {
+            gas_limit: 0,
+            ..approval
+        }
+        .plus_gas(stake_gas))
+    } else {
+        Ok(approval.plus_gas(stake_gas))
+    }
+}
+
+/// The stake transaction `submit_stake` sends, minus any permit
+fn stake_tx(
+    operator: Address,
+    compute_staking_address: Address,
+    token_address: Option<Address>,
+    beneficiary: Option<Address>,
+    amount: U256,
+    top_up: bool,
+) -> TransactionRequest {
+    match (token_address, beneficiary, top_up) {
+        (Some(token), _, false) => call_tx(
+            operator,
+            compute_staking_address,
+            IComputeStaking::stakeTokenAsProviderCall { token, amount },
+            U256::ZERO,
+        ),
+        (Some(token), _, true) => call_tx(
+            operator,
+            compute_staking_address,
+            IComputeStaking::increaseTokenStakeCall { token, amount },
+            U256::ZERO,
+        ),
+        (None, Some(beneficiary), _) => call_tx(
+            operator,
+            compute_staking_address,
+            IComputeStaking::stakeAsProviderForCall { beneficiary },
+            amount,
+        ),
+        (None, None, false) => call_tx(
+            operator,
+            compute_staking_address,
+            IComputeStaking::stakeAsProviderCall {},
+            amount,
+        ),
+        (None, None, true) => call_tx(
+            operator,
+            compute_staking_address,
+            IComputeStaking::increaseStakeCall {},
+            amount,
+        ),
+    }
+}
+
+/// Preview what a stake would do to the wallet's staking totals and reward
+/// projection, and what it would cost in gas, without broadcasting anything.
+/// Stake data and gas estimates are served from caches, so this is cheap
+/// enough to call as the amount is being typed.
+#[tauri::command]
+#[tracing::instrument(
+    level = "debug",
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        service_id = %request.service_id,
+        amount_wei = %request.amount_wei,
+    )
+)]
+pub async fn simulate_staking_action(
+    state: State<'_, AppState>,
+    request: StakeRequest,
+) -> Result<StakingInfoDiff, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    simulate_stake(&inner, &AlloyChain::new(&inner), &request).await
+}
+
+async fn simulate_stake(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    request: &StakeRequest,
+) -> Result<StakingInfoDiff, StakingError> {
+    let (amount, _) = stake_amount(inner, chain, request).await?;
+    let operator = wallet_address(inner)?;
+    let beneficiary = parse_beneficiary(inner, request).await?;
+    let staker = beneficiary.unwrap_or(operator);
+    let token_address = request
+        .token_address
+        .as_deref()
+        .map(|token| {
+            parse_address(token)
+                .map_err(|e| StakingError::InvalidAddress(format!("Invalid token address: {}", e)))
+        })
+        .transpose()?;
+    let compute_staking_address = inner
+        .compute_staking_address(inner.config.network.chain_id)
+        .map_err(StakingError::ContractNotConfigured)?;
+
+    let before = staking_info(inner, chain, staker, false).await?;
+    let existing = before
+        .staked_by_service
+        .iter()
+        .find(|service| service.service_id == "compute")
+        .map(|service| U256::from_str(&service.staked_wei).unwrap_or_default())
+        .unwrap_or_default();
+    let top_up = !existing.is_zero();
+    if !top_up {
+        check_stake_amount(
+            amount,
+            min_compute_stake(inner).await?,
+            token_address.is_none(),
+        )?;
+    }
+
+    let mut prices_stale = before.prices_stale;
+    let token = token_address
+        .map(|token| format!("{:?}", token))
+        .unwrap_or_else(|| "ETH".to_string());
+    let amount_usd = usd_or_stale(
+        token_usd(inner, chain, amount, &token).await,
+        &mut prices_stale,
+    );
+
+    let mut staked_after = before.staked_by_service.clone();
+    match staked_after
+        .iter_mut()
+        .find(|service| service.service_id == "compute")
+    {
+        Some(compute) => {
+            compute.staked_wei = existing.saturating_add(amount).to_string();
+            compute.staked_usd += amount_usd;
+        }
+        None => {
+            let opened = IComputeStaking::getStakeReturn {
+                amount,
+                stakeType: 2,
+                stakedAt: U256::from(chrono::Utc::now().timestamp()),
+            };
+            let min_stake = min_compute_stake(inner).await?.to_string();
+            let mut compute = compute_stake_info(
+                &opened,
+                token_address,
+                U256::ZERO,
+                &min_stake,
+                unstake_cooldown_secs(inner).await,
+            );
+            compute.staked_usd = amount_usd;
+            staked_after.push(compute);
+        }
+    }
+
+    let reward_rate = staking_parameters(inner)
+        .await
+        .ok()
+        .and_then(|parameters| parameters.reward_rate_wei)
+        .and_then(|rate| U256::from_str(&rate).ok());
+    let daily_before = daily_rewards(existing, before.apr, reward_rate);
+    let daily_after = daily_rewards(existing.saturating_add(amount), before.apr, reward_rate);
+    let daily_rewards_usd_after = match daily_after {
+        Some(daily) => Some(usd_or_stale(
+            token_usd(inner, chain, daily, &token).await,
+            &mut prices_stale,
+        )),
+        None => None,
+    };
+
+    let tx = stake_tx(
+        operator,
+        compute_staking_address,
+        token_address,
+        beneficiary,
+        amount,
+        top_up,
+    );
+    let (gas_cost, gas_error) = match simulation_gas_cost(inner, chain, &tx).await {
+        Ok(gas_cost) => (Some(gas_cost), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let (gas_cost_wei, gas_cost_usd) = gas_cost_fields(inner, gas_cost).await;
+
+    Ok(StakingInfoDiff {
+        amount_wei: amount.to_string(),
+        amount_usd,
+        top_up,
+        staked_by_token_after: token_totals(&staked_after),
+        total_staked_usd_after: before.total_staked_usd + amount_usd,
+        staked_by_token_before: before.staked_by_token,
+        total_staked_usd_before: before.total_staked_usd,
+        apr: before.apr,
+        daily_rewards_wei_before: daily_before.map(|daily| daily.to_string()),
+        daily_rewards_wei_after: daily_after.map(|daily| daily.to_string()),
+        daily_rewards_usd_after,
+        gas_cost_wei,
+        gas_cost_usd,
+        gas_error,
+        prices_stale,
+    })
+}
+
+/// Gas cost of `tx`, reusing a recent estimate for the same sender, contract
+/// and function
+async fn simulation_gas_cost(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    tx: &TransactionRequest,
+) -> Result<U256, StakingError> {
+    let selector = tx
+        .input
+        .input()
+        .and_then(|input| input.get(..4))
+        .and_then(|selector| <[u8; 4]>::try_from(selector).ok())
+        .unwrap_or_default();
+    let key = (
+        tx.from.unwrap_or_default(),
+        tx.to.and_then(|to| to.to().copied()).unwrap_or_default(),
+        selector,
+    );
+    if let Some(gas_cost) = inner.simulation_gas_cache.get(key) {
+        return Ok(gas_cost);
+    }
+
+    let gas_cost = chain.estimate_gas_cost(tx).await?.cost();
+    inner.simulation_gas_cache.insert(key, gas_cost);
+    Ok(gas_cost)
+}
+
+/// Rewards `staked` earns per day at `apr`, or at the contract's
+/// `reward_rate` (per staked ETH per second) while no APR can be measured
+fn daily_rewards(staked: U256, apr: Option<f64>, reward_rate: Option<U256>) -> Option<U256> {
+    match (apr, reward_rate) {
+        (Some(apr), _) => Some(U256::from(
+            (staked.saturating_to::<u128>() as f64 * apr / 365.0) as u128,
+        )),
+        (None, Some(rate)) => Some(
+            staked
+                .saturating_mul(rate)
+                .saturating_mul(U256::from(SECONDS_PER_DAY))
+                / U256::from(WEI_PER_ETH),
+        ),
+        (None, None) => None,
+    }
+}
+
+/// Gas used by a typical `claimRewards`, for costing claims without a stake to estimate against
+const TYPICAL_CLAIM_GAS: u64 = 80_000;
+
+/// Current gas conditions, so the UI can warn when claiming would cost more
+/// than the rewards it pays out
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn get_gas_conditions(state: State<'_, AppState>) -> Result<GasConditions, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+
+    let base_fee = provider
+        .get_block_by_number(BlockNumberOrTag::Latest, false)
+        .await
+        .map_err(|e| StakingError::RpcError(format!("Failed to get latest block: {}", e)))?
+        .and_then(|block| block.header.base_fee_per_gas);
+
+    let (priority_fee, effective_gas_price) = match base_fee {
+        Some(base_fee) => {
+            let priority_fee = provider
+                .estimate_eip1559_fees(None)
+                .await
+                .map_err(|e| StakingError::RpcError(format!("Failed to estimate fees: {}", e)))?
+                .max_priority_fee_per_gas;
+            (Some(priority_fee), base_fee.saturating_add(priority_fee))
+        }
+        None => {
+            let gas_price = provider
+                .get_gas_price()
+                .await
+                .map_err(|e| StakingError::RpcError(format!("Failed to get gas price: {}", e)))?;
+            (None, gas_price)
+        }
+    };
+
+    let claim_cost = U256::from(TYPICAL_CLAIM_GAS) * U256::from(effective_gas_price);
+    let mut prices_stale = false;
+    let claim_cost_usd = usd_or_stale(
+        token_usd(
+            &inner,
+            &AlloyChain::new(&inner),
+            claim_cost,
+            &gas_currency(&inner)?,
+        )
+        .await,
+        &mut prices_stale,
+    );
+
+    Ok(GasConditions {
+        base_fee_per_gas: base_fee.map(|fee| fee.to_string()),
+        priority_fee_per_gas: priority_fee.map(|fee| fee.to_string()),
+        effective_gas_price: effective_gas_price.to_string(),
+        claim_gas_limit: TYPICAL_CLAIM_GAS,
+        claim_cost_wei: claim_cost.to_string(),
+        claim_cost_usd,
+        prices_stale,
+    })
+}
+
+/// Extra gas reserve held back by `max_stakeable_amount`, as a percent of the
+/// estimated stake cost, to absorb gas price movement before the stake is sent
+const GAS_RESERVE_BUFFER_PERCENT: u64 = 20;
+
+/// Largest amount that can be staked from the connected wallet: its balance
+/// of the stake token, minus a gas reserve when that is also what gas is paid
+/// in (native ETH, unless the chain has a gas token configured). The reserve
+/// is the estimated gas cost of the stake, or of the top-up when the wallet
+/// already has one, plus `GAS_RESERVE_BUFFER_PERCENT`. A native stake is 0
+/// when the balance can't cover a new stake's minimum.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn max_stakeable_amount(
+    state: State<'_, AppState>,
+    token_address: Option<String>,
+) -> Result<String, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    max_stakeable(&inner, &AlloyChain::new(&inner), token_address.as_deref())
+        .await
+        .map(|amount| amount.to_string())
+}
+
+async fn max_stakeable(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    token_address: Option<&str>,
+) -> Result<U256, StakingError> {
+    let operator = wallet_address(inner)?;
+    let gas_token = inner
+        .gas_token(inner.config.network.chain_id)
+        .map_err(StakingError::Config)?;
+
+    if let Some(token_address) = token_address {
+        let token = parse_address(token_address)
+            .map_err(|e| StakingError::InvalidAddress(format!("Invalid token address: {}", e)))?;
+        let balance = chain.token_balance(token, operator).await?;
+        if gas_token != Some(token) || balance.is_zero() {
+            return Ok(balance);
+        }
+        let request = StakeRequest {
+            service_id: "compute".to_string(),
+            amount_wei: balance.to_string(),
+            amount_usd: None,
+            token_address: Some(format!("{:?}", token)),
+            check_balance: Some(false),
+            simulate: Some(false),
+            beneficiary: None,
+            use_permit: false,
+            permit_deadline: None,
+            fees: FeeOverrides::default(),
+        };
+        let cost = estimate_stake(inner, chain, &request).await?.cost();
+        return Ok(balance.saturating_sub(gas_reserve(cost)));
+    }
+
+    let balance = chain.balance(operator).await?;
+    if gas_token.is_some() {
+        return Ok(balance);
+    }
+    let compute_staking_address = inner
+        .compute_staking_address(inner.config.network.chain_id)
+        .map_err(StakingError::ContractNotConfigured)?;
+    let top_up = !chain
+        .get_stake(compute_staking_address, operator)
+        .await?
+        .amount
+        .is_zero();
+
+    // Gas doesn't depend on the amount, and estimating with the full balance
+    // would fail for lack of funds to pay for the gas itself
+    let amount = if top_up {
+        U256::from(1)
+    } else {
+        min_compute_stake(inner).await?
+    };
+    if balance < amount {
+        return Ok(U256::ZERO);
+    }
+    let tx = stake_tx(
+        operator,
+        compute_staking_address,
+        None,
+        None,
+        amount,
+        top_up,
+    );
+    let cost = chain.estimate_gas_cost(&tx).await?.cost();
+
+    Ok(balance.saturating_sub(gas_reserve(cost)))
+}
+
+/// Gas to hold back for a transaction estimated to cost `cost`
+fn gas_reserve(cost: U256) -> U256 {
+    cost * U256::from(100 + GAS_RESERVE_BUFFER_PERCENT) / U256::from(100)
+}
+
+#[tauri::command]
+#[tracing::instrument(
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        service_id = %request.service_id,
+        amount_wei = %request.amount_wei,
+        tx_hash,
+        outcome,
+    )
+)]
+pub async fn unstake(
+    state: State<'_, AppState>,
+    request: UnstakeRequest,
+) -> Result<StakeResult, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+    let result = run_unstake(&inner, &AlloyChain::new(&inner), &request).await;
+    hold_for_reconnect(&inner, result, || WriteIntent::Unstake(request))
+}
+
+async fn run_unstake(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    request: &UnstakeRequest,
+) -> Result<StakeResult, StakingError> {
+    let _in_flight = inner
+        .in_flight_writes
+        .begin("unstake", &request.service_id)?;
+
+    let result = submit_unstake(inner, chain, request).await;
+    log_stake_attempt(
+        inner,
+        StakingAction::Unstake,
+        &request.service_id,
+        &request.amount_wei,
+        &result,
+    );
+    result
+}
+
+/// Claim outstanding rewards and then withdraw the whole stake. The staking
+/// contracts have no combined exit, so this is two transactions: the claim
+/// first, so rewards aren't stranded, then the unstake. Refuses up front
+/// during the unstake cooldown. If the unstake fails after the claim went
+/// through, both results are returned and the unstake's carries the error.
+/// Only compute stakes can be withdrawn, so other services are refused.
+#[tauri::command]
+#[tracing::instrument(
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        service_id = %service_id,
+        tx_hash,
+        outcome,
+    )
+)]
+pub async fn exit_stake(
+    state: State<'_, AppState>,
+    service_id: String,
+) -> Result<Vec<StakeResult>, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+    run_exit(&inner, &AlloyChain::new(&inner), &service_id).await
+}
+
+async fn run_exit(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    service_id: &str,
+) -> Result<Vec<StakeResult>, StakingError> {
+    // The claim and the unstake must both land on the compute staking contract
+    ensure_compute_service("exited", service_id)?;
+    let _claim_in_flight = inner.in_flight_writes.begin("claim", "compute")?;
+    let _unstake_in_flight = inner.in_flight_writes.begin("unstake", "compute")?;
+
+    let operator = wallet_signer(inner)?.address();
+    let compute_staking_address = inner
+        .compute_staking_address(inner.config.network.chain_id)
+        .map_err(StakingError::ContractNotConfigured)?;
+
+    let current_stake = chain.get_stake(compute_staking_address, operator).await?;
+    if current_stake.amount.is_zero() || current_stake.stakedAt.is_zero() {
+        return Err(StakingError::NoActiveStake);
+    }
+    let cooldown_remaining =
+        unstake_cooldown_remaining(current_stake.stakedAt, unstake_cooldown_secs(inner).await);
+    if cooldown_remaining > 0 {
+        return Err(StakingError::CooldownActive {
+            remaining_seconds: cooldown_remaining,
+        });
+    }
+
+    let fees = FeeOverrides::default();
+    let mut results = Vec::new();
+
+    if !chain
+        .pending_rewards(compute_staking_address, operator)
+        .await?
+        .is_zero()
+    {
+        let claim = claim_service_rewards(inner, service_id, &fees, true, None, None).await;
+        log_claim_attempt(inner, service_id, &claim);
+        let claim = claim?;
+        let claimed = claim.success;
+        results.push(StakeResult {
+            success: claim.success,
+            tx_hash: claim.tx_hash,
+            approval_tx_hash: None,
+            new_stake_wei: current_stake.amount.to_string(),
+            amount_wei: None,
+            usd_price: None,
+            error: claim.error,
+            beneficiary: None,
+            beneficiary_name: None,
+            gas_cost_wei: claim.gas_cost_wei,
+            gas_cost_usd: claim.gas_cost_usd,
+            events: claim.events,
+        });
+        if !claimed {
+            return Ok(results);
+        }
+    }
+
+    let request = UnstakeRequest {
+        service_id: service_id.to_string(),
+        amount_wei: current_stake.amount.to_string(),
+        simulate: None,
+        fees,
+    };
+    let unstake = submit_unstake(inner, chain, &request).await;
+    log_stake_attempt(
+        inner,
+        StakingAction::Unstake,
+        service_id,
+        &request.amount_wei,
+        &unstake,
+    );
+    // The claim already landed, so a failed unstake is a partial result, not an error
+    results.push(match unstake {
+        Ok(result) => result,
+        Err(e) if !results.is_empty() => StakeResult {
+            success: false,
+            tx_hash: None,
+            approval_tx_hash: None,
+            new_stake_wei: current_stake.amount.to_string(),
+            amount_wei: None,
+            usd_price: None,
+            error: Some(e.to_string()),
+            beneficiary: None,
+            beneficiary_name: None,
+            gas_cost_wei: None,
+            gas_cost_usd: None,
+            events: Vec::new(),
+        },
+        Err(e) => return Err(e),
+    });
+
+    Ok(results)
+}
+
+/// Refuse `service_id` unless it is compute staking, the only contract this
+/// app can withdraw from
+fn ensure_compute_service(action: &str, service_id: &str) -> Result<(), StakingError> {
+    if service_id != "compute" {
+        return Err(StakingError::InvalidTransaction(format!(
+            "Only compute stakes can be {}, not {}",
+            action, service_id
+        )));
+    }
+    Ok(())
+}
+
+async fn submit_unstake(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    request: &UnstakeRequest,
+) -> Result<StakeResult, StakingError> {
+    ensure_compute_service("unstaked", &request.service_id)?;
+    let operator = wallet_signer(inner)?.address();
+
+    let compute_staking_address = inner
+        .compute_staking_address(inner.config.network.chain_id)
+        .map_err(StakingError::ContractNotConfigured)?;
+    check_chain_id(inner, chain.chain_id().await?)?;
+    ensure_not_paused(chain, compute_staking_address).await?;
+
+    // Refuse up front rather than letting the contract revert during lockup
+    let current_stake = chain.get_stake(compute_staking_address, operator).await?;
+    if current_stake.amount.is_zero() || current_stake.stakedAt.is_zero() {
+        return Err(StakingError::NoActiveStake);
+    }
+    let cooldown_remaining =
+        unstake_cooldown_remaining(current_stake.stakedAt, unstake_cooldown_secs(inner).await);
+    if cooldown_remaining > 0 {
+        return Err(StakingError::CooldownActive {
+            remaining_seconds: cooldown_remaining,
+        });
+    }
+
+    let requested = parse_wei(&request.amount_wei)?;
+    if requested.is_zero() {
+        return Err(StakingError::InvalidAmount(
+            "Unstake amount must be greater than zero".to_string(),
+        ));
+    }
+    if requested > current_stake.amount {
+        return Err(StakingError::InvalidAmount(format!(
+            "Unstake amount {} wei exceeds current stake of {} wei",
+            requested, current_stake.amount
+        )));
+    }
+
+    tracing::info!(
+        "Unstaking from service {} (requested {} wei)",
+        request.service_id,
+        request.amount_wei
+    );
+
+    // unstake() withdraws everything; the amount overload is only for partial withdrawals
+    let simulate = request.simulate.unwrap_or(true);
+    let tx = if requested == current_stake.amount {
+        call_tx(
+            operator,
+            compute_staking_address,
+            IComputeStaking::unstake_0Call {},
+            U256::ZERO,
+        )
+    } else {
+        call_tx(
+            operator,
+            compute_staking_address,
+            IComputeStaking::unstake_1Call { amount: requested },
+            U256::ZERO,
+        )
+    };
+    let submitted = chain.send(tx, &request.fees, simulate).await?;
+
+    // Report the stake the contract actually holds now, not an assumed zero
+    let new_stake = if submitted.error.is_none() {
+        chain
+            .get_stake(compute_staking_address, operator)
+            .await
+            .map(|r| r.amount)
+            .unwrap_or(U256::ZERO)
+    } else {
+        current_stake.amount
+    };
+
+    let (gas_cost_wei, gas_cost_usd) = gas_cost_fields(inner, submitted.gas_cost).await;
+    Ok(StakeResult {
+        success: submitted.error.is_none(),
+        tx_hash: Some(submitted.tx_hash.to_string()),
+        approval_tx_hash: None,
+        new_stake_wei: new_stake.to_string(),
+        amount_wei: None,
+        usd_price: None,
+        error: submitted.error,
+        beneficiary: None,
+        beneficiary_name: None,
+        gas_cost_wei,
+        gas_cost_usd,
+        events: decode_receipt_events(&submitted.logs),
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn preview_unstake(state: State<'_, AppState>) -> Result<UnstakePreview, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    let operator = wallet_address(&inner)?;
+
+    let compute_staking_address = inner
+        .compute_staking_address(inner.config.network.chain_id)
+        .map_err(StakingError::ContractNotConfigured)?;
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+    let compute_staking = IComputeStaking::new(compute_staking_address, &*provider);
+    let attempts = inner.config.network.rpc_max_attempts;
+
+    let current_stake = with_retry(attempts, || async {
+        compute_staking.getStake(operator).call().await
+    })
+    .await
+    .map_err(|e| StakingError::RpcError(format!("Failed to get stake: {}", e)))?;
+    if current_stake.amount.is_zero() || current_stake.stakedAt.is_zero() {
+        return Err(StakingError::NoActiveStake);
+    }
+
+    let principal = current_stake.amount;
+    let penalty = match with_retry(attempts, || async {
+        compute_staking
+            .unstakePenalty(operator, principal)
+            .call()
+            .await
+    })
+    .await
+    {
+        Ok(result) => result._0.min(principal),
+        // Deployments without a penalty schedule revert on the lookup
+        Err(e) => match call_error("Failed to get unstake penalty", &e) {
+            StakingError::TransactionReverted(_) => U256::ZERO,
+            other => return Err(other),
+        },
+    };
+
+    Ok(UnstakePreview {
+        principal_wei: principal.to_string(),
+        penalty_wei: penalty.to_string(),
+        net_receive_wei: (principal - penalty).to_string(),
+        cooldown_remaining_seconds: unstake_cooldown_remaining(
+            current_stake.stakedAt,
+            unstake_cooldown_secs(&inner).await,
+        ),
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        service_id = ?service_id,
+        tx_hash,
+        outcome,
+    )
+)]
+pub async fn claim_rewards(
+    state: State<'_, AppState>,
+    service_id: Option<String>,
+    fees: Option<FeeOverrides>,
+    simulate: Option<bool>,
+    min_amount_out_wei: Option<String>,
+    recipient: Option<String>,
+) -> Result<ClaimResult, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+    let result = run_claim(
+        &inner,
+        service_id.as_deref(),
+        fees.as_ref(),
+        simulate,
+        min_amount_out_wei.as_deref(),
+        recipient.as_deref(),
+    )
+    .await;
+    hold_for_reconnect(&inner, result, || WriteIntent::ClaimRewards {
+        service_id,
+        fees,
+        simulate,
+        min_amount_out_wei,
+        recipient,
+    })
+}
+
+async fn run_claim(
+    inner: &AppStateInner,
+    service_id: Option<&str>,
+    fees: Option<&FeeOverrides>,
+    simulate: Option<bool>,
+    min_amount_out_wei: Option<&str>,
+    recipient: Option<&str>,
+) -> Result<ClaimResult, StakingError> {
+    let min_amount_out = min_amount_out_wei.map(parse_wei).transpose()?;
+    let recipient = match recipient.map(str::trim) {
+        Some(name) if ens::is_name(name) => Some(resolve_ens_name(inner, name).await?),
+        Some(recipient) => Some(parse_address(recipient).map_err(|e| {
+            StakingError::InvalidAddress(format!("Invalid recipient address: {}", e))
+        })?),
+        None => None,
+    };
+    if recipient == Some(Address::ZERO) {
+        return Err(StakingError::InvalidAddress(
+            "Rewards claimed to the zero address would be lost".to_string(),
+        ));
+    }
+
+    // Compute is the default for callers that predate per-service claims
+    let service_id = service_id.unwrap_or("compute");
+    let _in_flight = inner.in_flight_writes.begin("claim", service_id)?;
+    tracing::info!("Claiming rewards for service {}", service_id);
+
+    let result = claim_service_rewards(
+        inner,
+        service_id,
+        &fees.cloned().unwrap_or_default(),
+        simulate.unwrap_or(true),
+        min_amount_out,
+        recipient,
+    )
+    .await;
+    log_claim_attempt(inner, service_id, &result);
+    result
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(wallet, chain_id, endpoint, tx_hash, outcome))]
+pub async fn claim_all_rewards(
+    state: State<'_, AppState>,
+    fees: Option<FeeOverrides>,
+    simulate: Option<bool>,
+) -> Result<Vec<ClaimResult>, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+    let fees = fees.unwrap_or_default();
+    let simulate = simulate.unwrap_or(true);
+
+    wallet_signer(&inner)?;
+
+    let mut results = Vec::new();
+    for service in service_descriptors(&inner).await {
+        let service_id = service.id.as_str();
+        let _in_flight = match inner.in_flight_writes.begin("claim", service_id) {
+            Ok(in_flight) => in_flight,
+            Err(e) => {
+                results.push(failed_claim(service_id, e));
+                continue;
+            }
+        };
+
+        // A failure on one service shouldn't stop the others from being claimed
+        let result = match pending_rewards(&inner, service_id).await {
+            Err(StakingError::ContractNotConfigured(_)) => continue,
+            Ok(pending) if pending.is_zero() => continue,
+            Ok(_) => {
+                tracing::info!("Claiming rewards for service {}", service_id);
+                claim_service_rewards(&inner, service_id, &fees, simulate, None, None).await
+            }
+            Err(e) => Err(e),
+        };
+        log_claim_attempt(&inner, service_id, &result);
+
+        results.push(result.unwrap_or_else(|e| failed_claim(service_id, e)));
+    }
+
+    Ok(results)
+}
+
+fn failed_claim(service_id: &str, error: StakingError) -> ClaimResult {
+    ClaimResult {
+        service_id: service_id.to_string(),
+        success: false,
+        tx_hash: None,
+        amount_claimed_wei: "0".to_string(),
+        error: Some(error.to_string()),
+        recipient: None,
+        recipient_name: None,
+        gas_cost_wei: None,
+        gas_cost_usd: None,
+        events: Vec::new(),
+        skip_reason: None,
+    }
+}
+
+/// Claim a service's rewards only when they exceed the claim's estimated gas
+/// cost by at least `min_net_wei`, so dust isn't claimed at a net loss. When
+/// they don't, nothing is sent and the result carries a `skip_reason`.
+#[tauri::command]
+#[tracing::instrument(
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        service_id = %service_id,
+        tx_hash,
+        outcome,
+    )
+)]
+pub async fn claim_if_worthwhile(
+    state: State<'_, AppState>,
+    service_id: String,
+    min_net_wei: String,
+    fees: Option<FeeOverrides>,
+    simulate: Option<bool>,
+) -> Result<ClaimResult, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+    let min_net = parse_wei(&min_net_wei)?;
+    let fees = fees.unwrap_or_default();
+    wallet_signer(&inner)?;
+
+    let _in_flight = inner.in_flight_writes.begin("claim", &service_id)?;
+    let pending = pending_rewards(&inner, &service_id).await?;
+    // Estimating a claim with nothing to claim may revert, so skip that here
+    let gas_cost = if pending.is_zero() {
+        U256::ZERO
+    } else {
+        claim_gas_cost(&inner, &service_id, &fees).await?
+    };
+    if let Some(reason) = claim_skip_reason(pending, gas_cost, min_net) {
+        tracing::info!("Skipping claim for service {}: {}", service_id, reason);
+        return Ok(skipped_claim(&service_id, reason));
+    }
+
+    tracing::info!("Claiming rewards for service {}", service_id);
+    let result = claim_service_rewards(
+        &inner,
+        &service_id,
+        &fees,
+        simulate.unwrap_or(true),
+        None,
+        None,
+    )
+    .await;
+    log_claim_attempt(&inner, &service_id, &result);
+    result
+}
+
+fn skipped_claim(service_id: &str, reason: String) -> ClaimResult {
+    ClaimResult {
+        service_id: service_id.to_string(),
+        success: false,
+        tx_hash: None,
+        amount_claimed_wei: "0".to_string(),
+        error: None,
+        recipient: None,
+        recipient_name: None,
+        gas_cost_wei: None,
+        gas_cost_usd: None,
+        events: Vec::new(),
+        skip_reason: Some(reason),
+    }
+}
+
+/// Why a claim of `pending` rewards isn't worth sending when it costs
+/// `gas_cost` and must net at least `min_net`, both in the reward token
+fn claim_skip_reason(pending: U256, gas_cost: U256, min_net: U256) -> Option<String> {
+    if pending.is_zero() {
+        return Some("No rewards to claim".to_string());
+    }
+    if pending >= gas_cost.saturating_add(min_net) {
+        return None;
+    }
+    Some(format!(
+        "Pending rewards of {} wei minus an estimated {} wei of gas would net less than the minimum of {} wei",
+        pending, gas_cost, min_net
+    ))
+}
+
+/// Estimated gas cost of claiming a service's rewards, at the overridden max
+/// fee when one is given, expressed in the service's reward token so it can
+/// be weighed against the rewards
+async fn claim_gas_cost(
+    inner: &AppStateInner,
+    service_id: &str,
+    fees: &FeeOverrides,
+) -> Result<U256, StakingError> {
+    let estimate = estimate_claim_gas(inner, service_id).await?;
+    let gas_cost = match parse_fee("max_fee_per_gas", fees.max_fee_per_gas.as_deref())? {
+        Some(max_fee) => U256::from(estimate.gas_limit) * U256::from(max_fee),
+        None => U256::from_str(&estimate.estimated_cost_wei).unwrap_or_default(),
+    };
+
+    let chain = AlloyChain::new(inner);
+    let reward_token = reward_token(inner, &chain, service_id).await?;
+    convert_by_value(
+        inner,
+        &chain,
+        gas_cost,
+        &gas_currency(inner)?,
+        &reward_token,
+    )
+    .await
+}
+
+/// Token gas is paid in on the active chain: the configured gas token's
+/// address, or "ETH" for the native asset
+fn gas_currency(inner: &AppStateInner) -> Result<String, StakingError> {
+    Ok(inner
+        .gas_token(inner.config.network.chain_id)
+        .map_err(StakingError::Config)?
+        .map_or_else(|| "ETH".to_string(), |token| format!("{:?}", token)))
+}
+
+/// `amount` of token `from` re-expressed by USD value in token `to`, each in
+/// its smallest unit. Tokens are named as in `token_usd`.
+async fn convert_by_value(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    amount: U256,
+    from: &str,
+    to: &str,
+) -> Result<U256, StakingError> {
+    if from.eq_ignore_ascii_case(to) {
+        return Ok(amount);
+    }
+
+    let oracle = &inner.price_oracle;
+    let from_price = oracle
+        .usd_price(from)
+        .await
+        .map_err(StakingError::PriceUnavailable)?;
+    let to_price = oracle
+        .usd_price(to)
+        .await
+        .map_err(StakingError::PriceUnavailable)?;
+    convert_units(
+        amount,
+        (token_decimals(chain, from).await?, from_price),
+        (token_decimals(chain, to).await?, to_price),
+    )
+}
+
+/// `amount` of a token with the given (decimals, USD price) as an amount of
+/// another
+fn convert_units(amount: U256, from: (u8, f64), to: (u8, f64)) -> Result<U256, StakingError> {
+    let usd = crate::price::units_to_usd(amount, from.0, from.1)
+        .map_err(StakingError::PriceUnavailable)?;
+    usd_to_units(usd, to.1, to.0)
+}
+
+/// Gas estimate for claiming a service's rewards from the connected wallet
+async fn estimate_claim_gas(
+    inner: &AppStateInner,
+    service_id: &str,
+) -> Result<GasEstimate, StakingError> {
+    let operator = wallet_address(inner)?;
+    let contract_address = service_contract(inner, service_id)?;
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+
+    if service_id == "node" {
+        let node_staking = INodeStakingManager::new(contract_address, &*provider);
+        estimate_gas_cost(&*provider, &node_staking.nodeClaimRewards().from(operator)).await
+    } else {
+        let compute_staking = IComputeStaking::new(contract_address, &*provider);
+        estimate_gas_cost(&*provider, &compute_staking.claimRewards().from(operator)).await
+    }
+}
+
+/// Token a service pays rewards in: "ETH" or an ERC20 address. The node
+/// staking manager names its reward token per operator, apart from the token
+/// staked; other services pay out in the token they take.
+async fn reward_token(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    service_id: &str,
+) -> Result<String, StakingError> {
+    if service_id == "node" {
+        let node_staking_address = service_contract(inner, service_id)?;
+        let reads = chain
+            .stake_reads(
+                wallet_address(inner)?,
+                None,
+                Some(node_staking_address),
+                &[],
+            )
+            .await?;
+        return match (reads.node_info, reads.errors.first()) {
+            (Some(node_info), _) => Ok(format!("{:?}", node_info.rewardToken)),
+            (None, error) => Err(StakingError::RpcError(format!(
+                "Failed to read the node reward token: {}",
+                error.map_or("no node info", |error| error.error.as_str())
+            ))),
+        };
+    }
+
+    Ok(service_descriptors(inner)
+        .await
+        .into_iter()
+        .find(|service| service.id == service_id)
+        .map(|service| service.stake_token)
+        .unwrap_or_else(|| "ETH".to_string()))
+}
+
+/// Highest gas price, in wei of the chain's gas currency, at which claiming a
+/// service's pending rewards (compute by default) still pays for its own
+/// gas: the rewards' value in that currency divided by the claim's estimated
+/// gas limit. Claiming is net-positive
+/// while the current max fee per gas is below it. "0" when there's nothing
+/// to claim.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn claim_break_even_gas_price(
+    state: State<'_, AppState>,
+    service_id: Option<String>,
+) -> Result<String, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    let service_id = service_id.as_deref().unwrap_or("compute");
+
+    let pending = pending_rewards(&inner, service_id).await?;
+    // Estimating a claim with nothing to claim may revert, so skip that here
+    if pending.is_zero() {
+        return Ok("0".to_string());
+    }
+    let gas_limit = estimate_claim_gas(&inner, service_id).await?.gas_limit;
+
+    let chain = AlloyChain::new(&inner);
+    let reward_token = reward_token(&inner, &chain, service_id).await?;
+    let pending_in_gas_currency = convert_by_value(
+        &inner,
+        &chain,
+        pending,
+        &reward_token,
+        &gas_currency(&inner)?,
+    )
+    .await?;
+
+    Ok(break_even_gas_price(pending_in_gas_currency, gas_limit).to_string())
+}
+
+/// Gas price at which `gas_limit` gas costs exactly `rewards`
+fn break_even_gas_price(rewards: U256, gas_limit: u64) -> U256 {
+    if gas_limit == 0 {
+        return U256::ZERO;
+    }
+    rewards / U256::from(gas_limit)
+}
+
+/// Account a read-only query is for: `address` when supplied, otherwise the
+/// `read_as` dev override or the connected wallet. Reads work for any address
+/// or ENS name; writes always act as the connected signer.
+async fn query_address(
+    inner: &AppStateInner,
+    address: Option<&str>,
+) -> Result<Address, StakingError> {
+    if let Some(address) = address.map(str::trim) {
+        if ens::is_name(address) {
+            return resolve_ens_name(inner, address).await;
+        }
+        return parse_address(address);
+    }
+    if let Some(read_as) = inner.dev_overrides.read_as {
+        return Ok(read_as);
+    }
+
+    wallet_address(inner)
+}
+
+/// Parse an address as users paste it: surrounding whitespace and a missing
+/// `0x` are fine, as is all-lowercase or all-uppercase hex. Mixed case must
+/// be a valid EIP-55 checksum, since a mismatch usually means a typo.
+pub(crate) fn parse_address(input: &str) -> Result<Address, StakingError> {
+    let trimmed = input.trim();
+    let hex_part = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed);
+
+    if hex_part.is_empty() {
+        return Err(StakingError::InvalidAddress("Address is empty".to_string()));
+    }
+    if !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(StakingError::InvalidAddress(format!(
+            "Address {} contains characters other than hex digits",
+            trimmed
+        )));
+    }
+    if hex_part.len() != 40 {
+        return Err(StakingError::InvalidAddress(format!(
+            "Address {} has {} hex digits; addresses have 40",
+            trimmed,
+            hex_part.len()
+        )));
+    }
+
+    let has_upper = hex_part.chars().any(|c| c.is_ascii_uppercase());
+    let has_lower = hex_part.chars().any(|c| c.is_ascii_lowercase());
+    if has_upper && has_lower {
+        return Address::parse_checksummed(format!("0x{}", hex_part), None).map_err(|_| {
+            StakingError::InvalidAddress(format!(
+                "Address {} has an invalid checksum; check it for typos",
+                trimmed
+            ))
+        });
+    }
+    Address::from_str(hex_part)
+        .map_err(|e| StakingError::InvalidAddress(format!("Invalid address {}: {}", trimmed, e)))
+}
+
+/// The connected wallet's signer, for writes
+fn wallet_signer(inner: &AppStateInner) -> Result<&WalletSigner, StakingError> {
+    let wallet = inner
+        .wallet_manager
+        .as_ref()
+        .ok_or(StakingError::WalletNotConnected)?;
+    match wallet.get_signer() {
+        Some(signer) => Ok(signer),
+        None if wallet.is_watch_only() => Err(StakingError::WatchOnlyWallet),
+        None => Err(StakingError::WalletNotInitialized),
+    }
+}
+
+/// The connected wallet's address, for reads; watch-only wallets have one too
+fn wallet_address(inner: &AppStateInner) -> Result<Address, StakingError> {
+    inner
+        .wallet_manager
+        .as_ref()
+        .ok_or(StakingError::WalletNotConnected)?
+        .address()
+        .ok_or(StakingError::WalletNotInitialized)
+}
+
+/// Address an ENS name points at, failing if it can't be resolved on this chain
+async fn resolve_ens_name(inner: &AppStateInner, name: &str) -> Result<Address, StakingError> {
+    ens::resolve_name(inner, name)
+        .await
+        .map_err(StakingError::InvalidAddress)?
+        .ok_or_else(|| {
+            StakingError::InvalidAddress(format!("ENS name {} has no address set", name))
+        })
+}
+
+/// Contract client for reads when no wallet has been connected yet
+async fn read_only_contract_client(inner: &AppStateInner) -> Result<ContractClient, StakingError> {
+    ContractClient::new(
+        &inner.rpc_urls(),
+        inner.config.network.chain_id,
+        inner.config.network.rpc_timeout(),
+    )
+    .await
+    .map_err(StakingError::RpcError)
+}
+
+/// Staking attempts made from this app, most recent first, including ones
+/// that failed before anything was broadcast
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn get_local_staking_log(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<StakingLogEntry>, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    inner
+        .staking_log
+        .entries(&inner.config.staking, limit)
+        .map_err(|e| StakingError::Config(format!("Failed to read staking log: {}", e)))
+}
+
+fn log_stake_attempt(
+    inner: &AppStateInner,
+    action: StakingAction,
+    service_id: &str,
+    amount_wei: &str,
+    result: &Result<StakeResult, StakingError>,
+) {
+    let (tx_hash, outcome, error) = match result {
+        Ok(r) if r.success => (r.tx_hash.clone(), StakingOutcome::Success, None),
+        Ok(r) => (r.tx_hash.clone(), StakingOutcome::Failed, r.error.clone()),
+        Err(e) => (None, StakingOutcome::NotSent, Some(e.to_string())),
+    };
+
+    inner.staking_log.record(
+        &inner.config.staking,
+        StakingLogEntry {
+            timestamp: chrono::Utc::now().timestamp(),
+            action,
+            service_id: service_id.to_string(),
+            amount_wei: Some(amount_wei.to_string()),
+            tx_hash,
+            outcome,
+            error,
+        },
+    );
+}
+
+fn log_claim_attempt(
+    inner: &AppStateInner,
+    service_id: &str,
+    result: &Result<ClaimResult, StakingError>,
+) {
+    let (amount_wei, tx_hash, outcome, error) = match result {
+        Ok(r) if r.success => (
+            Some(r.amount_claimed_wei.clone()),
+            r.tx_hash.clone(),
+            StakingOutcome::Success,
+            None,
+        ),
+        Ok(r) => (
+            None,
+            r.tx_hash.clone(),
+            StakingOutcome::Failed,
+            r.error.clone(),
+        ),
+        Err(e) => (None, None, StakingOutcome::NotSent, Some(e.to_string())),
+    };
+
+    inner.staking_log.record(
+        &inner.config.staking,
+        StakingLogEntry {
+            timestamp: chrono::Utc::now().timestamp(),
+            action: StakingAction::Claim,
+            service_id: service_id.to_string(),
+            amount_wei,
+            tx_hash,
+            outcome,
+            error,
+        },
+    );
+}
+
+/// Resubmit a pending transaction from the connected wallet with the same
+/// nonce and a higher max fee, replacing it in the mempool
+#[tauri::command]
+#[tracing::instrument(
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        original_tx = %tx_hash,
+        tx_hash,
+        outcome,
+    )
+)]
+pub async fn bump_transaction(
+    state: State<'_, AppState>,
+    tx_hash: String,
+    new_max_fee_wei: String,
+) -> Result<StakeResult, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+
+    let hash = TxHash::from_str(&tx_hash).map_err(|e| {
+        StakingError::InvalidTransaction(format!("Invalid transaction hash: {}", e))
+    })?;
+    let new_max_fee = parse_fee("max fee", Some(&new_max_fee_wei))?.unwrap_or_default();
+
+    let signer = wallet_signer(&inner)?;
+    let operator = signer.address();
+    ensure_expected_chain(&inner).await?;
+
+    let provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(EthereumWallet::from(signer.clone()))
+        .on_client(inner.rpc_client().await.map_err(StakingError::Config)?);
+
+    let original = pending_wallet_transaction(&inner, hash, operator).await?;
+    let to = original.to.ok_or_else(|| {
+        StakingError::InvalidTransaction("Contract deployments can't be bumped".to_string())
+    })?;
+
+    // Legacy transactions only carry a gas price, which caps both fees
+    let original_max_fee = original
+        .max_fee_per_gas
+        .or(original.gas_price)
+        .unwrap_or_default();
+    if new_max_fee <= original_max_fee {
+        return Err(StakingError::InvalidAmount(format!(
+            "New max fee {} must be higher than the original {}",
+            new_max_fee, original_max_fee
+        )));
+    }
+
+    // Raise the tip by the same amount so the replacement is actually preferred
+    let original_priority_fee = original
+        .max_priority_fee_per_gas
+        .unwrap_or(original_max_fee);
+    let fees = FeeOverrides {
+        max_fee_per_gas: Some(new_max_fee.to_string()),
+        max_priority_fee_per_gas: Some(
+            (original_priority_fee + (new_max_fee - original_max_fee))
+                .min(new_max_fee)
+                .to_string(),
+        ),
+        deadline_unix: None,
+    };
+
+    tracing::info!(
+        "Bumping transaction {} (nonce {}) to max fee {}",
+        tx_hash,
+        original.nonce,
+        new_max_fee
+    );
+
+    let call = CallBuilder::new_raw(&provider, original.input)
+        .to(to)
+        .value(original.value)
+        .from(operator)
+        .nonce(original.nonce)
+        .gas(original.gas);
+    // The original already went out, so skip simulation and just replace it
+    let submitted = send_and_confirm(&inner, &call, &fees, false).await?;
+
+    let (gas_cost_wei, gas_cost_usd) = gas_cost_fields(&inner, submitted.gas_cost).await;
+    Ok(StakeResult {
+        success: submitted.error.is_none(),
+        tx_hash: Some(submitted.tx_hash.to_string()),
+        approval_tx_hash: None,
+        new_stake_wei: current_compute_stake(&inner, operator).await.to_string(),
+        amount_wei: None,
+        usd_price: None,
+        error: submitted.error,
+        beneficiary: None,
+        beneficiary_name: None,
+        gas_cost_wei,
+        gas_cost_usd,
+        events: decode_receipt_events(&submitted.logs),
+    })
+}
+
+/// Evict a pending transaction from the connected wallet by sending 0 ETH to
+/// itself at the same nonce with higher fees. `tx_hash` on the result is the
+/// cancellation transaction.
+#[tauri::command]
+#[tracing::instrument(
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        original_tx = %tx_hash,
+        tx_hash,
+        outcome,
+    )
+)]
+pub async fn cancel_transaction(
+    state: State<'_, AppState>,
+    tx_hash: String,
+) -> Result<StakeResult, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+
+    let hash = TxHash::from_str(&tx_hash).map_err(|e| {
+        StakingError::InvalidTransaction(format!("Invalid transaction hash: {}", e))
+    })?;
+
+    let signer = wallet_signer(&inner)?;
+    let operator = signer.address();
+    ensure_expected_chain(&inner).await?;
+
+    let original = pending_wallet_transaction(&inner, hash, operator).await?;
+
+    // Nodes only accept a replacement that outbids both fees by a margin, and
+    // it has to be priced for the current network to get mined at all
+    let gas_price = inner
+        .provider()
+        .await
+        .map_err(StakingError::Config)?
+        .get_gas_price()
+        .await
+        .map_err(|e| StakingError::RpcError(format!("Failed to get gas price: {}", e)))?;
+    let original_max_fee = original
+        .max_fee_per_gas
+        .or(original.gas_price)
+        .unwrap_or_default();
+    let original_priority_fee = original
+        .max_priority_fee_per_gas
+        .unwrap_or(original_max_fee);
+    let max_fee = bump_fee(original_max_fee).max(gas_price);
+    let fees = FeeOverrides {
+        max_fee_per_gas: Some(max_fee.to_string()),
+        max_priority_fee_per_gas: Some(bump_fee(original_priority_fee).min(max_fee).to_string()),
+        deadline_unix: None,
+    };
+
+    tracing::info!(
+        "Cancelling transaction {} (nonce {}) with max fee {}",
+        tx_hash,
+        original.nonce,
+        max_fee
+    );
+
+    let provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(EthereumWallet::from(signer.clone()))
+        .on_client(inner.rpc_client().await.map_err(StakingError::Config)?);
+    let call = CallBuilder::new_raw(&provider, Bytes::new())
+        .to(operator)
+        .value(U256::ZERO)
+        .from(operator)
+        .nonce(original.nonce)
+        .gas(SELF_TRANSFER_GAS);
+    let submitted = send_and_confirm(&inner, &call, &fees, false).await?;
+
+    let (gas_cost_wei, gas_cost_usd) = gas_cost_fields(&inner, submitted.gas_cost).await;
+    Ok(StakeResult {
+        success: submitted.error.is_none(),
+        tx_hash: Some(submitted.tx_hash.to_string()),
+        approval_tx_hash: None,
+        new_stake_wei: current_compute_stake(&inner, operator).await.to_string(),
+        amount_wei: None,
+        usd_price: None,
+        error: submitted.error,
+        beneficiary: None,
+        beneficiary_name: None,
+        gas_cost_wei,
+        gas_cost_usd,
+        events: decode_receipt_events(&submitted.logs),
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(
+    level = "debug",
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        tx_hash = %tx_hash,
+    )
+)]
+pub async fn get_transaction_status(
+    state: State<'_, AppState>,
+    tx_hash: String,
+) -> Result<TransactionStatus, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    let hash = TxHash::from_str(tx_hash.trim()).map_err(|e| {
+        StakingError::InvalidTransaction(format!("Invalid transaction hash: {}", e))
+    })?;
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+
+    let receipt = provider
+        .get_transaction_receipt(hash)
+        .await
+        .or_else(pruned_as_missing)
+        .map_err(|e| StakingError::RpcError(format!("Failed to get receipt: {}", e)))?;
+
+    if let Some(receipt) = receipt {
+        let block_number = receipt.block_number;
+        let head = provider
+            .get_block_number()
+            .await
+            .map_err(|e| StakingError::RpcError(format!("Failed to get block number: {}", e)))?;
+        let confirmations = block_number
+            .map(|block| head.saturating_sub(block) + 1)
+            .unwrap_or_default();
+
+        return Ok(TransactionStatus {
+            status: if receipt.status() {
+                TransactionState::Success
+            } else {
+                TransactionState::Reverted
+            },
+            confirmations,
+            block_number,
+        });
+    }
+
+    // No receipt: either still in the mempool or unknown to this node
+    let transaction = provider
+        .get_transaction_by_hash(hash)
+        .await
+        .or_else(pruned_as_missing)
+        .map_err(|e| StakingError::RpcError(format!("Failed to get transaction: {}", e)))?;
+
+    Ok(TransactionStatus {
+        status: match transaction {
+            Some(_) => TransactionState::Pending,
+            None => TransactionState::NotFound,
+        },
+        confirmations: 0,
+        block_number: None,
+    })
+}
+
+/// Consecutive polls a transaction must be unknown to the node before it is
+/// reported dropped, since a failover endpoint may not have seen it yet
+const DROPPED_AFTER_MISSING_POLLS: u32 = 3;
+
+/// Wait for a transaction sent earlier, e.g. by `broadcast_raw_tx`,
+/// `bump_transaction` or before a restart, to be mined with `confirmations`
+/// (default `network.required_confirmations`), emitting the same status
+/// events as an inline send. Returns the final status: `dropped` if the node
+/// stops knowing the transaction, `unconfirmed` if `timeout_ms` (default
+/// `network.receipt_timeout_secs`) passes first.
+#[tauri::command]
+#[tracing::instrument(
+    skip_all,
+    fields(
+        wallet,
+        chain_id,
+        endpoint,
+        tx_hash = %tx_hash,
+        outcome,
+    )
+)]
+pub async fn wait_for_confirmation(
+    state: State<'_, AppState>,
+    tx_hash: String,
+    confirmations: Option<u32>,
+    timeout_ms: Option<u64>,
+) -> Result<TxStatusEvent, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    let hash = TxHash::from_str(tx_hash.trim()).map_err(|e| {
+        StakingError::InvalidTransaction(format!("Invalid transaction hash: {}", e))
+    })?;
+    let required = confirmations
+        .map(u64::from)
+        .unwrap_or(inner.config.network.required_confirmations)
+        .max(1);
+    let timeout = timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or_else(|| receipt_timeout(&inner));
+    let deadline = Instant::now() + timeout;
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+
+    emit_tx_status(&inner, hash, TxStatus::Submitted, None, 0, required, None);
+    let mut missing_polls = 0;
+    let receipt = loop {
+        // Failed lookups are retried on the next tick
+        match provider
+            .get_transaction_receipt(hash)
+            .await
+            .or_else(pruned_as_missing)
+        {
+            Ok(Some(receipt)) => break receipt,
+            Ok(None) => match provider
+                .get_transaction_by_hash(hash)
+                .await
+                .or_else(pruned_as_missing)
+            {
+                Ok(Some(_)) => missing_polls = 0,
+                Ok(None) => {
+                    missing_polls += 1;
+                    if missing_polls >= DROPPED_AFTER_MISSING_POLLS {
+                        return Ok(emit_tx_status(
+                            &inner,
+                            hash,
+                            TxStatus::Dropped,
+                            None,
+                            0,
+                            required,
+                            Some(
+                                "Transaction is not known to the node; it was dropped, \
+                                 replaced or never broadcast"
+                                    .to_string(),
+                            ),
+                        ));
+                    }
+                }
+                Err(e) => tracing::debug!("Failed to look up transaction: {}", e),
+            },
+            Err(e) => tracing::debug!("Failed to get receipt: {}", e),
+        }
+
+        if Instant::now() >= deadline {
+            return Ok(emit_tx_status(
+                &inner,
+                hash,
+                TxStatus::Unconfirmed,
+                None,
+                0,
+                required,
+                Some(format!(
+                    "Transaction not mined within {}s",
+                    timeout.as_secs()
+                )),
+            ));
+        }
+        tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await;
+    };
+
+    // Any mined transaction may have changed stake data
+    inner.staking_info_cache.invalidate();
+    if !receipt.status() {
+        return Ok(emit_tx_status(
+            &inner,
+            hash,
+            TxStatus::Reverted,
+            receipt.block_number,
+            1,
+            required,
+            Some("Transaction reverted".to_string()),
+        ));
+    }
+
+    Ok(confirm_mined(&inner, &receipt, required, deadline)
+        .await
+        .unwrap_or_else(|event| event))
+}
+
+/// Nodes that pruned old history answer lookups with an error instead of
+/// null; treat any error the node itself returned as "no record"
+fn pruned_as_missing<T>(
+    error: RpcError<TransportErrorKind>,
+) -> Result<Option<T>, RpcError<TransportErrorKind>> {
+    match error {
+        RpcError::ErrorResp(payload) => {
+            tracing::debug!("Treating RPC error as missing data: {}", payload.message);
+            Ok(None)
+        }
+        other => Err(other),
+    }
+}
+
+/// Gas used by a plain ETH transfer
+const SELF_TRANSFER_GAS: u128 = 21_000;
+
+/// Percent a replacement transaction raises each fee by; nodes require at least 10
+const REPLACEMENT_FEE_BUMP_PERCENT: u128 = 20;
+
+fn bump_fee(fee: u128) -> u128 {
+    (fee + fee * REPLACEMENT_FEE_BUMP_PERCENT / 100).max(fee + 1)
+}
+
+/// Look up a transaction that the connected wallet sent and that is still
+/// waiting in the mempool
+async fn pending_wallet_transaction(
+    inner: &AppStateInner,
+    hash: TxHash,
+    operator: Address,
+) -> Result<Transaction, StakingError> {
+    let transaction = inner
+        .provider()
+        .await
+        .map_err(StakingError::Config)?
+        .get_transaction_by_hash(hash)
+        .await
+        .map_err(|e| StakingError::RpcError(format!("Failed to get transaction: {}", e)))?
+        .ok_or_else(|| {
+            StakingError::InvalidTransaction(format!("Transaction {} not found", hash))
+        })?;
+
+    if let Some(block) = transaction.block_number {
+        return Err(StakingError::InvalidTransaction(format!(
+            "Transaction {} was already mined in block {}",
+            hash, block
+        )));
+    }
+    if transaction.from != operator {
+        return Err(StakingError::InvalidTransaction(format!(
+            "Transaction {} was not sent by the connected wallet",
+            hash
+        )));
+    }
+
+    Ok(transaction)
+}
+
+/// Operator's current compute stake, or zero if it can't be read. Replaced
+/// transactions are usually stakes, so results report where the stake landed.
+async fn current_compute_stake(inner: &AppStateInner, operator: Address) -> U256 {
+    let Ok(address) = inner.compute_staking_address(inner.config.network.chain_id) else {
+        return U256::ZERO;
+    };
+    let Ok(provider) = inner.provider().await else {
+        return U256::ZERO;
+    };
+
+    IComputeStaking::new(address, &*provider)
+        .getStake(operator)
+        .call()
+        .await
+        .map(|r| r.amount)
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn get_node_metrics(state: State<'_, AppState>) -> Result<NodeMetrics, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    let operator = wallet_address(&inner)?;
+
+    let node_staking_address = inner
+        .node_staking_address(inner.config.network.chain_id)
+        .map_err(StakingError::ContractNotConfigured)?;
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+    let node_staking = INodeStakingManager::new(node_staking_address, &*provider);
+
+    let node_info = with_retry(inner.config.network.rpc_max_attempts, || async {
+        node_staking.getNodeInfo(operator).call().await
+    })
+    .await
+    .map_err(|e| call_error("Failed to get node info", &e))?;
+
+    // Unregistered operators come back as an all-zero struct, not a revert
+    if node_info.registeredAt.is_zero() {
+        return Err(StakingError::NodeNotRegistered);
+    }
+
+    Ok(NodeMetrics {
+        uptime_seconds: node_info.uptime.saturating_to::<u64>(),
+        requests_served: node_info.requestsServed.saturating_to::<u64>(),
+        registered_at: node_info.registeredAt.saturating_to::<u64>(),
+        rpc_url: node_info.rpcUrl,
+        region: node_info.region,
+    })
+}
+
+/// Change the RPC endpoint and/or region the node advertises, without
+/// re-staking. Fields left out keep their registered values.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(wallet, chain_id, endpoint, tx_hash, outcome))]
+pub async fn update_node_registration(
+    state: State<'_, AppState>,
+    rpc_url: Option<String>,
+    region: Option<String>,
+) -> Result<StakeResult, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    ensure_writes_enabled(&inner)?;
+    let _in_flight = inner
+        .in_flight_writes
+        .begin("update_registration", "node")?;
+
+    let rpc_url = rpc_url.map(|url| url.trim().to_string());
+    let region = region.map(|region| region.trim().to_string());
+    if rpc_url.is_none() && region.is_none() {
+        return Err(StakingError::InvalidRegistration(
+            "Nothing to update; pass an RPC URL or a region".to_string(),
+        ));
+    }
+    if let Some(rpc_url) = &rpc_url {
+        validate_node_rpc_url(rpc_url)?;
+    }
+    if region.as_deref() == Some("") {
+        return Err(StakingError::InvalidRegistration(
+            "Region must not be empty".to_string(),
+        ));
+    }
+
+    let signer = wallet_signer(&inner)?;
+    let operator = signer.address();
+
+    let node_staking_address = inner
+        .node_staking_address(inner.config.network.chain_id)
+        .map_err(StakingError::ContractNotConfigured)?;
+    ensure_expected_chain(&inner).await?;
+
+    let read_provider = inner.provider().await.map_err(StakingError::Config)?;
+    let node_info = with_retry(inner.config.network.rpc_max_attempts, || async {
+        INodeStakingManager::new(node_staking_address, &*read_provider)
+            .getNodeInfo(operator)
+            .call()
+            .await
+    })
+    .await
+    .map_err(|e| call_error("Failed to get node info", &e))?;
+    if node_info.registeredAt.is_zero() {
+        return Err(StakingError::NodeNotRegistered);
+    }
+
+    let provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(EthereumWallet::from(signer.clone()))
+        .on_client(inner.rpc_client().await.map_err(StakingError::Config)?);
+    let node_staking = INodeStakingManager::new(node_staking_address, &provider);
+    let submitted = send_and_confirm(
+        &inner,
+        &node_staking
+            .updateNodeInfo(
+                rpc_url.unwrap_or(node_info.rpcUrl),
+                region.unwrap_or(node_info.region),
+            )
+            .from(operator),
+        &FeeOverrides::default(),
+        true,
+    )
+    .await?;
+
+    let (gas_cost_wei, gas_cost_usd) = gas_cost_fields(&inner, submitted.gas_cost).await;
+    Ok(StakeResult {
+        success: submitted.error.is_none(),
+        tx_hash: Some(submitted.tx_hash.to_string()),
+        approval_tx_hash: None,
+        new_stake_wei: node_info.stakeAmount.to_string(),
+        amount_wei: None,
+        usd_price: None,
+        error: submitted.error,
+        beneficiary: None,
+        beneficiary_name: None,
+        gas_cost_wei,
+        gas_cost_usd,
+        events: decode_receipt_events(&submitted.logs),
+    })
+}
+
+/// Check that a URL is one other nodes and clients could reach over JSON-RPC
+fn validate_node_rpc_url(rpc_url: &str) -> Result<(), StakingError> {
+    let url = Url::parse(rpc_url)
+        .map_err(|e| StakingError::InvalidRegistration(format!("Invalid RPC URL: {}", e)))?;
+
+    if !matches!(url.scheme(), "http" | "https" | "ws" | "wss") {
+        return Err(StakingError::InvalidRegistration(format!(
+            "RPC URL must use http, https, ws or wss, not {}",
+            url.scheme()
+        )));
+    }
+    if url.host_str().map_or(true, str::is_empty) {
+        return Err(StakingError::InvalidRegistration(
+            "RPC URL has no host".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Where a service's staking contract lives and what code backs it.
+/// Transactions always go to `address`; `implementation_address` is for
+/// checking which version a proxy currently points at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractInfo {
+    pub service_id: String,
+    /// Configured address (the proxy, for upgradeable deployments)
+    pub address: String,
+    pub has_code: bool,
+    /// EIP-1967 implementation, `None` if the contract isn't such a proxy
+    pub implementation_address: Option<String>,
+    pub implementation_has_code: Option<bool>,
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn get_contract_info(
+    state: State<'_, AppState>,
+    service_id: Option<String>,
+) -> Result<ContractInfo, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    let service_id = service_id.as_deref().unwrap_or("compute");
+    let address = service_contract(&inner, service_id)?;
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+
+    let has_code = code_present(&provider, address).await?;
+    let implementation = proxy_implementation(&provider, address).await?;
+    let implementation_has_code = match implementation {
+        Some(implementation) => Some(code_present(&provider, implementation).await?),
+        None => None,
+    };
+
+    Ok(ContractInfo {
+        service_id: service_id.to_string(),
+        address: format!("{:?}", address),
+        has_code,
+        implementation_address: implementation.map(|a| format!("{:?}", a)),
+        implementation_has_code,
+    })
+}
+
+async fn code_present(provider: &ReadProvider, address: Address) -> Result<bool, StakingError> {
+    provider
+        .get_code_at(address)
+        .await
+        .map(|code| !code.is_empty())
+        .map_err(|e| StakingError::RpcError(format!("Failed to get code at {}: {}", address, e)))
+}
+
+/// Stakeable services on the current chain, for rendering staking options
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn list_services(
+    state: State<'_, AppState>,
+) -> Result<Vec<ServiceDescriptor>, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    Ok(service_descriptors(&inner).await)
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn get_wallet_balance(
+    state: State<'_, AppState>,
+    token_address: Option<String>,
+) -> Result<WalletBalance, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    let operator = wallet_address(&inner)?;
+
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+    let attempts = inner.config.network.rpc_max_attempts;
+
+    let (balance, token) = match token_address.as_deref() {
+        None => {
+            let balance = provider
+                .get_balance(operator)
+                .await
+                .map_err(|e| StakingError::RpcError(format!("Failed to get balance: {}", e)))?;
+            (balance, "ETH".to_string())
+        }
+        Some(token_address) => {
+            let token_address = parse_address(token_address).map_err(|e| {
+                StakingError::InvalidAddress(format!("Invalid token address: {}", e))
+            })?;
+            let token = IERC20::new(token_address, &*provider);
+            let balance = with_retry(attempts, || async {
+                token.balanceOf(operator).call().await
+            })
+            .await
+            .map_err(|e| call_error("Failed to get token balance", &e))?
+            ._0;
+            (balance, format!("{:?}", token_address))
+        }
+    };
+
+    let mut prices_stale = false;
+    let balance_usd = usd_or_stale(
+        token_usd(&inner, &AlloyChain::new(&inner), balance, &token).await,
+        &mut prices_stale,
+    );
+
+    Ok(WalletBalance {
+        balance_wei: balance.to_string(),
+        balance_usd,
+        token,
+        prices_stale,
+    })
+}
+
+/// Every ERC20 a configured service stakes, with the wallet's balance of it
+/// and its allowance to the service's contract, so the UI can tell whether an
+/// approval is needed before staking
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn get_stake_tokens(
+    state: State<'_, AppState>,
+) -> Result<Vec<StakeTokenInfo>, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    let owner = query_address(&inner, None).await?;
+    let stake_tokens = stake_token_spenders(&service_descriptors(&inner).await);
+    if stake_tokens.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+    fetch_stake_tokens(&inner, &provider, owner, stake_tokens).await
+}
+
+/// ERC20 stake tokens and the contract each is staked into, one entry per
+/// token and contract. "ETH" and tokens configured by symbol have no
+/// contract to read, so they are left out.
+fn stake_token_spenders(services: &[ServiceDescriptor]) -> Vec<(String, Address, Address)> {
+    let mut stake_tokens: Vec<(String, Address, Address)> = Vec::new();
+    for service in services {
+        let (Ok(token), Ok(spender)) = (
+            Address::from_str(&service.stake_token),
+            Address::from_str(&service.contract_address),
+        ) else {
+            continue;
+        };
+        if !stake_tokens
+            .iter()
+            .any(|(_, t, s)| *t == token && *s == spender)
+        {
+            stake_tokens.push((service.id.clone(), token, spender));
+        }
+    }
+    stake_tokens
+}
+
+/// Read symbol, decimals, balance and allowance for each stake token in one
+/// Multicall3 round trip when the chain has one configured, otherwise call
+/// each token individually
+async fn fetch_stake_tokens(
+    inner: &AppStateInner,
+    provider: &ReadProvider,
+    owner: Address,
+    stake_tokens: Vec<(String, Address, Address)>,
+) -> Result<Vec<StakeTokenInfo>, StakingError> {
+    let attempts = inner.config.network.rpc_max_attempts;
+
+    if let Ok(multicall_address) = inner.multicall_address(inner.config.network.chain_id) {
+        let mut calls = Vec::new();
+        for (_, token, spender) in &stake_tokens {
+            calls.push(call3(*token, IERC20::symbolCall {}.abi_encode()));
+            calls.push(call3(*token, IERC20::decimalsCall {}.abi_encode()));
+            calls.push(call3(
+                *token,
+                IERC20::balanceOfCall { account: owner }.abi_encode(),
+            ));
+            calls.push(call3(
+                *token,
+                IERC20::allowanceCall {
+                    owner,
+                    spender: *spender,
+                }
+                .abi_encode(),
+            ));
+        }
+
+        match multicall(
+            provider,
+            multicall_address,
+            calls,
+            attempts,
+            BlockId::latest(),
+        )
+        .await
+        {
+            Ok(results) => {
+                let mut results = results.into_iter();
+                let mut tokens = Vec::with_capacity(stake_tokens.len());
+                for (service_id, token, spender) in stake_tokens {
+                    let symbol = decode_return::<IERC20::symbolCall>(results.next())
+                        .map(|r| r._0)
+                        .unwrap_or_default();
+                    let decimals =
+                        decode_return::<IERC20::decimalsCall>(results.next()).map(|r| r._0);
+                    let balance =
+                        decode_return::<IERC20::balanceOfCall>(results.next()).map(|r| r._0);
+                    let allowance =
+                        decode_return::<IERC20::allowanceCall>(results.next()).map(|r| r._0);
+                    let (decimals, balance, allowance) = match (decimals, balance, allowance) {
+                        (Ok(decimals), Ok(balance), Ok(allowance)) => {
+                            (decimals, balance, allowance)
+                        }
+                        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+                            return Err(StakingError::RpcError(format!(
+                                "Failed to read stake token {:?}: {}",
+                                token, e
+                            )));
+                        }
+                    };
+                    inner.token_decimals_cache.insert(token, decimals);
+                    tokens.push(StakeTokenInfo {
+                        token_address: format!("{:?}", token),
+                        symbol,
+                        decimals,
+                        service_id,
+                        spender: format!("{:?}", spender),
+                        balance_wei: balance.to_string(),
+                        allowance_wei: allowance.to_string(),
+                    });
+                }
+                return Ok(tokens);
+            }
+            Err(e) => tracing::warn!("Multicall failed, reading stake tokens one by one: {}", e),
+        }
+    }
+
+    // Without a multicall, read every token at once rather than one after another
+    let chain = AlloyChain::new(inner);
+    let chain = &chain;
+    join_all(
+        stake_tokens
+            .into_iter()
+            .map(|(service_id, token, spender)| async move {
+                let token_contract = IERC20::new(token, provider);
+                let (symbol, decimals, balance, allowance) = tokio::join!(
+                    with_retry(attempts, || async { token_contract.symbol().call().await }),
+                    chain.token_decimals(token),
+                    chain.token_balance(token, owner),
+                    chain.allowance(token, owner, spender),
+                );
+                Ok(StakeTokenInfo {
+                    token_address: format!("{:?}", token),
+                    symbol: symbol.map(|r| r._0).unwrap_or_default(),
+                    decimals: decimals?,
+                    service_id,
+                    spender: format!("{:?}", spender),
+                    balance_wei: balance?.to_string(),
+                    allowance_wei: allowance?.to_string(),
+                })
+            }),
+    )
+    .await
+    .into_iter()
+    .collect()
+}
+
+/// How nodes word a read of state they've pruned or never had: geth, Erigon,
+/// Nethermind, Besu and reth
+const MISSING_STATE_ERRORS: [&str; 6] = [
+    "missing trie node",
+    "header not found",
+    "historical state",
+    "state not available",
+    "world state",
+    "pruned",
+];
+
+/// Fail with `HistoricalStateUnavailable` unless the endpoint can serve state
+/// at `block`, which for anything but recent blocks takes an archive node
+async fn ensure_state_available(
+    inner: &AppStateInner,
+    address: Address,
+    block: u64,
+) -> Result<(), StakingError> {
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+    let head = provider
+        .get_block_number()
+        .await
+        .map_err(|e| StakingError::RpcError(format!("Failed to get block number: {}", e)))?;
+    if block > head {
+        return Err(StakingError::RpcError(format!(
+            "Block {} is past the chain head at {}",
+            block, head
+        )));
+    }
+
+    match provider.get_balance(address).number(block).await {
+        Ok(_) => Ok(()),
+        Err(e) if is_missing_state(&e.to_string()) => {
+            Err(StakingError::HistoricalStateUnavailable { block })
+        }
+        Err(e) => Err(StakingError::RpcError(format!(
+            "Failed to read state at block {}: {}",
+            block, e
+        ))),
+    }
+}
+
+fn is_missing_state(error: &str) -> bool {
+    let error = error.to_lowercase();
+    MISSING_STATE_ERRORS
+        .iter()
+        .any(|missing| error.contains(missing))
+}
+
+/// Last block produced at or before `timestamp`, e.g. to read stakes as of the
+/// end of a month with `get_staking_info`'s `at_block`
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn get_block_at_timestamp(
+    state: State<'_, AppState>,
+    timestamp: u64,
+) -> Result<u64, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+    let head = provider
+        .get_block_number()
+        .await
+        .map_err(|e| StakingError::RpcError(format!("Failed to get block number: {}", e)))?;
+    let provider = &provider;
+    block_at_timestamp(head, timestamp, |number| async move {
+        provider
+            .get_block_by_number(BlockNumberOrTag::Number(number), false)
+            .await
+            .map_err(|e| StakingError::RpcError(format!("Failed to get block {}: {}", number, e)))?
+            .map(|block| block.header.timestamp)
+            .ok_or_else(|| StakingError::RpcError(format!("Block {} not found", number)))
+    })
+    .await
+}
+
+/// Binary search blocks `0..=head` for the last one with a timestamp at or
+/// before `timestamp`, which takes about log2(head) block lookups
+async fn block_at_timestamp<F, Fut>(
+    head: u64,
+    timestamp: u64,
+    block_timestamp: F,
+) -> Result<u64, StakingError>
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<u64, StakingError>>,
+{
+    if block_timestamp(head).await? <= timestamp {
+        return Ok(head);
+    }
+    let genesis = block_timestamp(0).await?;
+    if genesis > timestamp {
+        return Err(StakingError::InvalidAmount(format!(
+            "Timestamp {} is before the chain's first block at {}",
+            timestamp, genesis
+        )));
+    }
+
+    // Block `low` is always at or before the timestamp and `high` after it
+    let (mut low, mut high) = (0, head);
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if block_timestamp(mid).await? <= timestamp {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(low)
+}
+
+/// Largest block range requested per `eth_getLogs` call; many RPC providers
+/// reject wider ranges
+const LOG_CHUNK_BLOCKS: u64 = 10_000;
+
+/// How far back history goes when no `from_block` is given
+const DEFAULT_HISTORY_BLOCKS: u64 = 500_000;
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn get_staking_history(
+    state: State<'_, AppState>,
+    from_block: Option<u64>,
+    limit: Option<usize>,
+) -> Result<Vec<StakingEvent>, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    let operator = wallet_address(&inner)?;
+
+    let chain_id = inner.config.network.chain_id;
+    let compute_staking_address = inner.compute_staking_address(chain_id).ok();
+    let node_staking_address = inner.node_staking_address(chain_id).ok();
+    let addresses: Vec<Address> = [compute_staking_address, node_staking_address]
+        .into_iter()
+        .flatten()
+        .collect();
+    if addresses.is_empty() {
+        return Err(StakingError::ContractNotConfigured(format!(
+            "No staking contracts configured for chain {}",
+            chain_id
+        )));
+    }
+
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+    let latest = provider
+        .get_block_number()
+        .await
+        .map_err(|e| StakingError::RpcError(format!("Failed to get block number: {}", e)))?;
+    let start = from_block.unwrap_or_else(|| latest.saturating_sub(DEFAULT_HISTORY_BLOCKS));
+
+    let filter = Filter::new()
+        .address(addresses)
+        .event_signature(vec![
+            IComputeStaking::Staked::SIGNATURE_HASH,
+            IComputeStaking::Unstaked::SIGNATURE_HASH,
+            IComputeStaking::RewardsClaimed::SIGNATURE_HASH,
+            INodeStakingManager::NodeStaked::SIGNATURE_HASH,
+            INodeStakingManager::NodeUnstaked::SIGNATURE_HASH,
+            INodeStakingManager::NodeRewardsClaimed::SIGNATURE_HASH,
+        ])
+        .topic1(operator.into_word());
+
+    let mut events = Vec::new();
+    let mut chunk_start = start;
+    while chunk_start <= latest {
+        let chunk_end = (chunk_start + LOG_CHUNK_BLOCKS - 1).min(latest);
+        let logs = provider
+            .get_logs(&filter.clone().from_block(chunk_start).to_block(chunk_end))
+            .await
+            .map_err(|e| {
+                StakingError::RpcError(format!(
+                    "Failed to get logs for blocks {}-{}: {}",
+                    chunk_start, chunk_end, e
+                ))
+            })?;
+
+        events.extend(logs.iter().filter_map(decode_staking_event));
+        chunk_start = chunk_end + 1;
+    }
+
+    // Newest first, then only look up timestamps for what we return
+    events.sort_by(|a, b| b.block_number.cmp(&a.block_number));
+    if let Some(limit) = limit {
+        events.truncate(limit);
+    }
+
+    let mut timestamps: HashMap<u64, Option<u64>> = HashMap::new();
+    for event in &mut events {
+        let timestamp = match timestamps.get(&event.block_number) {
+            Some(timestamp) => *timestamp,
+            None => {
+                let timestamp = provider
+                    .get_block_by_number(event.block_number.into(), false)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|block| block.header.timestamp);
+                timestamps.insert(event.block_number, timestamp);
+                timestamp
+            }
+        };
+        event.timestamp = timestamp;
+    }
+
+    Ok(events)
+}
+
+/// Bounds on the auto-claim interval: at most hourly, at least yearly
+const AUTO_CLAIM_INTERVAL_HOURS: std::ops::RangeInclusive<u32> = 1..=8760;
+
+/// Settings are all validated before anything is saved, so a bad value never
+/// reaches the worker
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(wallet, chain_id, endpoint, enabled = %enabled))]
+pub async fn enable_auto_claim(
+    state: State<'_, AppState>,
+    enabled: bool,
+    threshold_wei: Option<String>,
+    interval_hours: Option<u32>,
+) -> Result<(), StakingError> {
+    let mut inner = state.inner.write().await;
+    record_span_context(&inner);
+
+    let threshold = threshold_wei
+        .as_deref()
+        .map(parse_wei)
+        .transpose()
+        .map_err(|e| StakingError::InvalidAmount(format!("Invalid auto-claim threshold: {}", e)))?;
+    if let Some(interval) = interval_hours {
+        if !AUTO_CLAIM_INTERVAL_HOURS.contains(&interval) {
+            return Err(StakingError::Config(format!(
+                "Auto-claim interval must be between {} and {} hours, got {}",
+                AUTO_CLAIM_INTERVAL_HOURS.start(),
+                AUTO_CLAIM_INTERVAL_HOURS.end(),
+                interval
+            )));
+        }
+    }
+    // The worker claims with the connected wallet, so there must be one
+    if enabled {
+        wallet_signer(&inner)?;
+    }
+
+    inner
+        .config
+        .update(|config| {
+            config.earnings.auto_claim = enabled;
+            if let Some(threshold) = threshold {
+                config.earnings.auto_claim_threshold_wei = threshold.to_string();
+            }
+            if let Some(interval) = interval_hours {
+                config.earnings.auto_claim_interval_hours = interval;
+            }
+        })
+        .map_err(StakingError::Config)?;
+
+    // Restart so the worker picks up the new settings on its next check
+    inner.auto_claim_worker.stop();
+    if enabled {
+        inner
+            .auto_claim_worker
+            .start(state.inner.clone(), &state.shutdown);
+    }
+
+    Ok(())
+}
+
+/// Turn every staking write off, or back on, e.g. while a contract issue is
+/// investigated. Positions can still be viewed while writes are off.
+#[tauri::command]
+#[tracing::instrument(skip_all, fields(wallet, chain_id, endpoint, disabled = %disabled))]
+pub async fn set_writes_disabled(
+    state: State<'_, AppState>,
+    disabled: bool,
+) -> Result<(), StakingError> {
+    let mut inner = state.inner.write().await;
+    record_span_context(&inner);
+
+    inner
+        .config
+        .update(|config| config.safety.writes_disabled = disabled)
+        .map_err(StakingError::Config)?;
+
+    if disabled {
+        tracing::warn!("Staking writes disabled");
+    } else {
+        tracing::info!("Staking writes re-enabled");
+    }
+
+    Ok(())
+}
+
+/// Services with pending rewards, optionally as of `at_block`, which needs an
+/// archive-capable RPC endpoint like `get_staking_info`'s
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn get_pending_rewards(
+    state: State<'_, AppState>,
+    address: Option<String>,
+    at_block: Option<u64>,
+) -> Result<Vec<ServiceStakeInfo>, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    // Nothing to report for the connected wallet until one is loaded
+    let operator = match query_address(&inner, address.as_deref()).await {
+        Ok(operator) => operator,
+        Err(StakingError::WalletNotConnected | StakingError::WalletNotInitialized) => {
+            return Ok(vec![])
+        }
+        Err(e) => return Err(e),
+    };
+    if let Some(block) = at_block {
+        ensure_state_available(&inner, operator, block).await?;
+    }
+    pending_rewards_by_service(&inner, &AlloyChain::at_block(&inner, at_block), operator).await
+}
+
+/// `operator`'s services with pending rewards, read through `chain`
+async fn pending_rewards_by_service(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    operator: Address,
+) -> Result<Vec<ServiceStakeInfo>, StakingError> {
+    // Node-only operators may have no compute staking contract to read, and vice versa
+    let chain_id = inner.config.network.chain_id;
+    let compute_staking_address = inner.compute_staking_address(chain_id).ok();
+    let node_staking_address = inner.node_staking_address(chain_id).ok();
+    if compute_staking_address.is_none() && node_staking_address.is_none() {
+        return Err(StakingError::ContractNotConfigured(format!(
+            "No staking contracts configured for chain {}",
+            chain_id
+        )));
+    }
+
+    // Query staking contracts for pending rewards
+    let stakes = chain.legacy_stakes(operator).await?;
+
+    let mut result = Vec::new();
+    for stake in stakes {
+        let pending: u128 = stake.pending_rewards.parse().unwrap_or(0);
+        if pending > 0 {
+            result.push(ServiceStakeInfo {
+                service_id: stake.node_id.clone(),
+                service_name: format!("Node {}", &stake.node_id[..10]),
+                staked_wei: stake.staked_amount,
+                staked_usd: wei_to_tokens(
+                    U256::from_str(&stake.staked_value_usd).unwrap_or_default(),
+                ),
+                pending_rewards_wei: stake.pending_rewards,
+                stake_token: stake.staking_token,
+                stake_kind: None,
+                min_stake_wei: "1000000000000000000000".to_string(),
+                rpc_url: None,
+                region: None,
+                requests_served: None,
+                positions: Vec::new(),
+            });
+        }
+    }
+
+    // Query the compute staking contract for provider rewards and the node
+    // staking manager for operator rewards. A failed read is an error, not
+    // "no rewards".
+    let reads = chain
+        .stake_reads(operator, compute_staking_address, node_staking_address, &[])
+        .await?;
+    if let Some(error) = reads.errors.first() {
+        return Err(StakingError::RpcError(format!(
+            "Failed to read {} for {}: {}",
+            error.call, error.service_id, error.error
+        )));
+    }
+
+    if let (Some(stake), true) = (reads.compute_stake, reads.compute_pending > U256::ZERO) {
+        let staked = stake.amount;
+        let min_stake = min_compute_stake(inner).await?.to_string();
+        let cooldown_secs = unstake_cooldown_secs(inner).await;
+        let stake_token = match compute_staking_address {
+            Some(address) => chain.stake_token(address, operator).await?,
+            None => None,
+        };
+        let mut compute_stake = compute_stake_info(
+            &stake,
+            stake_token,
+            reads.compute_pending,
+            &min_stake,
+            cooldown_secs,
+        );
+        compute_stake.staked_usd = token_usd(inner, chain, staked, &compute_stake.stake_token)
+            .await
+            .unwrap_or(0.0);
+        result.push(compute_stake);
+    }
+
+    if let (Some(node_info), true) = (reads.node_info, reads.node_pending > U256::ZERO) {
+        let staked = node_info.stakeAmount;
+        let mut node_stake = node_stake_info(node_info, reads.node_pending);
+        node_stake.staked_usd = token_usd(inner, chain, staked, &node_stake.stake_token)
+            .await
+            .unwrap_or(0.0);
+        result.push(node_stake);
+    }
+
+    Ok(result)
+}
+
+/// Stake entry for the node staking manager; `staked_usd` is left for the caller to price
+fn node_stake_info(
+    node_info: INodeStakingManager::getNodeInfoReturn,
+    pending: U256,
+) -> ServiceStakeInfo {
+    // The node staking manager has no withdrawal, so its stake never unlocks here
+    let positions = if node_info.stakeAmount.is_zero() {
+        Vec::new()
+    } else {
+        vec![StakePosition {
+            position_id: 0,
+            staked_wei: node_info.stakeAmount.to_string(),
+            staked_at: node_info.registeredAt.saturating_to(),
+            cooldown_remaining_seconds: 0,
+            can_unstake: false,
+        }]
+    };
+
+    ServiceStakeInfo {
+        service_id: "node".to_string(),
+        service_name: "Node Operator".to_string(),
+        staked_wei: node_info.stakeAmount.to_string(),
+        staked_usd: 0.0,
+        pending_rewards_wei: pending.to_string(),
+        stake_token: format!("{:?}", node_info.stakeToken),
+        stake_kind: None,
+        min_stake_wei: "1000000000000000000000".to_string(), // 1000 JEJU minimum
+        rpc_url: Some(node_info.rpcUrl),
+        region: Some(node_info.region),
+        requests_served: Some(node_info.requestsServed.saturating_to::<u64>()),
+        positions,
+    }
+}
+
+/// The compute staking contract's reward rate, minimum stake, cooldown and
+/// early-unstake penalty, cached for an hour
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn get_staking_parameters(
+    state: State<'_, AppState>,
+) -> Result<StakingParameters, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    staking_parameters(&inner).await
+}
+
+/// Read the contract's parameters, or reuse them if read recently
+async fn staking_parameters(inner: &AppStateInner) -> Result<StakingParameters, StakingError> {
+    let compute_staking_address = inner
+        .compute_staking_address(inner.config.network.chain_id)
+        .map_err(StakingError::ContractNotConfigured)?;
+    if let Some(parameters) = inner.staking_parameters_cache.get(compute_staking_address) {
+        return Ok(parameters);
+    }
+
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+    let compute_staking = IComputeStaking::new(compute_staking_address, &*provider);
+    let attempts = inner.config.network.rpc_max_attempts;
+
+    let (reward_rate, min_stake, cooldown, penalty) = tokio::join!(
+        with_retry(attempts, || async {
+            compute_staking.rewardRate().call().await.map(|r| r._0)
+        }),
+        with_retry(attempts, || async {
+            compute_staking.minStake().call().await.map(|r| r._0)
+        }),
+        with_retry(attempts, || async {
+            compute_staking.unstakeCooldown().call().await.map(|r| r._0)
+        }),
+        with_retry(attempts, || async {
+            compute_staking
+                .earlyUnstakePenaltyBps()
+                .call()
+                .await
+                .map(|r| r._0)
+        }),
+    );
+
+    let parameters = StakingParameters {
+        reward_rate_wei: optional_parameter("reward rate", reward_rate)?.map(|v| v.to_string()),
+        min_stake_wei: optional_parameter("minimum stake", min_stake)?.map(|v| v.to_string()),
+        unstake_cooldown_secs: optional_parameter("unstake cooldown", cooldown)?
+            .map(|v| v.saturating_to()),
+        early_unstake_penalty_bps: optional_parameter("unstake penalty", penalty)?
+            .map(|v| v.saturating_to()),
+    };
+    inner
+        .staking_parameters_cache
+        .insert(compute_staking_address, parameters.clone());
+    Ok(parameters)
+}
+
+/// A parameter getter's value, or `None` if the contract doesn't have it.
+/// Getters that don't exist revert or return no data; anything else is a real
+/// failure and isn't cached.
+fn optional_parameter<T>(
+    name: &str,
+    result: Result<T, alloy::contract::Error>,
+) -> Result<Option<T>, StakingError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if is_transient(&e) => Err(call_error(&format!("Failed to read {}", name), &e)),
+        Err(
+            alloy::contract::Error::TransportError(RpcError::ErrorResp(_))
+            | alloy::contract::Error::AbiError(_),
+        ) => Ok(None),
+        Err(e) => Err(call_error(&format!("Failed to read {}", name), &e)),
+    }
+}
+
+/// Minimum compute stake: the contract's when it exposes one, otherwise the
+/// configured minimum for the current chain
+async fn min_compute_stake(inner: &AppStateInner) -> Result<U256, StakingError> {
+    let from_contract = staking_parameters(inner)
+        .await
+        .ok()
+        .and_then(|parameters| parameters.min_stake_wei)
+        .and_then(|min_stake| U256::from_str(&min_stake).ok());
+    if let Some(min_stake) = from_contract {
+        return Ok(min_stake);
+    }
+
+    let chain_id = inner.config.network.chain_id;
+    U256::from_str(inner.config.staking.min_compute_stake_wei(chain_id))
+        .map_err(|e| StakingError::Config(format!("Invalid minimum stake in config: {}", e)))
+}
+
+/// Unstake cooldown: the contract's when it exposes one, otherwise
+/// `staking.unstake_cooldown_secs` from the config
+async fn unstake_cooldown_secs(inner: &AppStateInner) -> u64 {
+    staking_parameters(inner)
+        .await
+        .ok()
+        .and_then(|parameters| parameters.unstake_cooldown_secs)
+        .unwrap_or(inner.config.staking.unstake_cooldown_secs)
+}
+
+/// Forecast compute staking rewards, extrapolated from the rate rewards have
+/// accrued at since `stakedAt`, or from the contract's `rewardRate` while the
+/// stake is too new for that. An estimate only: it assumes the reward rate
+/// and the stake stay constant, and reads low after a claim (like `apr`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewardsProjection {
+    pub staked_wei: String,
+    pub per_day_wei: String,
+    pub per_week_wei: String,
+    /// 30 days
+    pub per_month_wei: String,
+    pub per_day_usd: f64,
+    pub per_week_usd: f64,
+    pub per_month_usd: f64,
+    /// False when there is no stake, or it is too new to measure a rate and
+    /// the contract doesn't expose one; every amount is zero then
+    pub rate_available: bool,
+    pub prices_stale: bool,
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn get_rewards_projection(
+    state: State<'_, AppState>,
+) -> Result<RewardsProjection, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    let (staked, _, rate) = compute_accrual(&inner).await?;
+    let rate_available = rate.is_some();
+    let over = |secs: u64| rate.map_or(U256::ZERO, |rate| rate.over(secs));
+    let per_day = over(SECONDS_PER_DAY);
+    let per_week = over(7 * SECONDS_PER_DAY);
+    let per_month = over(30 * SECONDS_PER_DAY);
+
+    let mut prices_stale = false;
+    let oracle = &inner.price_oracle;
+    let per_day_usd = usd_or_stale(oracle.to_usd(per_day, "ETH").await, &mut prices_stale);
+    let per_week_usd = usd_or_stale(oracle.to_usd(per_week, "ETH").await, &mut prices_stale);
+    let per_month_usd = usd_or_stale(oracle.to_usd(per_month, "ETH").await, &mut prices_stale);
+
+    Ok(RewardsProjection {
+        staked_wei: staked.to_string(),
+        per_day_wei: per_day.to_string(),
+        per_week_wei: per_week.to_string(),
+        per_month_wei: per_month.to_string(),
+        per_day_usd,
+        per_week_usd,
+        per_month_usd,
+        rate_available,
+        prices_stale,
+    })
+}
+
+/// Rate compute rewards accrue at, as `accrued` wei every `per_secs`
+/// seconds
+#[derive(Debug, Clone, Copy)]
+struct AccrualRate {
+    accrued: U256,
+    per_secs: U256,
+}
+
+impl AccrualRate {
+    /// Rewards accrued over `secs`
+    fn over(&self, secs: u64) -> U256 {
+        self.accrued.saturating_mul(U256::from(secs)) / self.per_secs
+    }
+
+    /// Seconds until `amount` more has accrued; `None` when nothing accrues
+    fn secs_to_accrue(&self, amount: U256) -> Option<u64> {
+        if amount.is_zero() {
+            return Some(0);
+        }
+        if self.accrued.is_zero() {
+            return None;
+        }
+        Some(
+            amount
+                .saturating_mul(self.per_secs)
+                .div_ceil(self.accrued)
+                .saturating_to(),
+        )
+    }
+}
+
+/// The connected wallet's compute stake and pending rewards, and the rate
+/// rewards accrue at: measured from what accrued since `stakedAt`, or the
+/// contract's `rewardRate` while the stake is too new to measure. `None`
+/// when there's no stake or no way to tell.
+async fn compute_accrual(
+    inner: &AppStateInner,
+) -> Result<(U256, U256, Option<AccrualRate>), StakingError> {
+    let operator = query_address(inner, None).await?;
+    let compute_staking_address = inner
+        .compute_staking_address(inner.config.network.chain_id)
+        .map_err(StakingError::ContractNotConfigured)?;
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+    let compute_staking = IComputeStaking::new(compute_staking_address, &*provider);
+    let attempts = inner.config.network.rpc_max_attempts;
+
+    let stake = with_retry(attempts, || async {
+        compute_staking.getStake(operator).call().await
+    })
+    .await
+    .map_err(|e| call_error("Failed to read stake", &e))?;
+    let accrued = with_retry(attempts, || async {
+        compute_staking.pendingRewards(operator).call().await
+    })
+    .await
+    .map_err(|e| call_error("Failed to read pending rewards", &e))?
+    ._0;
+
+    if stake.amount.is_zero() {
+        return Ok((stake.amount, accrued, None));
+    }
+    let elapsed = stake_elapsed_secs(stake.stakedAt);
+    if elapsed >= MIN_RATE_SAMPLE_SECS {
+        let rate = AccrualRate {
+            accrued,
+            per_secs: U256::from(elapsed),
+        };
+        return Ok((stake.amount, accrued, Some(rate)));
+    }
+
+    // Per staked ETH per second, only needed while accruals are too short to sample
+    let rate = staking_parameters(inner)
+        .await
+        .ok()
+        .and_then(|parameters| parameters.reward_rate_wei)
+        .and_then(|rate| U256::from_str(&rate).ok())
+        .map(|rate| AccrualRate {
+            accrued: stake.amount.saturating_mul(rate),
+            per_secs: U256::from(WEI_PER_ETH),
+        });
+    Ok((stake.amount, accrued, rate))
+}
+
+/// When auto-claim is expected to next claim compute rewards: once pending
+/// rewards reach the threshold and the claim interval has passed. Assumes
+/// rewards keep accruing at their current rate, like `get_rewards_projection`;
+/// the worker claims on its next poll after that time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NextClaimEstimate {
+    pub auto_claim_enabled: bool,
+    pub pending_rewards_wei: String,
+    pub threshold_wei: String,
+    /// `None` when the rate is unknown
+    pub per_day_wei: Option<String>,
+    /// When the interval next allows a claim, as in `get_staking_info`
+    pub next_auto_claim_timestamp: Option<u64>,
+    /// When pending rewards should reach the threshold; now if they already have
+    pub threshold_reached_timestamp: Option<u64>,
+    /// The later of the two above
+    pub estimated_claim_timestamp: Option<u64>,
+    /// Set when no claim time can be given: auto-claim is off, or rewards
+    /// are below the threshold and their rate is unknown or zero
+    pub indeterminate: bool,
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn estimate_next_auto_claim(
+    state: State<'_, AppState>,
+) -> Result<NextClaimEstimate, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+
+    let (_, pending, rate) = compute_accrual(&inner).await?;
+    estimate_claim_time(
+        &inner.config.earnings,
+        pending,
+        rate,
+        chrono::Utc::now().timestamp() as u64,
+    )
+}
+
+fn estimate_claim_time(
+    earnings: &EarningsConfig,
+    pending: U256,
+    rate: Option<AccrualRate>,
+    now: u64,
+) -> Result<NextClaimEstimate, StakingError> {
+    let threshold = U256::from_str(&earnings.auto_claim_threshold_wei)
+        .map_err(|e| StakingError::Config(format!("Invalid auto-claim threshold: {}", e)))?;
+    let next_auto_claim_timestamp = auto_claim::next_claim_timestamp(earnings, now);
+    // Auto-claim never claims nothing, even with a zero threshold
+    let threshold_reached_timestamp = rate
+        .map_or(
+            (!pending.is_zero() && pending >= threshold).then_some(0),
+            |rate| rate.secs_to_accrue(threshold.max(U256::from(1)).saturating_sub(pending)),
+        )
+        .map(|secs| now.saturating_add(secs));
+    let estimated_claim_timestamp = next_auto_claim_timestamp
+        .zip(threshold_reached_timestamp)
+        .map(|(interval, threshold)| interval.max(threshold));
+
+    Ok(NextClaimEstimate {
+        auto_claim_enabled: earnings.auto_claim,
+        pending_rewards_wei: pending.to_string(),
+        threshold_wei: threshold.to_string(),
+        per_day_wei: rate.map(|rate| rate.over(SECONDS_PER_DAY).to_string()),
+        next_auto_claim_timestamp,
+        threshold_reached_timestamp,
+        estimated_claim_timestamp,
+        indeterminate: estimated_claim_timestamp.is_none(),
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, fields(wallet, chain_id, endpoint))]
+pub async fn check_staking_readiness(
+    state: State<'_, AppState>,
+) -> Result<ReadinessReport, StakingError> {
+    let inner = state.inner.read().await;
+    record_span_context(&inner);
+    Ok(readiness_report(&inner).await)
+}
+
+/// RPC, chain, contract and wallet checks; failures are reported per check
+pub(crate) async fn readiness_report(inner: &AppStateInner) -> ReadinessReport {
+    let chain_id = inner.config.network.chain_id;
+    let mut checks = Vec::new();
+
+    // Every other check needs a working RPC, so bail out of those if it's down
+    let provider = match inner.provider().await {
+        Ok(provider) => match provider.get_block_number().await {
+            Ok(block) => {
+                checks.push(ReadinessCheck::new(
+                    "rpc",
+                    Ok(format!("RPC responding at block {}", block)),
+                ));
+                Some(provider)
+            }
+            Err(e) => {
+                checks.push(ReadinessCheck::new(
+                    "rpc",
+                    Err(format!("RPC not responding: {}", e)),
+                ));
+                None
+            }
+        },
+        Err(e) => {
+            checks.push(ReadinessCheck::new("rpc", Err(e)));
+            None
+        }
+    };
+
+    if let Some(provider) = provider {
+        checks.push(ReadinessCheck::new(
+            "chain",
+            ensure_expected_chain(inner)
+                .await
+                .map(|_| format!("Connected to chain {}", chain_id))
+                .map_err(|e| e.to_string()),
+        ));
+
+        let contracts = [
+            ("compute_staking", inner.compute_staking_address(chain_id)),
+            ("node_staking", inner.node_staking_address(chain_id)),
+        ];
+        if contracts.iter().all(|(_, address)| address.is_err()) {
+            checks.push(ReadinessCheck::new(
+                "contracts",
+                Err(format!(
+                    "No staking contracts configured for chain {}",
+                    chain_id
+                )),
+            ));
+        }
+        for (id, address) in contracts {
+            let Ok(address) = address else { continue };
+            let result = match provider.get_code_at(address).await {
+                Ok(code) if !code.is_empty() => Ok(format!("Contract deployed at {}", address)),
+                Ok(_) => Err(format!("No contract code at {}", address)),
+                Err(e) => Err(format!("Failed to get code at {}: {}", address, e)),
+            };
+            checks.push(ReadinessCheck::new(id, result));
+        }
+
+        let wallet = match wallet_signer(inner) {
+            Ok(signer) => gas_funds_check(inner, &AlloyChain::new(inner), signer.address()).await,
+            Err(e) => Err(e.to_string()),
+        };
+        checks.push(ReadinessCheck::new("wallet", wallet));
+    }
+
+    ReadinessReport {
+        ready: checks.iter().all(|check| check.passed),
+        checks,
+    }
+}
+
+/// Readiness of `address` to pay for gas, in the chain's gas token
+async fn gas_funds_check(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    address: Address,
+) -> Result<String, String> {
+    let currency = gas_currency(inner).map_err(|e| e.to_string())?;
+    let balance = match inner
+        .gas_token(inner.config.network.chain_id)
+        .map_err(|e| e.to_string())?
+    {
+        Some(token) => chain.token_balance(token, address).await,
+        None => chain.balance(address).await,
+    }
+    .map_err(|e| format!("Failed to get balance: {}", e))?;
+    if balance.is_zero() {
+        return Err(format!("Wallet {} has no {} for gas", address, currency));
+    }
+
+    let decimals = token_decimals(chain, &currency)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(format!(
+        "Wallet {} has {} {}",
+        address,
+        format_token(balance, decimals),
+        currency
+    ))
+}
+
+/// Decode a compute or node staking log into a history entry
+fn decode_staking_event(log: &Log) -> Option<StakingEvent> {
+    let event = decode_known_event(log)?;
+    Some(StakingEvent {
+        kind: event.kind,
+        service_id: event.service_id.to_string(),
+        amount_wei: event.amount.to_string(),
+        tx_hash: log.transaction_hash.map(|hash| hash.to_string()),
+        block_number: log.block_number?,
+        timestamp: None,
+    })
+}
+
+/// Decode every log in a receipt for reporting on a write result
+fn decode_receipt_events(logs: &[Log]) -> Vec<DecodedEvent> {
+    logs.iter().map(decode_receipt_event).collect()
+}
+
+fn decode_receipt_event(log: &Log) -> DecodedEvent {
+    let address = format!("{:?}", log.address());
+    match decode_known_event(log) {
+        Some(event) => DecodedEvent {
+            address,
+            name: Some(event.name.to_string()),
+            fields: event
+                .fields
+                .into_iter()
+                .map(|(name, value)| EventField {
+                    name: name.to_string(),
+                    value,
+                })
+                .collect(),
+            topics: Vec::new(),
+            data: None,
+        },
+        None => DecodedEvent {
+            address,
+            name: None,
+            fields: Vec::new(),
+            topics: log.topics().iter().map(|topic| topic.to_string()).collect(),
+            data: Some(log.data().data.to_string()),
+        },
+    }
+}
+
+/// A compute or node staking event, decoded once for both the staking
+/// history and the events reported on write results
+struct KnownEvent {
+    name: &'static str,
+    kind: StakingEventKind,
+    service_id: &'static str,
+    amount: U256,
+    /// Every parameter, named as in the event's signature
+    fields: Vec<(&'static str, String)>,
+}
+
+/// Decode a log emitted by the compute or node staking contracts
+fn decode_known_event(log: &Log) -> Option<KnownEvent> {
+    let event = match *log.topic0()? {
+        IComputeStaking::Staked::SIGNATURE_HASH => {
+            let event = log.log_decode::<IComputeStaking::Staked>().ok()?.inner.data;
+            KnownEvent {
+                name: "Staked",
+                kind: StakingEventKind::Stake,
+                service_id: "compute",
+                amount: event.amount,
+                fields: vec![
+                    ("staker", format!("{:?}", event.staker)),
+                    ("amount", event.amount.to_string()),
+                    ("stakeType", event.stakeType.to_string()),
+                ],
+            }
+        }
+        IComputeStaking::Unstaked::SIGNATURE_HASH => {
+            let event = log
+                .log_decode::<IComputeStaking::Unstaked>()
+                .ok()?
+                .inner
+                .data;
+            KnownEvent {
+                name: "Unstaked",
+                kind: StakingEventKind::Unstake,
+                service_id: "compute",
+                amount: event.amount,
+                fields: vec![
+                    ("staker", format!("{:?}", event.staker)),
+                    ("amount", event.amount.to_string()),
+                ],
+            }
+        }
+        IComputeStaking::RewardsClaimed::SIGNATURE_HASH => {
+            let event = log
+                .log_decode::<IComputeStaking::RewardsClaimed>()
+                .ok()?
+                .inner
+                .data;
+            KnownEvent {
+                name: "RewardsClaimed",
+                kind: StakingEventKind::Claim,
+                service_id: "compute",
+                amount: event.amount,
+                fields: vec![
+                    ("staker", format!("{:?}", event.staker)),
+                    ("amount", event.amount.to_string()),
+                ],
+            }
+        }
+        INodeStakingManager::NodeStaked::SIGNATURE_HASH => {
+            let event = log
+                .log_decode::<INodeStakingManager::NodeStaked>()
+                .ok()?
+                .inner
+                .data;
+            KnownEvent {
+                name: "NodeStaked",
+                kind: StakingEventKind::Stake,
+                service_id: "node",
+                amount: event.amount,
+                fields: vec![
+                    ("operator", format!("{:?}", event.operator)),
+                    ("stakeToken", format!("{:?}", event.stakeToken)),
+                    ("amount", event.amount.to_string()),
+                ],
+            }
+        }
+        INodeStakingManager::NodeUnstaked::SIGNATURE_HASH => {
+            let event = log
+                .log_decode::<INodeStakingManager::NodeUnstaked>()
+                .ok()?
+                .inner
+                .data;
+            KnownEvent {
+                name: "NodeUnstaked",
+                kind: StakingEventKind::Unstake,
+                service_id: "node",
+                amount: event.amount,
+                fields: vec![
+                    ("operator", format!("{:?}", event.operator)),
+                    ("amount", event.amount.to_string()),
+                ],
+            }
+        }
+        INodeStakingManager::NodeRewardsClaimed::SIGNATURE_HASH => {
+            let event = log
+                .log_decode::<INodeStakingManager::NodeRewardsClaimed>()
+                .ok()?
+                .inner
+                .data;
+            KnownEvent {
+                name: "NodeRewardsClaimed",
+                kind: StakingEventKind::Claim,
+                service_id: "node",
+                amount: event.amount,
+                fields: vec![
+                    ("operator", format!("{:?}", event.operator)),
+                    ("amount", event.amount.to_string()),
+                ],
+            }
+        }
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// Seconds until a stake made at `staked_at` clears its lockup.
+/// A zero `staked_at` means nothing was ever staked, so there is no lockup.
+fn unstake_cooldown_remaining(staked_at: U256, cooldown_secs: u64) -> u64 {
+    if staked_at.is_zero() {
+        return 0;
+    }
+
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    staked_at
+        .saturating_to::<u64>()
+        .saturating_add(cooldown_secs)
+        .saturating_sub(now)
+}
+
+/// On-chain reads behind `get_staking_info`. Stakes are `None` when their
+/// contract isn't configured or the read failed; failures are listed in `errors`.
+#[derive(Default)]
+struct StakeReads {
+    compute_stake: Option<IComputeStaking::getStakeReturn>,
+    compute_pending: U256,
+    node_info: Option<INodeStakingManager::getNodeInfoReturn>,
+    node_pending: U256,
+    /// Registered services whose stake could be read, with pending rewards
+    registered: Vec<(ServiceDescriptor, IComputeStaking::getStakeReturn, U256)>,
+    errors: Vec<StakeReadError>,
+}
+
+impl StakeReads {
+    /// Keep a successful read, or record why it failed
+    fn note<T>(&mut self, service_id: &str, call: &str, result: Result<T, String>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                tracing::warn!("{} read for {} failed: {}", call, service_id, error);
+                self.errors.push(StakeReadError {
+                    service_id: service_id.to_string(),
+                    call: call.to_string(),
+                    error,
+                });
+                None
+            }
+        }
+    }
+
+    /// Keep a registered service's stake, along with its pending rewards if
+    /// the stake itself could be read
+    fn note_service(
+        &mut self,
+        service: &ServiceDescriptor,
+        stake: Result<IComputeStaking::getStakeReturn, String>,
+        pending: Result<U256, String>,
+    ) {
+        let Some(stake) = self.note(&service.id, "getStake", stake) else {
+            return;
+        };
+        let pending = self
+            .note(&service.id, "pendingRewards", pending)
+            .unwrap_or_default();
+        self.registered.push((service.clone(), stake, pending));
+    }
+}
+
+/// Read stakes and pending rewards in one Multicall3 round trip when the chain
+/// has one configured, otherwise call each contract individually
+async fn fetch_stake_reads(
+    inner: &AppStateInner,
+    provider: &ReadProvider,
+    operator: Address,
+    compute_staking_address: Option<Address>,
+    node_staking_address: Option<Address>,
+    registered_services: &[(ServiceDescriptor, Address)],
+    block: BlockId,
+) -> StakeReads {
+    let attempts = inner.config.network.rpc_max_attempts;
+
+    if let Ok(multicall_address) = inner.multicall_address(inner.config.network.chain_id) {
+        let mut calls = Vec::new();
+        if let Some(address) = compute_staking_address {
+            calls.push(call3(
+                address,
+                IComputeStaking::getStakeCall { staker: operator }.abi_encode(),
+            ));
+            calls.push(call3(
+                address,
+                IComputeStaking::pendingRewardsCall { staker: operator }.abi_encode(),
+            ));
+        }
+        if let Some(address) = node_staking_address {
+            calls.push(call3(
+                address,
+                INodeStakingManager::getNodeInfoCall { operator }.abi_encode(),
+            ));
+            calls.push(call3(
+                address,
+                INodeStakingManager::nodePendingRewardsCall { operator }.abi_encode(),
+            ));
+        }
+        for (_, address) in registered_services {
+            calls.push(call3(
+                *address,
+                IComputeStaking::getStakeCall { staker: operator }.abi_encode(),
+            ));
+            calls.push(call3(
+                *address,
+                IComputeStaking::pendingRewardsCall { staker: operator }.abi_encode(),
+            ));
+        }
+
+        match multicall(provider, multicall_address, calls, attempts, block).await {
+            Ok(results) => {
+                let mut results = results.into_iter();
+                let mut reads = StakeReads::default();
+                if compute_staking_address.is_some() {
+                    reads.compute_stake = reads.note(
+                        "compute",
+                        "getStake",
+                        decode_return::<IComputeStaking::getStakeCall>(results.next()),
+                    );
+                    reads.compute_pending = reads
+                        .note(
+                            "compute",
+                            "pendingRewards",
+                            decode_return::<IComputeStaking::pendingRewardsCall>(results.next()),
+                        )
+                        .map(|r| r._0)
+                        .unwrap_or_default();
+                }
+                if node_staking_address.is_some() {
+                    reads.node_info = reads.note(
+                        "node",
+                        "getNodeInfo",
+                        decode_return::<INodeStakingManager::getNodeInfoCall>(results.next()),
+                    );
+                    reads.node_pending = reads
+                        .note(
+                            "node",
+                            "nodePendingRewards",
+                            decode_return::<INodeStakingManager::nodePendingRewardsCall>(
+                                results.next(),
+                            ),
+                        )
+                        .map(|r| r._0)
+                        .unwrap_or_default();
+                }
+                for (service, _) in registered_services {
+                    let stake = reads.note(
+                        &service.id,
+                        "getStake",
+                        decode_return::<IComputeStaking::getStakeCall>(results.next()),
+                    );
+                    let pending = reads
+                        .note(
+                            &service.id,
+                            "pendingRewards",
+                            decode_return::<IComputeStaking::pendingRewardsCall>(results.next()),
+                        )
+                        .map(|r| r._0)
+                        .unwrap_or_default();
+                    if let Some(stake) = stake {
+                        reads.registered.push((service.clone(), stake, pending));
+                    }
+                }
+                return reads;
+            }
+            Err(e) => tracing::warn!("Multicall failed, reading stakes one by one: {}", e),
+        }
+    }
+
+    // Without a multicall, issue every read at once rather than one after another
+    let compute = async {
+        let compute_staking = IComputeStaking::new(compute_staking_address?, provider);
+        let (stake, pending) = tokio::join!(
+            with_retry(attempts, || async {
+                compute_staking.getStake(operator).block(block).call().await
+            }),
+            with_retry(attempts, || async {
+                compute_staking
+                    .pendingRewards(operator)
+                    .block(block)
+                    .call()
+                    .await
+            }),
+        );
+        Some((
+            stake.map_err(|e| revert_reason(&e)),
+            pending.map(|r| r._0).map_err(|e| revert_reason(&e)),
+        ))
+    };
+    let node = async {
+        let node_staking = INodeStakingManager::new(node_staking_address?, provider);
+        let (info, pending) = tokio::join!(
+            with_retry(attempts, || async {
+                node_staking.getNodeInfo(operator).block(block).call().await
+            }),
+            with_retry(attempts, || async {
+                node_staking
+                    .nodePendingRewards(operator)
+                    .block(block)
+                    .call()
+                    .await
+            }),
+        );
+        Some((
+            info.map_err(|e| revert_reason(&e)),
+            pending.map(|r| r._0).map_err(|e| revert_reason(&e)),
+        ))
+    };
+    let registered = join_all(registered_services.iter().map(|(_, address)| async move {
+        let staking = IComputeStaking::new(*address, provider);
+        let (stake, pending) = tokio::join!(
+            with_retry(attempts, || async {
+                staking.getStake(operator).block(block).call().await
+            }),
+            with_retry(attempts, || async {
+                staking.pendingRewards(operator).block(block).call().await
+            }),
+        );
+        (
+            stake.map_err(|e| revert_reason(&e)),
+            pending.map(|r| r._0).map_err(|e| revert_reason(&e)),
+        )
+    }));
+    let (compute, node, registered) = tokio::join!(compute, node, registered);
+
+    let mut reads = StakeReads::default();
+    if let Some((stake, pending)) = compute {
+        reads.compute_stake = reads.note("compute", "getStake", stake);
+        if reads.compute_stake.is_some() {
+            reads.compute_pending = reads
+                .note("compute", "pendingRewards", pending)
+                .unwrap_or_default();
+        }
+    }
+    if let Some((info, pending)) = node {
+        reads.node_info = reads.note("node", "getNodeInfo", info);
+        if reads.node_info.is_some() {
+            reads.node_pending = reads
+                .note("node", "nodePendingRewards", pending)
+                .unwrap_or_default();
+        }
+    }
+    for ((service, _), (stake, pending)) in registered_services.iter().zip(registered) {
+        reads.note_service(service, stake, pending);
+    }
+
+    reads
+}
+
+fn call3(target: Address, call_data: Vec<u8>) -> IMulticall3::Call3 {
+    IMulticall3::Call3 {
+        target,
+        allowFailure: true,
+        callData: call_data.into(),
+    }
+}
+
+/// Run view calls through Multicall3's `aggregate3` in a single `eth_call`.
+/// Each entry is the call's return data, or its revert data if it reverted.
+async fn multicall(
+    provider: &ReadProvider,
+    multicall_address: Address,
+    calls: Vec<IMulticall3::Call3>,
+    attempts: u32,
+    block: BlockId,
+) -> Result<Vec<Result<Bytes, Bytes>>, StakingError> {
+    let multicall = IMulticall3::new(multicall_address, provider);
+    let results = with_retry(attempts, || async {
+        multicall
+            .aggregate3(calls.clone())
+            .block(block)
+            .call()
+            .await
+    })
+    .await
+    .map_err(|e| call_error("Multicall failed", &e))?
+    .returnData;
+
+    Ok(results
+        .into_iter()
+        .map(|result| {
+            if result.success {
+                Ok(result.returnData)
+            } else {
+                Err(result.returnData)
+            }
+        })
+        .collect())
+}
+
+/// Decode one multicall result, or describe why it has no usable value
+fn decode_return<C: SolCall>(result: Option<Result<Bytes, Bytes>>) -> Result<C::Return, String> {
+    match result {
+        Some(Ok(data)) if data.is_empty() => {
+            // Calls to addresses without code succeed with no data
+            Err(
+                "Call returned no data; is the contract deployed at the configured address?"
+                    .to_string(),
+            )
+        }
+        Some(Ok(data)) => C::abi_decode_returns(&data, true)
+            .map_err(|e| format!("Failed to decode return data: {}", e)),
+        Some(Err(revert)) => Err(decode_revert_reason(&revert)
+            .map(|reason| format!("Reverted: {}", reason))
+            .unwrap_or_else(|| "Reverted without a reason".to_string())),
+        None => Err("Missing from multicall response".to_string()),
+    }
+}
+
+/// Seconds since a stake made at `staked_at`, zero if nothing was staked
+fn stake_elapsed_secs(staked_at: U256) -> u64 {
+    if staked_at.is_zero() {
+        return 0;
+    }
+
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    now.saturating_sub(staked_at.saturating_to::<u64>())
+}
+
+/// Annualize rewards accrued over `elapsed_secs` into `(apr, apy)` fractions.
+/// Rewards and stake must be in the same token. Samples shorter than
+/// `MIN_RATE_SAMPLE_SECS` are too noisy to extrapolate a year from.
+fn annualized_rates(staked: U256, accrued: U256, elapsed_secs: u64) -> Option<(f64, f64)> {
+    if staked.is_zero() || elapsed_secs < MIN_RATE_SAMPLE_SECS {
+        return None;
+    }
+
+    let staked: f64 = format_units(staked, 18).ok()?.parse().ok()?;
+    let accrued: f64 = format_units(accrued, 18).ok()?.parse().ok()?;
+
+    let apr = accrued / staked * (SECONDS_PER_YEAR / elapsed_secs as f64);
+    let apy = (1.0 + apr / 365.0).powi(365) - 1.0;
+
+    Some((apr, apy))
+}
+
+/// Stake entry for the compute staking contract, staked in `stake_token`
+/// (native ETH when `None`); `staked_usd` is left for the caller to price
+fn compute_stake_info(
+    stake: &IComputeStaking::getStakeReturn,
+    stake_token: Option<Address>,
+    pending: U256,
+    min_stake_wei: &str,
+    cooldown_secs: u64,
+) -> ServiceStakeInfo {
+    ServiceStakeInfo {
+        service_id: "compute".to_string(),
+        service_name: "Compute Provider".to_string(),
+        staked_wei: stake.amount.to_string(),
+        staked_usd: 0.0,
+        pending_rewards_wei: pending.to_string(),
+        stake_token: stake_token.map_or_else(|| "ETH".to_string(), |token| format!("{:?}", token)),
+        stake_kind: Some(StakeKind::from(stake.stakeType)),
+        min_stake_wei: min_stake_wei.to_string(),
+        rpc_url: None,
+        region: None,
+        requests_served: None,
+        positions: stake_positions(stake, cooldown_secs),
+    }
+}
+
+/// Positions behind a `getStake` result: the single position it describes,
+/// or none when nothing is staked
+fn stake_positions(
+    stake: &IComputeStaking::getStakeReturn,
+    cooldown_secs: u64,
+) -> Vec<StakePosition> {
+    if stake.amount.is_zero() {
+        return Vec::new();
+    }
+
+    let cooldown_remaining = unstake_cooldown_remaining(stake.stakedAt, cooldown_secs);
+    vec![StakePosition {
+        position_id: 0,
+        staked_wei: stake.amount.to_string(),
+        staked_at: stake.stakedAt.saturating_to(),
+        cooldown_remaining_seconds: cooldown_remaining,
+        can_unstake: !stake.stakedAt.is_zero() && cooldown_remaining == 0,
+    }]
+}
+
+/// Amount a stake request sends: `amount_wei` when given, otherwise
+/// `amount_usd` converted at a freshly fetched price, which is returned with it
+async fn stake_amount(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    request: &StakeRequest,
+) -> Result<(U256, Option<f64>), StakingError> {
+    let amount_usd = match request.amount_usd {
+        Some(amount_usd) if request.amount_wei.trim().is_empty() => amount_usd,
+        Some(_) => {
+            tracing::warn!("Both amount_wei and amount_usd given; using amount_wei");
+            return Ok((parse_wei(&request.amount_wei)?, None));
+        }
+        None if request.amount_wei.trim().is_empty() => {
+            return Err(StakingError::InvalidAmount(
+                "Either amount_wei or amount_usd is required".to_string(),
+            ));
+        }
+        None => return Ok((parse_wei(&request.amount_wei)?, None)),
+    };
+    if !amount_usd.is_finite() || amount_usd <= 0.0 {
+        return Err(StakingError::InvalidAmount(
+            "USD amount must be greater than zero".to_string(),
+        ));
+    }
+
+    let token = request.token_address.as_deref().unwrap_or("ETH");
+    let max_age = Duration::from_secs(inner.config.prices.max_quote_age_secs);
+    let price = inner
+        .price_oracle
+        .fresh_usd_price(token, max_age)
+        .await
+        .map_err(StakingError::PriceUnavailable)?;
+    let decimals = token_decimals(chain, token).await?;
+    Ok((usd_to_units(amount_usd, price, decimals)?, Some(price)))
+}
+
+/// Amount in a token's smallest unit worth `amount_usd` at `price` dollars
+/// per whole token
+fn usd_to_units(amount_usd: f64, price: f64, decimals: u8) -> Result<U256, StakingError> {
+    if !price.is_finite() || price <= 0.0 {
+        return Err(StakingError::PriceUnavailable(format!(
+            "Invalid price: {}",
+            price
+        )));
+    }
+
+    let whole_tokens = format!("{:.*}", usize::from(decimals), amount_usd / price);
+    parse_units(&whole_tokens, decimals)
+        .map(Into::into)
+        .map_err(|e| StakingError::InvalidAmount(format!("Invalid USD amount: {}", e)))
+}
+
+/// Parse a user-supplied wei amount. Input is decimal wei; a `0x` prefix
+/// selects hex. Fractions, signs and exponents are rejected rather than
+/// guessed at, since they usually mean an ETH value that was never converted.
+fn parse_wei(input: &str) -> Result<U256, StakingError> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(StakingError::InvalidAmount("Amount is empty".to_string()));
+    }
+    if trimmed.starts_with('-') {
+        return Err(StakingError::InvalidAmount(format!(
+            "Amount can't be negative: \"{}\"",
+            trimmed
+        )));
+    }
+
+    let (digits, radix) = match trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+    {
+        Some(hex) => (hex, 16),
+        None => (trimmed, 10),
+    };
+
+    if digits.contains('.') {
+        return Err(StakingError::InvalidAmount(format!(
+            "Amount must be a whole number of wei, got \"{}\"; convert ETH to wei first",
+            trimmed
+        )));
+    }
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(StakingError::InvalidAmount(format!(
+            "Amount must be {} wei, got \"{}\"",
+            if radix == 16 { "hex" } else { "decimal" },
+            trimmed
+        )));
+    }
+
+    U256::from_str_radix(digits, radix as u64)
+        .map_err(|_| StakingError::InvalidAmount(format!("Amount {} is too large", trimmed)))
+}
+
+/// Reject zero and below-minimum stakes before touching the chain
+fn check_stake_amount(amount: U256, min_stake: U256, is_eth: bool) -> Result<(), StakingError> {
+    if amount.is_zero() {
+        return Err(StakingError::InvalidAmount(
+            "Stake amount must be greater than zero".to_string(),
+        ));
+    }
+
+    if amount < min_stake {
+        let unit = if is_eth { "ETH" } else { "tokens" };
+        return Err(StakingError::InvalidAmount(format!(
+            "Stake amount too low: minimum stake is {} {}",
+            format_ether(min_stake),
+            unit
+        )));
+    }
+
+    Ok(())
+}
+
+/// Reject a stake for an address that already has one
+fn check_no_existing_stake(existing: U256) -> Result<(), StakingError> {
+    if existing.is_zero() {
+        return Ok(());
+    }
+
+    Err(StakingError::AlreadyStaked {
+        staked_wei: existing.to_string(),
+    })
+}
+
+/// Reject a top-up for an address with nothing staked
+fn check_existing_stake(existing: U256) -> Result<(), StakingError> {
+    if existing.is_zero() {
+        return Err(StakingError::NoActiveStake);
+    }
+
+    Ok(())
+}
+
+/// Format an 18-decimal amount without trailing zeros, e.g. "0.1"
+fn format_ether(amount: U256) -> String {
+    format_token(amount, 18)
+}
+
+/// Format an amount with `decimals` decimal places without trailing zeros
+fn format_token(amount: U256, decimals: u8) -> String {
+    let formatted = format_units(amount, decimals).unwrap_or_else(|_| amount.to_string());
+    if formatted.contains('.') {
+        formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    } else {
+        formatted
+    }
+}
+
+/// Unwrap a USD conversion, falling back to zero and flagging stale prices
+fn usd_or_stale(value: Result<f64, String>, prices_stale: &mut bool) -> f64 {
+    value.unwrap_or_else(|e| {
+        tracing::warn!("Price lookup failed: {}", e);
+        *prices_stale = true;
+        0.0
+    })
+}
+
+/// USD value of `amount` of `token` in the token's smallest unit. `token` is
+/// "ETH", a token address, whose `decimals()` is read, or a token configured
+/// by symbol, which is taken to have 18 decimals like ETH.
+async fn token_usd(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    amount: U256,
+    token: &str,
+) -> Result<f64, String> {
+    if amount.is_zero() {
+        return Ok(0.0);
+    }
+
+    let decimals = token_decimals(chain, token)
+        .await
+        .map_err(|e| e.to_string())?;
+    inner
+        .price_oracle
+        .units_to_usd(amount, token, decimals)
+        .await
+}
+
+/// Decimals of `token`: "ETH", a token address, whose `decimals()` is read,
+/// or a token configured by symbol, taken to have 18 like ETH
+async fn token_decimals(chain: &dyn StakingChain, token: &str) -> Result<u8, StakingError> {
+    match Address::from_str(token) {
+        Ok(address) => chain.token_decimals(address).await,
+        Err(_) => Ok(18),
+    }
+}
+
+/// Estimate gas for a call and price it at the current max fee.
+/// Falls back to the legacy gas price on chains without EIP-1559.
+async fn estimate_gas_cost<T, P, D>(
+    provider: &impl Provider<T>,
+    call: &CallBuilder<T, P, D>,
+) -> Result<GasEstimate, StakingError>
+where
+    T: Transport + Clone,
+    P: Provider<T>,
+    D: CallDecoder,
+{
+    let gas_limit = call
+        .estimate_gas()
+        .await
+        .map_err(|e| call_error("Failed to estimate gas", &e))?;
+
+    let max_fee_per_gas = match provider.estimate_eip1559_fees(None).await {
+        Ok(fees) => fees.max_fee_per_gas,
+        Err(_) => provider
+            .get_gas_price()
+            .await
+            .map_err(|e| StakingError::RpcError(format!("Failed to get gas price: {}", e)))?,
+    };
+
+    Ok(GasEstimate {
+        gas_limit: gas_limit as u64,
+        max_fee_per_gas: max_fee_per_gas.to_string(),
+        estimated_cost_wei: (U256::from(gas_limit) * U256::from(max_fee_per_gas)).to_string(),
+    })
+}
+
+/// Fail if the sender of `tx` can't cover `value` plus its estimated gas,
+/// paid in the chain's gas token where one is configured
+async fn ensure_funds_cover(
+    inner: &AppStateInner,
+    chain: &dyn StakingChain,
+    tx: &TransactionRequest,
+    value: U256,
+) -> Result<(), StakingError> {
+    let sender = tx.from.unwrap_or_default();
+    let cost = chain.estimate_gas_cost(tx).await?.cost();
+    let balance = chain.balance(sender).await?;
+    let gas_balance = match inner
+        .gas_token(inner.config.network.chain_id)
+        .map_err(StakingError::Config)?
+    {
+        Some(token) => Some((token, chain.token_balance(token, sender).await?)),
+        None => None,
+    };
+
+    check_funds(value, cost, balance, gas_balance)
+}
+
+/// Check the wallet can pay `value` and `gas_cost`. `gas_balance` is the gas
+/// token and the wallet's balance of it on chains that charge gas in one;
+/// otherwise both come out of the native balance.
+fn check_funds(
+    value: U256,
+    gas_cost: U256,
+    native_balance: U256,
+    gas_balance: Option<(Address, U256)>,
+) -> Result<(), StakingError> {
+    let Some((token, available)) = gas_balance else {
+        return check_native_balance(value.saturating_add(gas_cost), native_balance);
+    };
+
+    check_native_balance(value, native_balance)?;
+    if gas_cost > available {
+        return Err(StakingError::InsufficientGasToken {
+            token: format!("{:?}", token),
+            required: gas_cost.to_string(),
+            available: available.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn check_native_balance(required: U256, balance: U256) -> Result<(), StakingError> {
+    if required > balance {
+        return Err(StakingError::InsufficientBalance {
+            token: "ETH".to_string(),
+            required_wei: required.to_string(),
+            available_wei: balance.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// A transaction that reached the mempool, and how it ended.
+/// `error` is set when it reverted or was not mined in time.
+pub(crate) struct SubmittedTx {
+    pub tx_hash: TxHash,
+    pub error: Option<String>,
+    /// Logs from the receipt; empty unless the transaction succeeded
+    pub logs: Vec<Log>,
+    /// Gas paid according to the receipt; `None` unless it was mined and kept
+    pub gas_cost: Option<U256>,
+}
+
+/// Gas cost as the wei and USD fields reported on results
+async fn gas_cost_fields(
+    inner: &AppStateInner,
+    gas_cost: Option<U256>,
+) -> (Option<String>, Option<f64>) {
+    let Some(gas_cost) = gas_cost else {
+        return (None, None);
+    };
+
+    let usd = match gas_currency(inner) {
+        Ok(currency) => token_usd(inner, &AlloyChain::new(inner), gas_cost, &currency).await,
+        Err(e) => Err(e.to_string()),
+    };
+    let usd = match usd {
+        Ok(usd) => Some(usd),
+        Err(e) => {
+            tracing::warn!("Price lookup failed: {}", e);
+            None
+        }
+    };
+    (Some(gas_cost.to_string()), usd)
+}
+
+/// Sum service stakes per token, in order of first appearance
+fn token_totals(stakes: &[ServiceStakeInfo]) -> Vec<TokenTotal> {
+    sum_by_token(stakes.iter().map(|stake| {
+        (
+            stake.stake_token.as_str(),
+            U256::from_str(&stake.staked_wei).unwrap_or_default(),
+            stake.staked_usd,
+        )
+    }))
+}
+
+/// The native ETH entry of per-token totals, or "0"
+fn native_total(totals: &[TokenTotal]) -> String {
+    totals
+        .iter()
+        .find(|total| total.token.eq_ignore_ascii_case("ETH"))
+        .map_or_else(|| "0".to_string(), |total| total.amount_wei.clone())
+}
+
+/// Sum (token, amount, USD value) entries per token, in order of first appearance
+fn sum_by_token<'a>(amounts: impl IntoIterator<Item = (&'a str, U256, f64)>) -> Vec<TokenTotal> {
+    let mut totals: Vec<(String, U256, f64)> = Vec::new();
+    for (token, amount, amount_usd) in amounts {
+        match totals
+            .iter_mut()
+            .find(|(total_token, _, _)| total_token.eq_ignore_ascii_case(token))
+        {
+            Some((_, total, total_usd)) => {
+                *total = total.saturating_add(amount);
+                *total_usd += amount_usd;
+            }
+            None => totals.push((token.to_string(), amount, amount_usd)),
+        }
+    }
+
+    totals
+        .into_iter()
+        .map(|(token, amount, amount_usd)| TokenTotal {
+            token,
+            amount_wei: amount.to_string(),
+            amount_usd,
+        })
+        .collect()
+}
+
+/// Staking contract backing a claimable service on the configured chain
+fn service_contract(inner: &AppStateInner, service_id: &str) -> Result<Address, StakingError> {
+    let chain_id = inner.config.network.chain_id;
+    match service_id {
+        "compute" => inner
+            .compute_staking_address(chain_id)
+            .map_err(StakingError::ContractNotConfigured),
+        "node" => inner
+            .node_staking_address(chain_id)
+            .map_err(StakingError::ContractNotConfigured),
+        other => {
+            let service = inner
+                .registered_services(chain_id)
+                .iter()
+                .find(|service| service.id == other)
+                .ok_or_else(|| StakingError::UnknownService(other.to_string()))?;
+            parse_contract_address(&service.contract_address).map_err(|e| {
+                StakingError::ContractNotConfigured(format!(
+                    "Invalid contract for service {}: {}",
+                    other, e
+                ))
+            })
+        }
+    }
+}
+
+/// `bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)`
+const EIP1967_IMPLEMENTATION_SLOT: U256 = U256::from_be_bytes(alloy::primitives::hex!(
+    "360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc"
+));
+
+/// Implementation behind an EIP-1967 proxy, read from its storage slot.
+/// `None` when the slot is empty, i.e. `address` isn't such a proxy.
+async fn proxy_implementation(
+    provider: &ReadProvider,
+    address: Address,
+) -> Result<Option<Address>, StakingError> {
+    let slot = provider
+        .get_storage_at(address, EIP1967_IMPLEMENTATION_SLOT)
+        .await
+        .map_err(|e| StakingError::RpcError(format!("Failed to read proxy slot: {}", e)))?;
+
+    // The address sits in the low 20 bytes of the word
+    let implementation = Address::from_word(slot.to_be_bytes::<32>().into());
+    Ok((!implementation.is_zero()).then_some(implementation))
+}
+
+/// Every service that can be staked on the current chain: compute and node
+/// staking where deployed, then any registered in config. The compute
+/// minimum is `min_compute_stake`'s, the one `stake` enforces.
+pub(crate) async fn service_descriptors(inner: &AppStateInner) -> Vec<ServiceDescriptor> {
+    let chain_id = inner.config.network.chain_id;
+    let mut services = Vec::new();
+
+    if let Ok(address) = inner.compute_staking_address(chain_id) {
+        services.push(ServiceDescriptor {
+            id: "compute".to_string(),
+            name: "Compute Provider".to_string(),
+            contract_address: address.to_string(),
+            stake_token: "ETH".to_string(),
+            min_stake_wei: match min_compute_stake(inner).await {
+                Ok(min_stake) => min_stake.to_string(),
+                Err(_) => inner
+                    .config
+                    .staking
+                    .min_compute_stake_wei(chain_id)
+                    .to_string(),
+            },
+        });
+    }
+    if let Ok(address) = inner.node_staking_address(chain_id) {
+        services.push(ServiceDescriptor {
+            id: "node".to_string(),
+            name: "Node Operator".to_string(),
+            contract_address: address.to_string(),
+            stake_token: "JEJU".to_string(),
+            min_stake_wei: "1000000000000000000000".to_string(), // 1000 JEJU minimum
+        });
+    }
+
+    services.extend(
+        registered_service_contracts(inner)
+            .into_iter()
+            .map(|(service, _)| service),
+    );
+    services
+}
+
+/// Id of the service whose staking contract is at `address`
+fn service_at(inner: &AppStateInner, address: Address) -> Option<String> {
+    let chain_id = inner.config.network.chain_id;
+    if inner.compute_staking_address(chain_id).ok() == Some(address) {
+        return Some("compute".to_string());
+    }
+    if inner.node_staking_address(chain_id).ok() == Some(address) {
+        return Some("node".to_string());
+    }
+    registered_service_contracts(inner)
+        .into_iter()
+        .find(|(_, contract)| *contract == address)
+        .map(|(service, _)| service.id)
+}
+
+/// Registered services with a usable contract address. Entries reusing a
+/// built-in id are skipped so they can't shadow compute or node staking.
+fn registered_service_contracts(inner: &AppStateInner) -> Vec<(ServiceDescriptor, Address)> {
+    inner
+        .registered_services(inner.config.network.chain_id)
+        .iter()
+        .filter(|service| service.id != "compute" && service.id != "node")
+        .filter_map(
+            |service| match parse_contract_address(&service.contract_address) {
+                Ok(address) => Some((service.clone(), address)),
+                Err(e) => {
+                    tracing::warn!("Skipping service {}: {}", service.id, e);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// Pending staking rewards for a service and the connected wallet
+pub(crate) async fn pending_rewards(
+    inner: &AppStateInner,
+    service_id: &str,
+) -> Result<U256, StakingError> {
+    let operator = wallet_address(inner)?;
+
+    let contract_address = service_contract(inner, service_id)?;
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+
+    let attempts = inner.config.network.rpc_max_attempts;
+
+    let pending = if service_id == "node" {
+        let node_staking = INodeStakingManager::new(contract_address, &*provider);
+        with_retry(attempts, || async {
+            node_staking.nodePendingRewards(operator).call().await
+        })
+        .await
+        .map(|r| r._0)
+    } else {
+        let compute_staking = IComputeStaking::new(contract_address, &*provider);
+        with_retry(attempts, || async {
+            compute_staking.pendingRewards(operator).call().await
+        })
+        .await
+        .map(|r| r._0)
+    };
+
+    pending.map_err(|e| StakingError::RpcError(format!("Failed to get pending rewards: {}", e)))
+}
+
+/// Claim a service's staking rewards with the connected wallet and wait for the
+/// receipt. Rewards go to `recipient` when given, which only compute staking supports.
+pub(crate) async fn send_claim(
+    inner: &AppStateInner,
+    service_id: &str,
+    fees: &FeeOverrides,
+    simulate: bool,
+    recipient: Option<Address>,
+) -> Result<SubmittedTx, StakingError> {
+    let signer = wallet_signer(inner)?;
+    let operator = signer.address();
+
+    let contract_address = service_contract(inner, service_id)?;
+    ensure_expected_chain(inner).await?;
+    ensure_not_paused(&AlloyChain::new(inner), contract_address).await?;
+
+    let provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(EthereumWallet::from(signer.clone()))
+        .on_client(inner.rpc_client().await.map_err(StakingError::Config)?);
+
+    if service_id == "node" {
+        if recipient.is_some() {
+            return Err(StakingError::InvalidAddress(
+                "The node staking manager can only pay rewards to the operator".to_string(),
+            ));
+        }
+        let node_staking = INodeStakingManager::new(contract_address, &provider);
+        send_and_confirm(
+            inner,
+            &node_staking.nodeClaimRewards().from(operator),
+            fees,
+            simulate,
+        )
+        .await
+    } else if let Some(recipient) = recipient {
+        let compute_staking = IComputeStaking::new(contract_address, &provider);
+        send_and_confirm(
+            inner,
+            &compute_staking.claimRewardsTo(recipient).from(operator),
+            fees,
+            simulate,
+        )
+        .await
+    } else {
+        let compute_staking = IComputeStaking::new(contract_address, &provider);
+        send_and_confirm(
+            inner,
+            &compute_staking.claimRewards().from(operator),
+            fees,
+            simulate,
+        )
+        .await
+    }
+}
+
+/// Claim one service's rewards. The claimed amount comes from the receipt's
+/// claim event, falling back to the pending amount read just before claiming.
+/// With `min_amount_out`, the claim is simulated first and refused unless it
+/// would pay out at least that much (and never nothing).
+async fn claim_service_rewards(
+    inner: &AppStateInner,
+    service_id: &str,
+    fees: &FeeOverrides,
+    simulate: bool,
+    min_amount_out: Option<U256>,
+    recipient: Option<Address>,
+) -> Result<ClaimResult, StakingError> {
+    let pending = pending_rewards(inner, service_id).await?;
+    if let Some(min_amount_out) = min_amount_out {
+        let min_amount_out = min_amount_out.max(U256::from(1));
+        let amount_out = simulate_claim(inner, service_id).await?;
+        if amount_out < min_amount_out {
+            return Err(StakingError::BelowMinimumOutput {
+                min_wei: min_amount_out.to_string(),
+                actual_wei: amount_out.to_string(),
+            });
+        }
+    }
+    let submitted = send_claim(inner, service_id, fees, simulate, recipient).await?;
+    let success = submitted.error.is_none();
+
+    let amount_claimed = if success {
+        let contract_address = service_contract(inner, service_id)?;
+        claimed_from_logs(&submitted.logs, contract_address).unwrap_or(pending)
+    } else {
+        U256::ZERO
+    };
+
+    let (gas_cost_wei, gas_cost_usd) = gas_cost_fields(inner, submitted.gas_cost).await;
+    Ok(ClaimResult {
+        service_id: service_id.to_string(),
+        success,
+        tx_hash: Some(submitted.tx_hash.to_string()),
+        amount_claimed_wei: amount_claimed.to_string(),
+        error: submitted.error,
+        recipient: recipient.map(|recipient| format!("{:?}", recipient)),
+        recipient_name: match recipient {
+            Some(recipient) => ens::lookup_address(inner, recipient).await,
+            None => None,
+        },
+        gas_cost_wei,
+        gas_cost_usd,
+        events: decode_receipt_events(&submitted.logs),
+        skip_reason: None,
+    })
+}
+
+/// Amount a claim would pay out right now, from an `eth_call` of the claim
+async fn simulate_claim(inner: &AppStateInner, service_id: &str) -> Result<U256, StakingError> {
+    let operator = wallet_address(inner)?;
+    let contract_address = service_contract(inner, service_id)?;
+    let provider = inner.provider().await.map_err(StakingError::Config)?;
+
+    let amount = if service_id == "node" {
+        INodeStakingManager::new(contract_address, &*provider)
+            .nodeClaimRewards()
+            .from(operator)
+            .call()
+            .await
+            .map(|r| r._0)
+    } else {
+        IComputeStaking::new(contract_address, &*provider)
+            .claimRewards()
+            .from(operator)
+            .call()
+            .await
+            .map(|r| r._0)
+    };
+    amount.map_err(|e| call_error("Claim would revert", &e))
+}
+
+/// Sum the claim events `contract_address` emitted, or `None` if there were none
+fn claimed_from_logs(logs: &[Log], contract_address: Address) -> Option<U256> {
+    logs.iter()
+        .filter(|log| log.address() == contract_address)
+        .filter_map(|log| {
+            if let Ok(event) = log.log_decode::<IComputeStaking::RewardsClaimed>() {
+                return Some(event.inner.amount);
+            }
+            log.log_decode::<INodeStakingManager::NodeRewardsClaimed>()
+                .ok()
+                .map(|event| event.inner.amount)
+        })
+        .reduce(|total, amount| total + amount)
+}
+
+/// Parse an optional wei-per-gas fee
+fn parse_fee(name: &str, value: Option<&str>) -> Result<Option<u128>, StakingError> {
+    value
+        .map(|value| {
+            u128::from_str(value)
+                .map_err(|e| StakingError::InvalidAmount(format!("Invalid {}: {}", name, e)))
+        })
+        .transpose()
+}
+
+fn receipt_timeout(inner: &AppStateInner) -> Duration {
+    Duration::from_secs(inner.config.network.receipt_timeout_secs)
+}
+
+/// Broadcast a contract call with any fee overrides and wait for its receipt.
+/// With `simulate`, the call is run through `eth_call` first and a revert is
+/// returned without broadcasting. The nonce is filled under `send_lock`, which
+/// is released before waiting on the receipt.
+async fn send_and_confirm<T, P, D>(
+    inner: &AppStateInner,
+    call: &CallBuilder<T, P, D>,
+    fees: &FeeOverrides,
+    simulate: bool,
+) -> Result<SubmittedTx, StakingError>
+where
+    T: Transport + Clone,
+    P: Provider<T> + Clone,
+    D: CallDecoder + Clone,
+{
+    // Commands check this up front; this also stops background writes such as auto-claim
+    ensure_writes_enabled(inner)?;
+    let call = &fees.apply(call.clone())?;
+
+    if simulate {
+        call.call_raw()
+            .await
+            .map_err(|e| call_error("Transaction would revert", &e))?;
+    }
+    ensure_gas_price_below_ceiling(inner, &call.provider).await?;
+    // A caller-pinned nonce (a fee bump) must never move to a fresh one
+    let nonce_pinned = call.clone().into_transaction_request().nonce.is_some();
+
+    let send_guard = inner.send_lock.lock().await;
+    // Checked as late as possible, since waiting on the lock or a simulation
+    // can take a while after a sleep
+    fees.check_deadline(chrono::Utc::now().timestamp() as u64)?;
+    prompt_hardware_confirmation(inner);
+    let resubmitted;
+    let pending = match call.send().await {
+        Ok(pending) => pending,
+        // Another transaction from this wallet took the nonce; resend once with a fresh one
+        Err(e) if !nonce_pinned && is_stale_nonce(&e) => {
+            let from = call
+                .clone()
+                .into_transaction_request()
+                .from
+                .ok_or_else(|| call_error("Failed to send transaction", &e))?;
+            let nonce = call
+                .provider
+                .get_transaction_count(from)
+                .pending()
+                .await
+                .map_err(|e| StakingError::RpcError(format!("Failed to get nonce: {}", e)))?;
+
+            tracing::warn!("Nonce too low, resending with nonce {}", nonce);
+            fees.check_deadline(chrono::Utc::now().timestamp() as u64)?;
+            resubmitted = call.clone().nonce(nonce);
+            resubmitted
+                .send()
+                .await
+                .map_err(|e| call_error("Failed to send transaction", &e))?
+        }
+        Err(e) => return Err(call_error("Failed to send transaction", &e)),
+    };
+    drop(send_guard);
+
+    confirm_sent(inner, pending, call.clone().into_transaction_request()).await
+}
+
+/// Fail while the network gas price is above `network.max_gas_price_wei`
+async fn ensure_gas_price_below_ceiling<T: Transport + Clone>(
+    inner: &AppStateInner,
+    provider: &impl Provider<T>,
+) -> Result<(), StakingError> {
+    let Some(ceiling) = gas_price_ceiling(inner)? else {
+        return Ok(());
+    };
+
+    let current = provider
+        .get_gas_price()
+        .await
+        .map_err(|e| StakingError::RpcError(format!("Failed to get gas price: {}", e)))?;
+    check_gas_price(U256::from(current), ceiling)
+}
+
+/// `network.max_gas_price_wei`, if set
+fn gas_price_ceiling(inner: &AppStateInner) -> Result<Option<U256>, StakingError> {
+    inner
+        .config
+        .network
+        .max_gas_price_wei
+        .as_deref()
+        .map(U256::from_str)
+        .transpose()
+        .map_err(|e| StakingError::Config(format!("Invalid gas price ceiling: {}", e)))
+}
+
+/// Check the max fee (or legacy gas price) a signed transaction offers
+/// against the ceiling
+fn check_signed_fee(tx: &TransactionRequest, ceiling: U256) -> Result<(), StakingError> {
+    match tx.max_fee_per_gas.or(tx.gas_price) {
+        Some(fee) => check_gas_price(U256::from(fee), ceiling),
+        None => Ok(()),
+    }
+}
+
+fn check_gas_price(current: U256, ceiling: U256) -> Result<(), StakingError> {
+    if current > ceiling {
+        return Err(StakingError::GasPriceTooHigh {
+            current: current.to_string(),
+            ceiling: ceiling.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// How nodes word the rejection of a nonce that's already been used: geth,
+/// reth, anvil and hardhat; Parity/OpenEthereum; Nethermind; Besu
+const STALE_NONCE_ERRORS: [&str; 4] = [
+    "nonce too low",
+    "nonce is too low",
+    "oldnonce",
+    "nonce_too_low",
+];
+
+/// Whether the node rejected a send because its nonce was already used, as
+/// opposed to any other failure, which must not be resent
+fn is_stale_nonce(error: &alloy::contract::Error) -> bool {
+    let alloy::contract::Error::TransportError(RpcError::ErrorResp(payload)) = error else {
+        return false;
+    };
+    let message = payload.message.to_lowercase();
+    STALE_NONCE_ERRORS
+        .iter()
+        .any(|stale| message.contains(stale))
+}
+
+/// Local log entry for a broadcast staking transaction whose outcome isn't
+/// known yet. `None` for transactions that aren't staking actions, such as
+/// token approvals and cancellations.
+fn unconfirmed_log_entry(
+    inner: &AppStateInner,
+    tx_hash: TxHash,
+    request: &TransactionRequest,
+) -> Option<StakingLogEntry> {
+    let selector: [u8; 4] = request.input.input()?.get(..4)?.try_into().ok()?;
+    let action = match selector {
+        IComputeStaking::stakeAsProviderCall::SELECTOR
+        | IComputeStaking::stakeAsProviderForCall::SELECTOR
+        | IComputeStaking::stakeTokenAsProviderCall::SELECTOR => StakingAction::Stake,
+        IComputeStaking::increaseStakeCall::SELECTOR
+        | IComputeStaking::increaseTokenStakeCall::SELECTOR => StakingAction::IncreaseStake,
+        IComputeStaking::unstake_0Call::SELECTOR | IComputeStaking::unstake_1Call::SELECTOR => {
+            StakingAction::Unstake
+        }
+        IComputeStaking::claimRewardsCall::SELECTOR
+        | IComputeStaking::claimRewardsToCall::SELECTOR
+        | INodeStakingManager::nodeClaimRewardsCall::SELECTOR => StakingAction::Claim,
+        _ => return None,
+    };
+
+    let to = request.to.and_then(|to| to.to().copied());
+    let service_id = to
+        .and_then(|to| service_at(inner, to))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    Some(StakingLogEntry {
+        timestamp: chrono::Utc::now().timestamp(),
+        action,
+        service_id,
+        amount_wei: request
+            .value
+            .filter(|value| !value.is_zero())
+            .map(|value| value.to_string()),
+        tx_hash: Some(tx_hash.to_string()),
+        outcome: StakingOutcome::Pending,
+        error: None,
+    })
+}
+
+/// Wait for a broadcast transaction's receipt and confirmations, emitting its
+/// status along the way. If it reverts, `replay` is re-run as a call on the
+/// parent block to recover the reason.
+async fn confirm_sent<T>(
+    inner: &AppStateInner,
+    pending: PendingTransactionBuilder<'_, T, Ethereum>,
+    replay: TransactionRequest,
+) -> Result<SubmittedTx, StakingError>
+where
+    T: Transport + Clone,
+{
+    let tx_hash = *pending.tx_hash();
+    let required = inner.config.network.required_confirmations.max(1);
+    emit_tx_status(inner, tx_hash, TxStatus::Submitted, None, 0, required, None);
+    inner.poll_activity.note_transaction();
+    // Logged as pending if the app exits before this returns
+    let _unconfirmed = unconfirmed_log_entry(inner, tx_hash, &replay)
+        .map(|entry| inner.unconfirmed_txs.track(tx_hash, entry));
+
+    let receipt = match pending
+        .with_timeout(Some(receipt_timeout(inner)))
+        .get_receipt()
+        .await
+    {
+        Ok(receipt) => receipt,
+        Err(e) => {
+            let error = format!("Transaction not confirmed: {}", e);
+            emit_tx_status(
+                inner,
+                tx_hash,
+                TxStatus::Unconfirmed,
+                None,
+                0,
+                required,
+                Some(error.clone()),
+            );
+            return Ok(SubmittedTx {
+                tx_hash,
+                error: Some(error),
+                logs: Vec::new(),
+                gas_cost: None,
+            });
+        }
+    };
+
+    // Any mined transaction may have changed stake data
+    inner.staking_info_cache.invalidate();
+    let gas_cost = U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price);
+
+    if receipt.status() {
+        let deadline = Instant::now() + receipt_timeout(inner);
+        if let Err(event) = confirm_mined(inner, &receipt, required, deadline).await {
+            return Ok(SubmittedTx {
+                tx_hash,
+                error: event.error,
+                logs: Vec::new(),
+                gas_cost: None,
+            });
+        }
+        return Ok(SubmittedTx {
+            tx_hash,
+            error: None,
+            logs: receipt.inner.logs().to_vec(),
+            gas_cost: Some(gas_cost),
+        });
+    }
+
+    // Receipts carry no revert data, so replay the call on the parent block
+    let reason = match (receipt.block_number, inner.provider().await) {
+        (Some(block), Ok(provider)) => provider
+            .call(&replay)
+            .block(BlockId::number(block.saturating_sub(1)))
+            .await
+            .err()
+            .map(|e| revert_reason(&alloy::contract::Error::TransportError(e))),
+        _ => None,
+    };
+
+    let error = match reason {
+        Some(reason) => format!("Transaction reverted: {}", reason),
+        None => "Transaction reverted".to_string(),
+    };
+    emit_tx_status(
+        inner,
+        tx_hash,
+        TxStatus::Reverted,
+        receipt.block_number,
+        1,
+        required,
+        Some(error.clone()),
+    );
+
+    Ok(SubmittedTx {
+        tx_hash,
+        error: Some(error),
+        logs: Vec::new(),
+        gas_cost: Some(gas_cost),
+    })
+}
+
+/// How often the chain head is checked while waiting for confirmations
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Wait for a successful transaction's confirmations, then report it
+/// confirmed and watch it for reorgs. Fails with the final status event if it
+/// is reorged out or `deadline` passes first.
+async fn confirm_mined(
+    inner: &AppStateInner,
+    receipt: &TransactionReceipt,
+    required: u64,
+    deadline: Instant,
+) -> Result<TxStatusEvent, TxStatusEvent> {
+    let tx_hash = receipt.transaction_hash;
+    let block_number = wait_for_confirmations(inner, receipt, required, deadline).await?;
+
+    let event = emit_tx_status(
+        inner,
+        tx_hash,
+        TxStatus::Confirmed,
+        Some(block_number),
+        required,
+        required,
+        None,
+    );
+    inner.reorg_watcher.track(
+        inner.config.network.chain_id,
+        tx_hash,
+        block_number,
+        receipt.block_hash,
+    );
+    Ok(event)
+}
+
+/// Wait until a mined transaction has `required` confirmations, returning the
+/// block it ended up in. Fails with the final status event if it is reorged
+/// out or `deadline` passes first.
+async fn wait_for_confirmations(
+    inner: &AppStateInner,
+    receipt: &TransactionReceipt,
+    required: u64,
+    deadline: Instant,
+) -> Result<u64, TxStatusEvent> {
+    let tx_hash = receipt.transaction_hash;
+    let mut block_number = receipt.block_number.unwrap_or_default();
+    let mut block_hash = receipt.block_hash;
+    if required <= 1 {
+        return Ok(block_number);
+    }
+
+    let provider = match inner.provider().await {
+        Ok(provider) => provider,
+        Err(e) => {
+            return Err(emit_tx_status(
+                inner,
+                tx_hash,
+                TxStatus::Unconfirmed,
+                Some(block_number),
+                1,
+                required,
+                Some(format!("Failed to check confirmations: {}", e)),
+            ))
+        }
+    };
+    let mut reported = 0;
+
+    loop {
+        // A failed poll is retried on the next tick rather than failing the write
+        if let Ok(head) = provider.get_block_number().await {
+            let confirmations = head.saturating_sub(block_number) + 1;
+
+            if confirmations >= required {
+                // Make sure the transaction is still where we counted it from
+                match provider.get_transaction_receipt(tx_hash).await {
+                    Ok(Some(current)) if current.block_hash == block_hash => {
+                        return Ok(block_number)
+                    }
+                    // Re-mined in a different block after a reorg; count again
+                    Ok(Some(current)) => {
+                        block_number = current.block_number.unwrap_or(head);
+                        block_hash = current.block_hash;
+                        continue;
+                    }
+                    Ok(None) => {
+                        let error = format!(
+                            "Transaction was reorged out after {} of {} confirmations",
+                            reported, required
+                        );
+                        return Err(emit_tx_status(
+                            inner,
+                            tx_hash,
+                            TxStatus::Dropped,
+                            None,
+                            reported,
+                            required,
+                            Some(error),
+                        ));
+                    }
+                    Err(e) => tracing::debug!("Failed to re-check receipt: {}", e),
+                }
+            } else if confirmations != reported {
+                reported = confirmations;
+                emit_tx_status(
+                    inner,
+                    tx_hash,
+                    TxStatus::Confirming,
+                    Some(block_number),
+                    confirmations,
+                    required,
+                    None,
+                );
+            }
+        }
+
+        if Instant::now() >= deadline {
+            let error = format!(
+                "Transaction has {} of {} confirmations; timed out waiting for the rest",
+                reported, required
+            );
+            return Err(emit_tx_status(
+                inner,
+                tx_hash,
+                TxStatus::Unconfirmed,
+                Some(block_number),
+                reported,
+                required,
+                Some(error),
+            ));
+        }
+
+        tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await;
+    }
+}
+
+/// Tell the frontend about a transaction status change, returning the event.
+/// Best effort: a missing app handle (tests) or a failed emit never affects
+/// the transaction.
+fn emit_tx_status(
+    inner: &AppStateInner,
+    tx_hash: TxHash,
+    status: TxStatus,
+    block_number: Option<u64>,
+    confirmations: u64,
+    required_confirmations: u64,
+    error: Option<String>,
+) -> TxStatusEvent {
+    trace_tx_status(tx_hash, &status, block_number, error.as_deref());
+
+    let event = TxStatusEvent {
+        tx_hash: tx_hash.to_string(),
+        status,
+        block_number,
+        confirmations,
+        required_confirmations,
+        error,
+    };
+    if let Some(handle) = inner.app_handle.as_ref() {
+        if let Err(e) = handle.emit(TX_STATUS_EVENT, event.clone()) {
+            tracing::warn!("Failed to emit transaction status for {}: {}", tx_hash, e);
+        }
+    }
+    event
+}
+
+/// Record a transaction's progress on the current command span and log it.
+/// Reverts are logged at error with their reason, other failures at warn.
+fn trace_tx_status(
+    tx_hash: TxHash,
+    status: &TxStatus,
+    block_number: Option<u64>,
+    error: Option<&str>,
+) {
+    let span = tracing::Span::current();
+    span.record("tx_hash", tracing::field::display(tx_hash));
+
+    match status {
+        TxStatus::Submitted | TxStatus::Confirming => {
+            tracing::debug!(%tx_hash, ?block_number, "Transaction {:?}", status);
+        }
+        TxStatus::Confirmed => {
+            span.record("outcome", "confirmed");
+            tracing::info!(%tx_hash, ?block_number, "Transaction confirmed");
+        }
+        TxStatus::Reverted => {
+            span.record("outcome", "reverted");
+            tracing::error!(
+                %tx_hash,
+                ?block_number,
+                reason = error.unwrap_or("unknown"),
+                "Transaction reverted"
+            );
+        }
+        TxStatus::Unconfirmed | TxStatus::Dropped => {
+            span.record("outcome", "unconfirmed");
+            tracing::warn!(%tx_hash, ?block_number, error, "Transaction not confirmed");
+        }
+    }
+}
+
+/// Fill in the wallet, chain and endpoint fields of the current command span
+fn record_span_context(inner: &AppStateInner) {
+    let span = tracing::Span::current();
+    if span.is_disabled() {
+        return;
+    }
+
+    if let Ok(address) = wallet_address(inner) {
+        span.record("wallet", mask_address(address));
+    }
+    span.record("chain_id", inner.config.network.chain_id);
+    if let Some(endpoint) = inner.connected_rpc_url() {
+        span.record("endpoint", endpoint);
+    }
+}
+
+/// Delay before the first retry of a transient RPC failure; doubles per attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+
+/// Run a read-only contract call, retrying transient RPC failures with
+/// exponential backoff. Reverts and other deterministic errors return at once.
+async fn with_retry<R, F, Fut>(max_attempts: u32, mut call: F) -> Result<R, alloy::contract::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<R, alloy::contract::Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        match call().await {
+            Err(e) if attempt < max_attempts && is_transient(&e) => {
+                let delay = RETRY_BASE_DELAY * 2u32.pow(attempt - 1);
+                tracing::debug!(
+                    "Transient RPC error (attempt {}/{}), retrying in {:?}: {}",
+                    attempt,
+                    max_attempts,
+                    delay,
+                    e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether a failed call is worth retrying: rate limits, gateway errors,
+/// dropped connections and timeouts
+fn is_transient(error: &alloy::contract::Error) -> bool {
+    match error {
+        alloy::contract::Error::TransportError(RpcError::Transport(kind)) => match kind {
+            TransportErrorKind::HttpError(http) => matches!(http.status, 429 | 502 | 503 | 504),
+            TransportErrorKind::Custom(e) => {
+                let message = e.to_string().to_lowercase();
+                ["timed out", "timeout", "connection", "429"]
+                    .iter()
+                    .any(|needle| message.contains(needle))
+            }
+            TransportErrorKind::BackendGone | TransportErrorKind::MissingBatchResponse(_) => true,
+            _ => false,
+        },
+        alloy::contract::Error::TransportError(RpcError::ErrorResp(payload)) => {
+            // Error responses carrying revert data are deterministic
+            let message = payload.message.to_lowercase();
+            payload.data.is_none()
+                && (payload.code == 429
+                    || message.contains("rate limit")
+                    || message.contains("too many requests"))
+        }
+        _ => false,
+    }
+}
+
+/// Fail while the safety switch has staking writes turned off
+fn ensure_writes_enabled(inner: &AppStateInner) -> Result<(), StakingError> {
+    if inner.config.safety.writes_disabled {
+        return Err(StakingError::WritesDisabled);
+    }
+    if inner.shutdown.is_cancelled() {
+        return Err(StakingError::ShuttingDown);
+    }
+    Ok(())
+}
+
+/// Refuse a write to `contract` while it is paused, rather than broadcasting
+/// a transaction that would revert
+async fn ensure_not_paused(
+    chain: &dyn StakingChain,
+    contract: Address,
+) -> Result<(), StakingError> {
+    if chain.paused(contract).await? == Some(true) {
+        return Err(StakingError::ContractPaused);
+    }
+    Ok(())
+}
+
+/// Fail unless the RPC endpoint reports the chain the config expects
+async fn ensure_expected_chain(inner: &AppStateInner) -> Result<(), StakingError> {
+    let actual = inner.chain_id().await.map_err(StakingError::RpcError)?;
+    check_chain_id(inner, actual)
+}
+
+/// Fail unless `actual` is the chain the config expects
+fn check_chain_id(inner: &AppStateInner, actual: u64) -> Result<(), StakingError> {
+    let network = &inner.config.network;
+    let expected = network.expected_chain_id.unwrap_or(network.chain_id);
+
+    if actual != expected {
+        return Err(StakingError::WrongChain { actual, expected });
+    }
+
+    Ok(())
+}
+
+/// Classify a failed contract call, keeping reverts apart from transport failures
+fn call_error(context: &str, error: &alloy::contract::Error) -> StakingError {
+    let reason = revert_reason(error);
+    let message = format!("{}: {}", context, reason);
+    match error {
+        alloy::contract::Error::TransportError(RpcError::ErrorResp(payload))
+            if payload.try_data_as::<Bytes>().is_some() =>
+        {
+            tracing::error!(%reason, "{}", context);
+            StakingError::TransactionReverted(message)
+        }
+        _ if message.contains(SIGNING_REJECTED) => StakingError::SigningRejected(message),
+        _ => {
+            // The endpoint is recorded on the enclosing command span
+            tracing::warn!("RPC error: {}", message);
+            StakingError::RpcError(message)
+        }
+    }
+}
+
+/// Classify a failed signature, keeping a rejection on the device apart
+fn signing_error(context: &str, error: &impl std::fmt::Display) -> StakingError {
+    let message = format!("{}: {}", context, error);
+    if message.contains(SIGNING_REJECTED) {
+        StakingError::SigningRejected(message)
+    } else {
+        StakingError::InvalidTransaction(message)
+    }
+}
+
+/// Pull a readable revert reason out of a failed contract call
+fn revert_reason(error: &alloy::contract::Error) -> String {
+    if let alloy::contract::Error::TransportError(RpcError::ErrorResp(payload)) = error {
+        if let Some(Ok(data)) = payload.try_data_as::<Bytes>() {
+            if let Some(reason) = decode_revert_reason(&data) {
+                return reason;
+            }
+        }
+        return payload.message.clone();
+    }
+    error.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::chain::mock::{MockChain, GAS_COST_WEI, GAS_LIMIT};
+    use super::*;
+    use crate::wallet::WalletManager;
+    use alloy::signers::local::PrivateKeySigner;
+    use alloy::sol_types::SolStruct;
+
+    #[test]
+    fn test_parse_wei_decimal() {
+        assert_eq!(
+            parse_wei("1000000000000000000").unwrap(),
+            U256::from(1_000_000_000_000_000_000u128)
+        );
+        assert_eq!(parse_wei("0").unwrap(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_parse_wei_trims_whitespace() {
+        assert_eq!(parse_wei("  42\n").unwrap(), U256::from(42));
+    }
+
+    #[test]
+    fn test_parse_wei_hex_prefix() {
+        assert_eq!(parse_wei("0x2a").unwrap(), U256::from(42));
+        assert_eq!(parse_wei("0X2A").unwrap(), U256::from(42));
+        assert!(parse_wei("0x").is_err());
+    }
+
+    #[test]
+    fn test_parse_wei_rejects_empty() {
+        assert!(parse_wei("").is_err());
+        assert!(parse_wei("   ").is_err());
+    }
+
+    #[test]
+    fn test_parse_wei_rejects_negative() {
+        assert!(parse_wei("-1").is_err());
+        assert!(parse_wei(" -0").is_err());
+    }
+
+    #[test]
+    fn test_parse_wei_rejects_fractional_eth() {
+        let err = parse_wei("1.5").unwrap_err();
+        assert!(err.to_string().contains("whole number of wei"));
+        assert!(parse_wei("0.1").is_err());
+    }
+
+    #[test]
+    fn test_parse_wei_rejects_non_digits() {
+        assert!(parse_wei("1e18").is_err());
+        assert!(parse_wei("+5").is_err());
+        assert!(parse_wei("1_000").is_err());
+        assert!(parse_wei("12abc").is_err());
+        assert!(parse_wei("0xzz").is_err());
+    }
+
+    const CHECKSUMMED: &str = "0x5FbDB2315678afecb367f032d93F642f64180aa3";
+
+    #[test]
+    fn test_parse_address_accepts_checksummed() {
+        let expected = Address::from_str(CHECKSUMMED).unwrap();
+        assert_eq!(parse_address(CHECKSUMMED).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_address_accepts_single_case() {
+        let expected = Address::from_str(CHECKSUMMED).unwrap();
+        assert_eq!(
+            parse_address(&CHECKSUMMED.to_lowercase()).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_address(&CHECKSUMMED.to_uppercase()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_address_accepts_missing_prefix_and_whitespace() {
+        let expected = Address::from_str(CHECKSUMMED).unwrap();
+        assert_eq!(parse_address(&CHECKSUMMED[2..]).unwrap(), expected);
+        assert_eq!(
+            parse_address(&format!("  {}\n", CHECKSUMMED)).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_address(&format!(" {} ", &CHECKSUMMED[2..].to_lowercase())).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_address_rejects_bad_checksum() {
+        let typo = CHECKSUMMED.replace("5FbDB", "5fbDB");
+        let err = parse_address(&typo).unwrap_err();
+        assert!(err.to_string().contains("checksum"));
+    }
+
+    #[test]
+    fn test_parse_address_rejects_wrong_length() {
+        let err = parse_address(&CHECKSUMMED[..41]).unwrap_err();
+        assert!(err.to_string().contains("39 hex digits"));
+        assert!(parse_address(&format!("{}00", CHECKSUMMED)).is_err());
+        assert!(parse_address("0x").is_err());
+        assert!(parse_address("   ").is_err());
+    }
+
+    #[test]
+    fn test_parse_address_rejects_non_hex() {
+        let err = parse_address("0x5FbDB2315678afecb367f032d93F642f64180aaz").unwrap_err();
+        assert!(err.to_string().contains("hex digits"));
+        assert!(parse_address("vitalik").is_err());
+    }
+
+    #[test]
+    fn test_usd_to_wei_converts_at_price() {
+        assert_eq!(
+            usd_to_units(100.0, 2000.0, 18).unwrap(),
+            parse_wei("50000000000000000").unwrap()
+        );
+        assert!(usd_to_units(100.0, 0.0, 18).is_err());
+    }
+
+    #[test]
+    fn test_usd_to_units_uses_token_decimals() {
+        // $100 of a $1 stablecoin with 6 decimals
+        assert_eq!(
+            usd_to_units(100.0, 1.0, 6).unwrap(),
+            U256::from(100_000_000u64)
+        );
+        assert_eq!(
+            usd_to_units(100.0, 1.0, 18).unwrap(),
+            U256::from(100u64) * U256::from(ONE_ETH)
+        );
+    }
+
+    #[test]
+    fn test_parse_wei_rejects_overflow() {
+        let max = U256::MAX.to_string();
+        assert_eq!(parse_wei(&max).unwrap(), U256::MAX);
+        assert!(parse_wei(&format!("{}0", max)).is_err());
+    }
+
+    fn service(id: &str) -> ServiceDescriptor {
+        ServiceDescriptor {
+            id: id.to_string(),
+            name: id.to_string(),
+            contract_address: Address::ZERO.to_string(),
+            stake_token: "ETH".to_string(),
+            min_stake_wei: "0".to_string(),
+        }
+    }
+
+    fn stake_return(amount: u64) -> IComputeStaking::getStakeReturn {
+        IComputeStaking::getStakeReturn {
+            amount: U256::from(amount),
+            stakeType: 0,
+            stakedAt: U256::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_note_service_keeps_every_readable_service() {
+        let services = [service("compute"), service("storage"), service("oracle")];
+        let mut reads = StakeReads::default();
+        for (i, service) in services.iter().enumerate() {
+            reads.note_service(service, Ok(stake_return(i as u64)), Ok(U256::ZERO));
+        }
+
+        let ids: Vec<_> = reads
+            .registered
+            .iter()
+            .map(|(s, _, _)| s.id.as_str())
+            .collect();
+        assert_eq!(ids, ["compute", "storage", "oracle"]);
+        assert!(reads.errors.is_empty());
+    }
+
+    #[test]
+    fn test_note_service_skips_pending_when_stake_unreadable() {
+        let mut reads = StakeReads::default();
+        reads.note_service(
+            &service("storage"),
+            Err("Reverted".to_string()),
+            Err("Reverted".to_string()),
+        );
+
+        assert!(reads.registered.is_empty());
+        assert_eq!(reads.errors.len(), 1);
+        assert_eq!(reads.errors[0].call, "getStake");
+    }
+
+    #[tokio::test]
+    async fn test_node_pending_rewards_reported_as_node_service() {
+        let state = mock_chain_state("pending-node");
+        let node_staking = Address::repeat_byte(0x44);
+        state
+            .inner
+            .write()
+            .await
+            .config
+            .contracts
+            .entry(31337)
+            .or_default()
+            .node_staking_manager = Some(format!("{:?}", node_staking));
+        let inner = state.inner.read().await;
+        let chain = MockChain::new(31337);
+        let operator = dev_address();
+        chain.set_stake(MOCK_COMPUTE_STAKING, operator, U256::from(ONE_ETH), 1);
+        chain.set_stake(node_staking, operator, U256::from(1_000u64), 1_700_000_000);
+        chain.set_pending(node_staking, operator, U256::from(5_000u64));
+
+        // The compute stake has nothing pending, so only the node is listed
+        let rewards = pending_rewards_by_service(&inner, &chain, operator)
+            .await
+            .unwrap();
+        assert_eq!(rewards.len(), 1);
+        assert_eq!(rewards[0].service_id, "node");
+        assert_eq!(rewards[0].pending_rewards_wei, "5000");
+        assert_eq!(rewards[0].staked_wei, "1000");
+        assert_eq!(rewards[0].positions[0].staked_at, 1_700_000_000);
+
+        chain.set_pending(node_staking, operator, U256::ZERO);
+        assert!(pending_rewards_by_service(&inner, &chain, operator)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_fresh_stake_allowed() {
+        assert!(check_no_existing_stake(U256::ZERO).is_ok());
+    }
+
+    #[test]
+    fn test_already_staked_rejected() {
+        let existing = U256::from(100_000_000_000_000_000u128);
+        match check_no_existing_stake(existing) {
+            Err(StakingError::AlreadyStaked { staked_wei }) => {
+                assert_eq!(staked_wei, "100000000000000000")
+            }
+            other => panic!("expected AlreadyStaked, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_in_flight_write_blocks_duplicate_until_dropped() {
+        let writes = InFlightWrites::default();
+
+        let first = writes.begin("stake", "compute").unwrap();
+        assert!(matches!(
+            writes.begin("stake", "compute"),
+            Err(StakingError::OperationInFlight { .. })
+        ));
+        // Other actions and services are independent
+        assert!(writes.begin("unstake", "compute").is_ok());
+        assert!(writes.begin("stake", "node").is_ok());
+
+        drop(first);
+        assert!(writes.begin("stake", "compute").is_ok());
+    }
+
+    #[test]
+    fn test_convert_units_between_decimals() {
+        // 1000 of a 6-decimal $1 token is half an ETH at $2000
+        assert_eq!(
+            convert_units(U256::from(1_000_000_000u64), (6, 1.0), (18, 2000.0)).unwrap(),
+            U256::from(ONE_ETH / 2)
+        );
+        assert_eq!(
+            convert_units(U256::from(ONE_ETH / 2), (18, 2000.0), (6, 1.0)).unwrap(),
+            U256::from(1_000_000_000u64)
+        );
+    }
+
+    #[test]
+    fn test_break_even_gas_price() {
+        // 0.001 ETH of rewards over a 50k gas claim breaks even at 20 gwei
+        assert_eq!(
+            break_even_gas_price(U256::from(1_000_000_000_000_000u64), 50_000),
+            U256::from(20_000_000_000u64)
+        );
+        assert_eq!(break_even_gas_price(U256::ZERO, 50_000), U256::ZERO);
+        assert_eq!(break_even_gas_price(U256::from(ONE_ETH), 0), U256::ZERO);
+    }
+
+    #[test]
+    fn test_claim_skip_reason_weighs_gas_against_rewards() {
+        let wei = U256::from;
+        assert!(claim_skip_reason(wei(0), wei(0), wei(0)).is_some());
+        // Dust that doesn't cover its own gas
+        assert!(claim_skip_reason(wei(50), wei(80), wei(0)).is_some());
+        assert!(claim_skip_reason(wei(100), wei(80), wei(30)).is_some());
+        assert!(claim_skip_reason(wei(110), wei(80), wei(30)).is_none());
+    }
+
+    #[test]
+    fn test_stake_tokens_are_erc20s_per_contract() {
+        let token = Address::repeat_byte(0x11);
+        let contract = Address::repeat_byte(0x22);
+        let other_contract = Address::repeat_byte(0x33);
+        let erc20_service = |id: &str, contract: Address| ServiceDescriptor {
+            contract_address: contract.to_string(),
+            stake_token: token.to_string(),
+            ..service(id)
+        };
+        let mut symbol_service = service("node");
+        symbol_service.stake_token = "JEJU".to_string();
+
+        let stake_tokens = stake_token_spenders(&[
+            service("compute"),
+            symbol_service,
+            erc20_service("storage", contract),
+            erc20_service("storage-v2", contract),
+            erc20_service("oracle", other_contract),
+        ]);
+        assert_eq!(
+            stake_tokens,
+            vec![
+                ("storage".to_string(), token, contract),
+                ("oracle".to_string(), token, other_contract),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stake_kind_keeps_unknown_values() {
+        assert_eq!(StakeKind::from(2), StakeKind::Provider);
+        assert_eq!(StakeKind::from(3), StakeKind::Guardian);
+        assert_eq!(StakeKind::from(9), StakeKind::Unknown(9));
+        assert_eq!(
+            serde_json::to_value(StakeKind::Provider).unwrap(),
+            serde_json::json!("provider")
+        );
+
+        let mut stake = stake_return(100);
+        stake.stakeType = 7;
+        let info = compute_stake_info(&stake, None, U256::ZERO, "0", 0);
+        assert_eq!(info.stake_kind, Some(StakeKind::Unknown(7)));
+    }
+
+    #[test]
+    fn test_token_totals_keep_tokens_apart() {
+        let stake = |token: &str, staked_wei: u64, staked_usd: f64| ServiceStakeInfo {
+            stake_token: token.to_string(),
+            staked_usd,
+            ..compute_stake_info(&stake_return(staked_wei), None, U256::ZERO, "0", 0)
+        };
+        let jeju = "0x00000000000000000000000000000000000000aA";
+
+        let totals = token_totals(&[
+            stake("ETH", 100, 1.0),
+            stake(jeju, 7, 0.5),
+            stake("ETH", 50, 0.5),
+            stake(&jeju.to_lowercase(), 3, 0.25),
+        ]);
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[0].token, "ETH");
+        assert_eq!(totals[0].amount_wei, "150");
+        assert_eq!(totals[0].amount_usd, 1.5);
+        assert_eq!(totals[1].token, jeju);
+        assert_eq!(totals[1].amount_wei, "10");
+        assert_eq!(totals[1].amount_usd, 0.75);
+        assert_eq!(native_total(&totals), "150");
+        assert_eq!(native_total(&totals[1..]), "0");
+    }
+
+    #[tokio::test]
+    async fn test_min_stake_tracks_configured_minimum() {
+        let state = AppState::new();
+        let mut inner = state.inner.write().await;
+        // No compute contract on this chain, so the minimum comes from config
+        inner.config.network.chain_id = 424242;
+        inner.config.staking.min_compute_stake_wei = "5000".to_string();
+
+        let min_stake = min_compute_stake(&inner).await.unwrap();
+        assert_eq!(min_stake, U256::from(5000));
+        let info = compute_stake_info(
+            &stake_return(0),
+            None,
+            U256::ZERO,
+            &min_stake.to_string(),
+            0,
+        );
+        assert_eq!(info.min_stake_wei, "5000");
+        assert!(check_stake_amount(U256::from(4999), min_stake, true).is_err());
+        assert!(check_stake_amount(U256::from(5000), min_stake, true).is_ok());
+
+        inner
+            .config
+            .staking
+            .min_compute_stake_wei_by_chain
+            .insert(424242, "8000".to_string());
+
+        let min_stake = min_compute_stake(&inner).await.unwrap();
+        assert_eq!(min_stake, U256::from(8000));
+        let info = compute_stake_info(
+            &stake_return(0),
+            None,
+            U256::ZERO,
+            &min_stake.to_string(),
+            0,
+        );
+        assert_eq!(info.min_stake_wei, "8000");
+        assert!(check_stake_amount(U256::from(5000), min_stake, true).is_err());
+        assert!(check_stake_amount(U256::from(8000), min_stake, true).is_ok());
+    }
+
+    // First default account of anvil / hardhat dev nodes
+    const DEV_PRIVATE_KEY: &str =
+        "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    #[tokio::test]
+    #[ignore = "requires a local dev node; set JEJU_TEST_RPC_URL (default http://127.0.0.1:8545)"]
+    async fn test_concurrent_sends_get_distinct_nonces() {
+        let state = AppState::new();
+        let mut inner = state.inner.write().await;
+        inner.config.network.rpc_url = std::env::var("JEJU_TEST_RPC_URL")
+            .unwrap_or_else(|_| "http://127.0.0.1:8545".to_string());
+        let inner = inner.downgrade();
+
+        let signer = PrivateKeySigner::from_str(DEV_PRIVATE_KEY).unwrap();
+        let operator = signer.address();
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(signer))
+            .on_client(inner.rpc_client().await.unwrap());
+
+        let first = CallBuilder::new_raw(&provider, Bytes::new())
+            .to(Address::repeat_byte(0x11))
+            .value(U256::from(1))
+            .from(operator);
+        let second = CallBuilder::new_raw(&provider, Bytes::new())
+            .to(Address::repeat_byte(0x22))
+            .value(U256::from(1))
+            .from(operator);
+
+        let fees = FeeOverrides::default();
+        let (first, second) = tokio::join!(
+            send_and_confirm(&inner, &first, &fees, false),
+            send_and_confirm(&inner, &second, &fees, false)
+        );
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_eq!(first.error, None);
+        assert_eq!(second.error, None);
+
+        let nonce_of = |tx_hash| {
+            let provider = &provider;
+            async move {
+                provider
+                    .get_transaction_by_hash(tx_hash)
+                    .await
+                    .unwrap()
+                    .unwrap()
+                    .nonce
+            }
+        };
+        assert_ne!(
+            nonce_of(first.tx_hash).await,
+            nonce_of(second.tx_hash).await
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local dev node with IComputeStaking deployed; set JEJU_TEST_RPC_URL and JEJU_TEST_COMPUTE_STAKING"]
+    async fn test_concurrent_stakes_broadcast_once() {
+        let state = AppState::new();
+        let mut inner = state.inner.write().await;
+        inner.config.network.rpc_url = std::env::var("JEJU_TEST_RPC_URL")
+            .unwrap_or_else(|_| "http://127.0.0.1:8545".to_string());
+        inner.config.network.chain_id = 31337;
+        inner.config.network.expected_chain_id = None;
+        inner
+            .config
+            .contracts
+            .entry(31337)
+            .or_default()
+            .compute_staking = Some(std::env::var("JEJU_TEST_COMPUTE_STAKING").unwrap());
+
+        let mut wallet =
+            WalletManager::new(inner.rpc_urls(), 31337, inner.config.network.rpc_timeout());
+        wallet.import_wallet(DEV_PRIVATE_KEY, "test").unwrap();
+        inner.wallet_manager = Some(wallet);
+        let inner = inner.downgrade();
+
+        let operator = PrivateKeySigner::from_str(DEV_PRIVATE_KEY)
+            .unwrap()
+            .address();
+        let provider = inner.provider().await.unwrap();
+        let nonce_before = provider.get_transaction_count(operator).await.unwrap();
+
+        let request = StakeRequest {
+            service_id: "compute".to_string(),
+            amount_wei: inner
+                .config
+                .staking
+                .min_compute_stake_wei(31337)
+                .to_string(),
+            amount_usd: None,
+            token_address: None,
+            check_balance: Some(false),
+            simulate: Some(false),
+            beneficiary: None,
+            use_permit: false,
+            permit_deadline: None,
+            fees: FeeOverrides::default(),
+        };
+        let chain = AlloyChain::new(&inner);
+        let (first, second) = tokio::join!(
+            run_stake(&inner, &chain, &request, false),
+            run_stake(&inner, &chain, &request, false)
+        );
+
+        let in_flight = [&first, &second]
+            .iter()
+            .filter(|result| matches!(result, Err(StakingError::OperationInFlight { .. })))
+            .count();
+        assert_eq!(in_flight, 1);
+        assert_eq!(
+            provider.get_transaction_count(operator).await.unwrap(),
+            nonce_before + 1
+        );
+    }
+
+    const MOCK_COMPUTE_STAKING: Address = Address::repeat_byte(0x11);
+    const ONE_ETH: u128 = 1_000_000_000_000_000_000;
+
+    /// State for the dev chain with the DEV_PRIVATE_KEY wallet connected and
+    /// the compute contract's parameters cached, so that only `MockChain`
+    /// is consulted. Nothing it points at is reachable.
+    fn mock_chain_state(log_name: &str) -> AppState {
+        let state = AppState::new();
+        let mut inner = state.inner.try_write().unwrap();
+        inner.config.network.rpc_url = "http://127.0.0.1:9".to_string();
+        inner.config.network.chain_id = 31337;
+        inner.config.network.expected_chain_id = None;
+        inner
+            .config
+            .contracts
+            .entry(31337)
+            .or_default()
+            .compute_staking = Some(format!("{:?}", MOCK_COMPUTE_STAKING));
+        inner.config.staking.log_path = Some(
+            std::env::temp_dir()
+                .join(format!(
+                    "jeju-node-{}-{}.json",
+                    log_name,
+                    std::process::id()
+                ))
+                .to_string_lossy()
+                .into_owned(),
+        );
+        inner.config.prices.source_url = "http://127.0.0.1:9/simple/price".to_string();
+        inner.price_oracle = crate::price::PriceOracle::new(inner.config.prices.clone());
+        inner.staking_parameters_cache.insert(
+            MOCK_COMPUTE_STAKING,
+            StakingParameters {
+                min_stake_wei: Some(ONE_ETH.to_string()),
+                unstake_cooldown_secs: Some(0),
+                ..Default::default()
+            },
+        );
+
+        let mut wallet =
+            WalletManager::new(inner.rpc_urls(), 31337, inner.config.network.rpc_timeout());
+        wallet.import_wallet(DEV_PRIVATE_KEY, "test").unwrap();
+        inner.wallet_manager = Some(wallet);
+        drop(inner);
+        state
+    }
+
+    fn dev_address() -> Address {
+        PrivateKeySigner::from_str(DEV_PRIVATE_KEY)
+            .unwrap()
+            .address()
+    }
+
+    fn eth_stake_request(amount_wei: u128, simulate: bool) -> StakeRequest {
+        StakeRequest {
+            service_id: "compute".to_string(),
+            amount_wei: amount_wei.to_string(),
+            amount_usd: None,
+            token_address: None,
+            check_balance: Some(true),
+            simulate: Some(simulate),
+            beneficiary: None,
+            use_permit: false,
+            permit_deadline: None,
+            fees: FeeOverrides::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_staking_info_reads_through_chain() {
+        let state = mock_chain_state("staking-info");
+        let inner = state.inner.read().await;
+        let chain = MockChain::new(31337);
+        let operator = dev_address();
+        chain.set_stake(MOCK_COMPUTE_STAKING, operator, U256::from(2 * ONE_ETH), 1);
+        chain.set_pending(MOCK_COMPUTE_STAKING, operator, U256::from(5000));
+
+        let info = staking_info(&inner, &chain, operator, true).await.unwrap();
+        assert_eq!(info.total_staked_wei, (2 * ONE_ETH).to_string());
+        assert_eq!(info.staked_by_token.len(), 1);
+        assert_eq!(
+            info.staked_by_token[0].amount_wei,
+            (2 * ONE_ETH).to_string()
+        );
+        assert_eq!(info.pending_rewards_wei, "5000");
+        assert_eq!(info.staked_by_service.len(), 1);
+        assert_eq!(info.staked_by_service[0].service_id, "compute");
+        assert_eq!(info.staked_by_service[0].min_stake_wei, ONE_ETH.to_string());
+        assert!(info.can_unstake);
+        assert!(info.read_errors.is_empty());
+        assert!(info.data_complete);
+        // The price source is unreachable
+        assert!(info.prices_stale);
+    }
+
+    #[test]
+    fn test_staking_log_decodes_to_history_and_fields() {
+        let event = IComputeStaking::Staked {
+            staker: dev_address(),
+            amount: U256::from(ONE_ETH),
+            stakeType: 2,
+        };
+        let log = Log {
+            inner: alloy::primitives::Log {
+                address: MOCK_COMPUTE_STAKING,
+                data: event.encode_log_data(),
+            },
+            block_number: Some(7),
+            ..Default::default()
+        };
+
+        let history = decode_staking_event(&log).unwrap();
+        assert!(matches!(history.kind, StakingEventKind::Stake));
+        assert_eq!(history.service_id, "compute");
+        assert_eq!(history.amount_wei, ONE_ETH.to_string());
+        assert_eq!(history.block_number, 7);
+
+        let decoded = decode_receipt_event(&log);
+        assert_eq!(decoded.name.as_deref(), Some("Staked"));
+        let names: Vec<_> = decoded
+            .fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect();
+        assert_eq!(names, ["staker", "amount", "stakeType"]);
+        assert_eq!(decoded.fields[1].value, ONE_ETH.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_token_compute_stake_is_labelled_by_its_token() {
+        let state = mock_chain_state("staking-info-token-stake");
+        let inner = state.inner.read().await;
+        let token = Address::repeat_byte(0x22);
+        let mut chain = MockChain::new(31337);
+        chain.stake_token = Some(token);
+        let operator = dev_address();
+        chain.set_stake(MOCK_COMPUTE_STAKING, operator, U256::from(2 * ONE_ETH), 1);
+
+        let info = staking_info(&inner, &chain, operator, true).await.unwrap();
+        assert_eq!(
+            info.staked_by_service[0].stake_token,
+            format!("{:?}", token)
+        );
+        assert_eq!(info.staked_by_token.len(), 1);
+        assert_eq!(info.staked_by_token[0].token, format!("{:?}", token));
+        // Nothing is staked in native ETH
+        assert_eq!(info.total_staked_wei, "0");
+    }
+
+    #[tokio::test]
+    async fn test_staking_info_lists_failed_reads() {
+        let state = mock_chain_state("staking-info-errors");
+        let inner = state.inner.read().await;
+        let mut chain = MockChain::new(31337);
+        chain.rpc_error = Some("connection reset".to_string());
+        let operator = dev_address();
+
+        let info = staking_info(&inner, &chain, operator, true).await.unwrap();
+        assert_eq!(info.total_staked_wei, "0");
+        assert!(info.staked_by_token.is_empty());
+        assert!(info.staked_by_service.is_empty());
+        assert!(!info.data_complete);
+        assert!(!info.read_errors.is_empty());
+        assert!(info
+            .read_errors
+            .iter()
+            .all(|e| e.service_id == "compute" && e.error.contains("connection reset")));
+
+        // A failed read isn't cached
+        assert!(inner
+            .staking_info_cache
+            .get(operator, Duration::from_secs(3600))
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_historical_staking_info_bypasses_cache() {
+        let state = mock_chain_state("staking-info-historical");
+        let inner = state.inner.read().await;
+        let mut chain = MockChain::new(31337);
+        let operator = dev_address();
+        chain.set_stake(MOCK_COMPUTE_STAKING, operator, U256::from(ONE_ETH), 1);
+        chain.set_pending(MOCK_COMPUTE_STAKING, operator, U256::from(5000));
+        chain.at_block = Some(100);
+
+        let info = staking_info(&inner, &chain, operator, false).await.unwrap();
+        assert_eq!(info.pending_rewards_wei, "5000");
+        // APR is measured up to now, which means nothing for a past block
+        assert_eq!(info.apr, None);
+        assert!(inner
+            .staking_info_cache
+            .get(operator, Duration::from_secs(3600))
+            .is_none());
+
+        chain.at_block = None;
+        let info = staking_info(&inner, &chain, operator, false).await.unwrap();
+        assert!(info.apr.is_some());
+        assert!(inner
+            .staking_info_cache
+            .get(operator, Duration::from_secs(3600))
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_block_at_timestamp_finds_last_block_before() {
+        // Block n is produced at 1000 + 12n
+        let lookups = std::sync::atomic::AtomicUsize::new(0);
+        let lookups = &lookups;
+        let block_timestamp = |number: u64| async move {
+            lookups.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Ok(1000 + 12 * number)
+        };
+
+        assert_eq!(
+            block_at_timestamp(1_000_000, 1000, block_timestamp)
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            block_at_timestamp(1_000_000, 1011, block_timestamp)
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            block_at_timestamp(1_000_000, 1012, block_timestamp)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            block_at_timestamp(1_000_000, 1000 + 12 * 654_321 + 5, block_timestamp)
+                .await
+                .unwrap(),
+            654_321
+        );
+        assert_eq!(
+            block_at_timestamp(1_000_000, u64::MAX, block_timestamp)
+                .await
+                .unwrap(),
+            1_000_000
+        );
+        assert!(matches!(
+            block_at_timestamp(1_000_000, 999, block_timestamp).await,
+            Err(StakingError::InvalidAmount(_))
+        ));
+
+        lookups.store(0, std::sync::atomic::Ordering::Relaxed);
+        block_at_timestamp(1_000_000, 5_000_000, block_timestamp)
+            .await
+            .unwrap();
+        assert!(lookups.load(std::sync::atomic::Ordering::Relaxed) <= 22);
+    }
+
+    #[test]
+    fn test_missing_state_wordings() {
+        assert!(is_missing_state("missing trie node 1a2b (path )"));
+        assert!(is_missing_state(
+            "server returned an error response: error code -32000: header not found"
+        ));
+        assert!(is_missing_state("state at block #12 is pruned"));
+        assert!(!is_missing_state("execution reverted"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_legacy_read_marks_info_incomplete() {
+        let state = mock_chain_state("staking-info-legacy");
+        let inner = state.inner.read().await;
+        let mut chain = MockChain::new(31337);
+        chain.legacy_error = Some("connection reset".to_string());
+        let operator = dev_address();
+        chain.set_stake(MOCK_COMPUTE_STAKING, operator, U256::from(ONE_ETH), 1);
+
+        // The compute stake is still shown, but not presented as the full picture
+        let info = staking_info(&inner, &chain, operator, true).await.unwrap();
+        assert_eq!(info.staked_by_service.len(), 1);
+        assert!(!info.data_complete);
+        assert_eq!(info.read_errors.len(), 1);
+        assert_eq!(info.read_errors[0].service_id, "legacy");
+
+        chain.legacy_error = None;
+        let info = staking_info(&inner, &chain, operator, true).await.unwrap();
+        assert!(info.data_complete);
+    }
+
+    #[tokio::test]
+    async fn test_stake_sends_once_and_rereads_stake() {
+        let state = mock_chain_state("stake");
+        let inner = state.inner.read().await;
+        let chain = MockChain::new(31337);
+
+        let result = run_stake(&inner, &chain, &eth_stake_request(ONE_ETH, true), false)
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.error, None);
+        assert_eq!(result.new_stake_wei, ONE_ETH.to_string());
+        assert_eq!(result.amount_wei, Some(ONE_ETH.to_string()));
+
+        let sent = chain.sent.lock();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].from, Some(dev_address()));
+        assert_eq!(sent[0].to, Some(MOCK_COMPUTE_STAKING.into()));
+        assert_eq!(sent[0].value, Some(U256::from(ONE_ETH)));
+        assert!(sent[0]
+            .input
+            .input()
+            .unwrap()
+            .starts_with(&IComputeStaking::stakeAsProviderCall::SELECTOR));
+    }
+
+    #[tokio::test]
+    async fn test_stake_rejects_existing_position() {
+        let state = mock_chain_state("stake-existing");
+        let inner = state.inner.read().await;
+        let chain = MockChain::new(31337);
+        chain.set_stake(MOCK_COMPUTE_STAKING, dev_address(), U256::from(ONE_ETH), 1);
+
+        let result = run_stake(&inner, &chain, &eth_stake_request(ONE_ETH, true), false).await;
+        assert!(matches!(result, Err(StakingError::AlreadyStaked { .. })));
+        assert!(chain.sent.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stake_revert_is_reported() {
+        let state = mock_chain_state("stake-revert");
+        let inner = state.inner.read().await;
+        let mut chain = MockChain::new(31337);
+        chain.revert = Some("Staking paused".to_string());
+
+        // Simulation catches it before anything is sent
+        let result = run_stake(&inner, &chain, &eth_stake_request(ONE_ETH, true), false).await;
+        assert!(
+            matches!(result, Err(StakingError::TransactionReverted(ref e)) if e.contains("Staking paused"))
+        );
+        assert!(chain.sent.lock().is_empty());
+
+        // Without simulation it's mined and reverts
+        let result = run_stake(&inner, &chain, &eth_stake_request(ONE_ETH, false), false)
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("Staking paused"));
+        assert_eq!(result.new_stake_wei, "0");
+        assert_eq!(chain.sent.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stake_rpc_error_sends_nothing() {
+        let state = mock_chain_state("stake-rpc-error");
+        let inner = state.inner.read().await;
+        let mut chain = MockChain::new(31337);
+        chain.rpc_error = Some("connection reset".to_string());
+
+        let result = run_stake(&inner, &chain, &eth_stake_request(ONE_ETH, true), false).await;
+        assert!(matches!(result, Err(StakingError::RpcError(_))));
+        assert!(chain.sent.lock().is_empty());
+    }
+
+    #[test]
+    fn test_next_auto_claim_waits_for_threshold_and_interval() {
+        let now = 1_700_000_000;
+        let earnings = EarningsConfig {
+            auto_claim: true,
+            auto_claim_threshold_wei: "1000".to_string(),
+            auto_claim_interval_hours: 24,
+            last_auto_claim_timestamp: Some(now - 3600),
+            ..EarningsConfig::default()
+        };
+        // 100 wei an hour
+        let rate = Some(AccrualRate {
+            accrued: U256::from(100),
+            per_secs: U256::from(3600),
+        });
+
+        // Threshold in 9 hours, interval in 23: the interval decides
+        let estimate = estimate_claim_time(&earnings, U256::from(100), rate, now).unwrap();
+        assert_eq!(estimate.threshold_reached_timestamp, Some(now + 9 * 3600));
+        assert_eq!(estimate.next_auto_claim_timestamp, Some(now + 23 * 3600));
+        assert_eq!(estimate.estimated_claim_timestamp, Some(now + 23 * 3600));
+        assert_eq!(estimate.per_day_wei.as_deref(), Some("2400"));
+        assert!(!estimate.indeterminate);
+
+        // Interval passed: the threshold decides, rounding up to a whole second
+        let due = EarningsConfig {
+            last_auto_claim_timestamp: None,
+            ..earnings.clone()
+        };
+        let estimate = estimate_claim_time(&due, U256::from(999), rate, now).unwrap();
+        assert_eq!(estimate.estimated_claim_timestamp, Some(now + 36));
+
+        // Already over the threshold
+        let estimate = estimate_claim_time(&due, U256::from(1000), None, now).unwrap();
+        assert_eq!(estimate.estimated_claim_timestamp, Some(now));
+    }
+
+    #[test]
+    fn test_next_auto_claim_is_indeterminate_without_a_rate() {
+        let now = 1_700_000_000;
+        let earnings = EarningsConfig {
+            auto_claim_threshold_wei: "1000".to_string(),
+            ..EarningsConfig::default()
+        };
+        let zero = Some(AccrualRate {
+            accrued: U256::ZERO,
+            per_secs: U256::from(3600),
+        });
+
+        for rate in [None, zero] {
+            let estimate = estimate_claim_time(&earnings, U256::from(10), rate, now).unwrap();
+            assert_eq!(estimate.threshold_reached_timestamp, None);
+            assert_eq!(estimate.next_auto_claim_timestamp, Some(now));
+            assert!(estimate.indeterminate);
+        }
+
+        // A zero threshold still needs something to claim
+        let any = EarningsConfig {
+            auto_claim_threshold_wei: "0".to_string(),
+            ..earnings.clone()
+        };
+        assert!(
+            estimate_claim_time(&any, U256::ZERO, None, now)
+                .unwrap()
+                .indeterminate
+        );
+
+        let off = EarningsConfig {
+            auto_claim: false,
+            ..earnings
+        };
+        assert!(
+            estimate_claim_time(&off, U256::from(5000), None, now)
+                .unwrap()
+                .indeterminate
+        );
+    }
+
+    #[test]
+    fn test_permit_deadline_bounds() {
+        let now = 1_700_000_000;
+        assert_eq!(
+            permit_deadline(None, now).unwrap(),
+            now + PERMIT_VALIDITY.as_secs()
+        );
+        assert_eq!(permit_deadline(Some(now + 600), now).unwrap(), now + 600);
+        assert!(matches!(
+            permit_deadline(Some(now - 1), now),
+            Err(StakingError::InvalidPermitDeadline(_))
+        ));
+        assert!(permit_deadline(Some(now + 30), now).is_err());
+        assert!(permit_deadline(Some(now + 2 * 24 * 60 * 60), now).is_err());
+    }
+
+    /// Permit details of a token whose domain uses `version`
+    fn token_permit(token: Address, version: &'static str) -> TokenPermit {
+        let domain = Eip712Domain::new(
+            Some("Jeju".into()),
+            Some(version.into()),
+            Some(U256::from(31337)),
+            Some(token),
+            None,
+        );
+        TokenPermit {
+            name: "Jeju".to_string(),
+            nonce: U256::ZERO,
+            domain_separator: domain.separator(),
+        }
+    }
+
+    #[test]
+    fn test_permit_domain_matches_separator() {
+        let token = Address::repeat_byte(0x22);
+
+        let domain = permit_domain(&token_permit(token, "2"), 31337, token).unwrap();
+        assert_eq!(domain.version.as_deref(), Some("2"));
+        assert_eq!(domain.verifying_contract, Some(token));
+
+        // A separator built for another chain or with an unknown version can't be reproduced
+        assert!(permit_domain(&token_permit(token, "2"), 1, token).is_none());
+        assert!(permit_domain(&token_permit(token, "3"), 31337, token).is_none());
+    }
+
+    /// A contract domain for relayed stakes with the given EIP-5267 fields
+    fn authorization_info(fields: u8, chain_id: u64) -> AuthorizationInfo {
+        AuthorizationInfo {
+            fields,
+            name: "ComputeStaking".to_string(),
+            version: "1".to_string(),
+            chain_id: U256::from(chain_id),
+            verifying_contract: MOCK_COMPUTE_STAKING,
+            salt: B256::ZERO,
+            nonce: U256::from(3),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stake_authorization_recovers_to_wallet() {
+        let state = mock_chain_state("stake-authorization");
+        let inner = state.inner.read().await;
+        let token = Address::repeat_byte(0x22);
+        let mut chain = MockChain::new(31337);
+        chain.authorization = Some(authorization_info(0x0f, 31337));
+
+        let signed = authorize_stake(&inner, &chain, &token_stake_request(token))
+            .await
+            .unwrap();
+        assert!(chain.sent.lock().is_empty());
+        assert_eq!(signed.nonce, "3");
+
+        // The payload handed to the relayer hashes to what was signed
+        let hash = signed.typed_data.eip712_signing_hash().unwrap();
+        assert_eq!(hash.to_string(), signed.signing_hash);
+        assert_eq!(signed.typed_data.domain.chain_id, Some(U256::from(31337)));
+        assert_eq!(
+            signed.typed_data.message["amount"],
+            serde_json::json!(U256::from(ONE_ETH))
+        );
+        let signature = Signature::try_from(&hex::decode(&signed.signature).unwrap()[..]).unwrap();
+        assert_eq!(
+            signature.recover_address_from_prehash(&hash).unwrap(),
+            dev_address()
+        );
+
+        // ETH can't be relayed, and neither can a contract without a domain
+        let eth = authorize_stake(&inner, &chain, &eth_stake_request(ONE_ETH, true)).await;
+        assert!(matches!(eth, Err(StakingError::InvalidTransaction(_))));
+        chain.authorization = None;
+        let unsupported = authorize_stake(&inner, &chain, &token_stake_request(token)).await;
+        assert!(matches!(
+            unsupported,
+            Err(StakingError::InvalidTransaction(_))
+        ));
+    }
+
+    #[test]
+    fn test_authorization_domain_must_bind_chain_and_contract() {
+        let domain = authorization_domain(
+            &authorization_info(0x0f, 31337),
+            31337,
+            MOCK_COMPUTE_STAKING,
+        )
+        .unwrap();
+        assert_eq!(domain.name.as_deref(), Some("ComputeStaking"));
+        assert_eq!(domain.verifying_contract, Some(MOCK_COMPUTE_STAKING));
+        assert_eq!(domain.salt, None);
+
+        assert!(matches!(
+            authorization_domain(
+                &authorization_info(0x0b, 31337),
+                31337,
+                MOCK_COMPUTE_STAKING
+            ),
+            Err(StakingError::InvalidTransaction(_))
+        ));
+        assert!(matches!(
+            authorization_domain(&authorization_info(0x0f, 1), 31337, MOCK_COMPUTE_STAKING),
+            Err(StakingError::WrongChain {
+                actual: 1,
+                expected: 31337
+            })
+        ));
+        assert!(authorization_domain(
+            &authorization_info(0x0f, 31337),
+            31337,
+            Address::repeat_byte(0x99)
+        )
+        .is_err());
+    }
+
+    fn token_stake_request(token: Address) -> StakeRequest {
+        StakeRequest {
+            token_address: Some(format!("{:?}", token)),
+            use_permit: true,
+            ..eth_stake_request(ONE_ETH, true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stake_with_permit_skips_approval() {
+        let state = mock_chain_state("stake-permit");
+        let inner = state.inner.read().await;
+        let token = Address::repeat_byte(0x22);
+        let mut chain = MockChain::new(31337);
+        chain.permit = Some(token_permit(token, "1"));
+
+        let result = run_stake(&inner, &chain, &token_stake_request(token), false)
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.approval_tx_hash, None);
+
+        let sent = chain.sent.lock();
+        assert_eq!(sent.len(), 1);
+        let call =
+            IComputeStaking::stakeWithPermitCall::abi_decode(sent[0].input.input().unwrap(), true)
+                .unwrap();
+        assert_eq!(call.token, token);
+        assert_eq!(call.amount, U256::from(ONE_ETH));
+
+        // The signature recovers to the wallet over the permit the token will check
+        let permit = Permit {
+            owner: dev_address(),
+            spender: MOCK_COMPUTE_STAKING,
+            value: call.amount,
+            nonce: U256::ZERO,
+            deadline: call.deadline,
+        };
+        let domain = permit_domain(chain.permit.as_ref().unwrap(), 31337, token).unwrap();
+        let signature =
+            Signature::from_rs_and_parity(call.r.into(), call.s.into(), call.v as u64).unwrap();
+        assert_eq!(
+            signature
+                .recover_address_from_prehash(&permit.eip712_signing_hash(&domain))
+                .unwrap(),
+            dev_address()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stake_without_permit_support_approves() {
+        let state = mock_chain_state("stake-permit-fallback");
+        let inner = state.inner.read().await;
+        let token = Address::repeat_byte(0x22);
+        let chain = MockChain::new(31337);
+
+        let result = run_stake(&inner, &chain, &token_stake_request(token), false)
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert!(result.approval_tx_hash.is_some());
+
+        let sent = chain.sent.lock();
+        assert_eq!(sent.len(), 2);
+        assert!(sent[0]
+            .input
+            .input()
+            .unwrap()
+            .starts_with(&IERC20::approveCall::SELECTOR));
+        assert!(sent[1]
+            .input
+            .input()
+            .unwrap()
+            .starts_with(&IComputeStaking::stakeTokenAsProviderCall::SELECTOR));
+    }
+
+    #[tokio::test]
+    async fn test_partial_unstake_reports_remaining_stake() {
+        let state = mock_chain_state("unstake-partial");
+        let inner = state.inner.read().await;
+        let chain = MockChain::new(31337);
+        chain.set_stake(
+            MOCK_COMPUTE_STAKING,
+            dev_address(),
+            U256::from(3 * ONE_ETH),
+            1,
+        );
+        let request = UnstakeRequest {
+            service_id: "compute".to_string(),
+            amount_wei: ONE_ETH.to_string(),
+            simulate: Some(true),
+            fees: FeeOverrides::default(),
+        };
+
+        let result = run_unstake(&inner, &chain, &request).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.new_stake_wei, (2 * ONE_ETH).to_string());
+
+        let sent = chain.sent.lock();
+        assert_eq!(sent.len(), 1);
+        let call = IComputeStaking::unstake_1Call::abi_decode(sent[0].input.input().unwrap(), true)
+            .unwrap();
+        assert_eq!(call.amount, U256::from(ONE_ETH));
+    }
+
+    #[tokio::test]
+    async fn test_listed_compute_minimum_is_the_enforced_one() {
+        let state = mock_chain_state("service-minimum");
+        state
+            .inner
+            .write()
+            .await
+            .config
+            .staking
+            .min_compute_stake_wei = (5 * ONE_ETH).to_string();
+        let inner = state.inner.read().await;
+
+        // The contract's minimum, cached in `mock_chain_state`, wins over config
+        let services = service_descriptors(&inner).await;
+        let compute = services
+            .iter()
+            .find(|service| service.id == "compute")
+            .unwrap();
+        assert_eq!(compute.min_stake_wei, ONE_ETH.to_string());
+        assert_eq!(
+            compute.min_stake_wei,
+            min_compute_stake(&inner).await.unwrap().to_string()
+        );
+        assert_eq!(
+            service_at(&inner, MOCK_COMPUTE_STAKING).as_deref(),
+            Some("compute")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_node_rewards_are_paid_in_the_node_reward_token() {
+        let state = mock_chain_state("node-reward-token");
+        let node_staking = Address::repeat_byte(0x44);
+        state
+            .inner
+            .write()
+            .await
+            .config
+            .contracts
+            .entry(31337)
+            .or_default()
+            .node_staking_manager = Some(format!("{:?}", node_staking));
+        let inner = state.inner.read().await;
+        let mut chain = MockChain::new(31337);
+        chain.node_reward_token = Address::repeat_byte(0x55);
+
+        assert_eq!(
+            reward_token(&inner, &chain, "node").await.unwrap(),
+            format!("{:?}", chain.node_reward_token)
+        );
+        assert_eq!(
+            reward_token(&inner, &chain, "compute").await.unwrap(),
+            "ETH"
+        );
+
+        chain.rpc_error = Some("unreachable".to_string());
+        assert!(reward_token(&inner, &chain, "node").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unstake_refuses_other_services() {
+        let state = mock_chain_state("unstake-node");
+        let inner = state.inner.read().await;
+        let chain = MockChain::new(31337);
+        chain.set_stake(
+            MOCK_COMPUTE_STAKING,
+            dev_address(),
+            U256::from(2 * ONE_ETH),
+            1,
+        );
+        let request = UnstakeRequest {
+            service_id: "node".to_string(),
+            amount_wei: ONE_ETH.to_string(),
+            simulate: Some(true),
+            fees: FeeOverrides::default(),
+        };
+
+        assert!(matches!(
+            run_unstake(&inner, &chain, &request).await,
+            Err(StakingError::InvalidTransaction(_))
+        ));
+        assert!(chain.sent.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_exit_withdraws_whole_compute_stake() {
+        let state = mock_chain_state("exit-compute");
+        let inner = state.inner.read().await;
+        let chain = MockChain::new(31337);
+        chain.set_stake(
+            MOCK_COMPUTE_STAKING,
+            dev_address(),
+            U256::from(2 * ONE_ETH),
+            1,
+        );
+
+        let results = run_exit(&inner, &chain, "compute").await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].success);
+        assert_eq!(results[0].new_stake_wei, "0");
+
+        let sent = chain.sent.lock();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0]
+            .input
+            .input()
+            .unwrap()
+            .starts_with(&IComputeStaking::unstake_0Call::SELECTOR));
+    }
+
+    #[tokio::test]
+    async fn test_exit_refuses_other_services() {
+        let state = mock_chain_state("exit-node");
+        let inner = state.inner.read().await;
+        let chain = MockChain::new(31337);
+        chain.set_stake(
+            MOCK_COMPUTE_STAKING,
+            dev_address(),
+            U256::from(2 * ONE_ETH),
+            1,
+        );
+
+        assert!(matches!(
+            run_exit(&inner, &chain, "node").await,
+            Err(StakingError::InvalidTransaction(_))
+        ));
+        assert!(chain.sent.lock().is_empty());
+        // Nothing was left in flight for the compute stake
+        assert!(inner.in_flight_writes.begin("unstake", "compute").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_max_stakeable_reserves_gas_for_top_up() {
+        let state = mock_chain_state("max-stakeable-top-up");
+        let inner = state.inner.read().await;
+        let mut chain = MockChain::new(31337);
+        chain.balance = U256::from(2 * ONE_ETH);
+        chain.set_stake(MOCK_COMPUTE_STAKING, dev_address(), U256::from(ONE_ETH), 1);
+
+        let reserve = U256::from(GAS_COST_WEI) * U256::from(100 + GAS_RESERVE_BUFFER_PERCENT)
+            / U256::from(100);
+        assert_eq!(
+            max_stakeable(&inner, &chain, None).await.unwrap(),
+            U256::from(2 * ONE_ETH) - reserve
+        );
+        let estimated = chain.estimated.lock();
+        assert!(estimated[0]
+            .input
+            .input()
+            .unwrap()
+            .starts_with(&IComputeStaking::increaseStakeCall::SELECTOR));
+    }
+
+    #[tokio::test]
+    async fn test_max_stakeable_leaves_eth_whole_when_gas_is_a_token() {
+        let state = mock_chain_state("max-stakeable-gas-token");
+        let gas_token = Address::repeat_byte(0x33);
+        state
+            .inner
+            .write()
+            .await
+            .config
+            .contracts
+            .entry(31337)
+            .or_default()
+            .gas_token = Some(format!("{:?}", gas_token));
+        let inner = state.inner.read().await;
+        let mut chain = MockChain::new(31337);
+        chain.balance = U256::from(2 * ONE_ETH);
+        chain
+            .token_balances
+            .insert(gas_token, U256::from(10 * GAS_COST_WEI));
+
+        assert_eq!(
+            max_stakeable(&inner, &chain, None).await.unwrap(),
+            U256::from(2 * ONE_ETH)
+        );
+        // Staking the gas token itself holds back its gas
+        let staked = max_stakeable(&inner, &chain, Some(&format!("{:?}", gas_token)))
+            .await
+            .unwrap();
+        assert!(staked < U256::from(10 * GAS_COST_WEI));
+        assert!(gas_funds_check(&inner, &chain, dev_address())
+            .await
+            .unwrap()
+            .ends_with(&format!("{:?}", gas_token)));
+
+        chain.token_balances.insert(gas_token, U256::ZERO);
+        assert!(gas_funds_check(&inner, &chain, dev_address())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_max_stakeable_is_zero_below_minimum() {
+        let state = mock_chain_state("max-stakeable-below-minimum");
+        let inner = state.inner.read().await;
+        let mut chain = MockChain::new(31337);
+        chain.balance = U256::from(ONE_ETH / 2);
+
+        assert_eq!(
+            max_stakeable(&inner, &chain, None).await.unwrap(),
+            U256::ZERO
+        );
+        assert!(chain.estimated.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_token_stake_estimate_includes_approval() {
+        let state = mock_chain_state("estimate-token-stake");
+        let inner = state.inner.read().await;
+        let token = Address::repeat_byte(0x22);
+        let chain = MockChain::new(31337);
+
+        let estimate = estimate_stake(&inner, &chain, &token_stake_request(token))
+            .await
+            .unwrap();
+        assert_eq!(estimate.gas_limit, 2 * GAS_LIMIT);
+        assert_eq!(estimate.cost(), U256::from(2 * GAS_COST_WEI));
+
+        let estimated = chain.estimated.lock();
+        assert_eq!(estimated.len(), 2);
+        assert_eq!(estimated[0].to, Some(token.into()));
+        assert!(estimated[0]
+            .input
+            .input()
+            .unwrap()
+            .starts_with(&IERC20::approveCall::SELECTOR));
+        assert_eq!(estimated[1].value, Some(U256::ZERO));
+        assert!(estimated[1]
+            .input
+            .input()
+            .unwrap()
+            .starts_with(&IComputeStaking::stakeTokenAsProviderCall::SELECTOR));
+    }
+
+    #[tokio::test]
+    async fn test_token_stake_estimate_skips_covered_approval() {
+        let state = mock_chain_state("estimate-token-allowance");
+        let inner = state.inner.read().await;
+        let token = Address::repeat_byte(0x22);
+        let mut chain = MockChain::new(31337);
+        chain.allowance = U256::from(ONE_ETH);
+
+        let estimate = estimate_stake(&inner, &chain, &token_stake_request(token))
+            .await
+            .unwrap();
+        assert_eq!(estimate.gas_limit, GAS_LIMIT);
+        assert_eq!(chain.estimated.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_beneficiary_stake_estimate_stakes_for_beneficiary() {
+        let state = mock_chain_state("estimate-beneficiary");
+        let inner = state.inner.read().await;
+        let chain = MockChain::new(31337);
+        let beneficiary = Address::repeat_byte(0x33);
+        let request = StakeRequest {
+            beneficiary: Some(format!("{:?}", beneficiary)),
+            ..eth_stake_request(ONE_ETH, true)
+        };
+
+        estimate_stake(&inner, &chain, &request).await.unwrap();
+        let estimated = chain.estimated.lock();
+        assert_eq!(estimated.len(), 1);
+        assert_eq!(estimated[0].value, Some(U256::from(ONE_ETH)));
+        let call = IComputeStaking::stakeAsProviderForCall::abi_decode(
+            estimated[0].input.input().unwrap(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(call.beneficiary, beneficiary);
+    }
+
+    #[test]
+    fn test_gas_price_ceiling_is_inclusive() {
+        let ceiling = U256::from(50_000_000_000u64);
+        assert!(check_gas_price(U256::from(1), ceiling).is_ok());
+        assert!(check_gas_price(ceiling, ceiling).is_ok());
+        match check_gas_price(ceiling + U256::from(1), ceiling) {
+            Err(StakingError::GasPriceTooHigh { current, ceiling }) => {
+                assert_eq!(current, "50000000001");
+                assert_eq!(ceiling, "50000000000");
+            }
+            other => panic!("expected GasPriceTooHigh, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_signed_fee_is_held_to_the_ceiling() {
+        let ceiling = U256::from(50_000_000_000u64);
+        let dynamic = TransactionRequest::default()
+            .max_fee_per_gas(60_000_000_000)
+            .max_priority_fee_per_gas(1);
+        assert!(matches!(
+            check_signed_fee(&dynamic, ceiling),
+            Err(StakingError::GasPriceTooHigh { .. })
+        ));
+        let legacy = TransactionRequest::default().with_gas_price(50_000_000_000);
+        assert!(check_signed_fee(&legacy, ceiling).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_write_without_signer_is_held_and_retried() {
+        let state = mock_chain_state("pending-write");
+        let inner = state.inner.read().await;
+        let chain = MockChain::new(31337);
+        let request = eth_stake_request(ONE_ETH, true);
+
+        // Other failures are not held
+        let result: Result<(), _> =
+            hold_for_reconnect(&inner, Err(StakingError::ContractPaused), || {
+                WriteIntent::Stake(request.clone())
+            });
+        assert!(matches!(result, Err(StakingError::ContractPaused)));
+        assert!(inner.pending_write.take(PENDING_WRITE_TTL).is_none());
+
+        let result: Result<(), _> =
+            hold_for_reconnect(&inner, Err(StakingError::WalletNotConnected), || {
+                WriteIntent::Stake(request.clone())
+            });
+        assert!(matches!(result, Err(StakingError::WalletNotConnected)));
+        let intent = inner.pending_write.take(PENDING_WRITE_TTL).unwrap();
+        // Taking the intent clears it, so it can't be sent twice
+        assert!(inner.pending_write.take(PENDING_WRITE_TTL).is_none());
+
+        let RetriedWrite::Stake(result) = retry_write(&inner, &chain, intent).await.unwrap() else {
+            panic!("expected a stake result");
+        };
+        assert!(result.success);
+        assert_eq!(result.new_stake_wei, ONE_ETH.to_string());
+        assert_eq!(chain.sent.lock().len(), 1);
+
+        // A stale intent isn't sent
+        inner.pending_write.hold(WriteIntent::Stake(request));
+        assert!(inner.pending_write.take(Duration::ZERO).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stake_past_deadline_is_not_broadcast() {
+        let state = mock_chain_state("stake-deadline");
+        let inner = state.inner.read().await;
+        let chain = MockChain::new(31337);
+        let now = chrono::Utc::now().timestamp() as u64;
+
+        let mut request = eth_stake_request(ONE_ETH, true);
+        request.fees.deadline_unix = Some(now - 1);
+        let result = run_stake(&inner, &chain, &request, false).await;
+        assert!(matches!(
+            result,
+            Err(StakingError::DeadlineExceeded { deadline_unix, .. }) if deadline_unix == now - 1
+        ));
+        assert!(chain.sent.lock().is_empty());
+
+        request.fees.deadline_unix = Some(now + 600);
+        assert!(
+            run_stake(&inner, &chain, &request, false)
+                .await
+                .unwrap()
+                .success
+        );
+        assert_eq!(chain.sent.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_gas_is_checked_against_the_chain_gas_token() {
+        let state = mock_chain_state("stake-gas-token");
+        let gas_token = Address::repeat_byte(0x33);
+        state
+            .inner
+            .write()
+            .await
+            .config
+            .contracts
+            .entry(31337)
+            .or_default()
+            .gas_token = Some(format!("{:?}", gas_token));
+        let inner = state.inner.read().await;
+        // Exactly the stake, which only suffices when gas isn't paid in ETH
+        let mut chain = MockChain::new(31337);
+        chain.balance = U256::from(ONE_ETH);
+        chain
+            .token_balances
+            .insert(gas_token, U256::from(GAS_COST_WEI - 1));
+
+        let result = run_stake(&inner, &chain, &eth_stake_request(ONE_ETH, true), false).await;
+        assert!(matches!(
+            result,
+            Err(StakingError::InsufficientGasToken { token, required, .. })
+                if token == format!("{:?}", gas_token) && required == GAS_COST_WEI.to_string()
+        ));
+        assert!(chain.sent.lock().is_empty());
+
+        chain
+            .token_balances
+            .insert(gas_token, U256::from(GAS_COST_WEI));
+        assert!(
+            run_stake(&inner, &chain, &eth_stake_request(ONE_ETH, true), false)
+                .await
+                .unwrap()
+                .success
+        );
+
+        // Paying gas in ETH, the same balance falls short of stake plus gas
+        assert!(matches!(
+            check_funds(U256::from(ONE_ETH), U256::from(GAS_COST_WEI), U256::from(ONE_ETH), None),
+            Err(StakingError::InsufficientBalance { token, .. }) if token == "ETH"
+        ));
+    }
+
+    #[test]
+    fn test_deadline_allows_the_deadline_second_itself() {
+        let fees = FeeOverrides {
+            deadline_unix: Some(1_000),
+            ..FeeOverrides::default()
+        };
+        assert!(fees.check_deadline(999).is_ok());
+        assert!(fees.check_deadline(1_000).is_ok());
+        assert!(matches!(
+            fees.check_deadline(1_001),
+            Err(StakingError::DeadlineExceeded {
+                deadline_unix: 1_000,
+                now: 1_001
+            })
+        ));
+        assert!(FeeOverrides::default().check_deadline(u64::MAX).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_paused_contract_refuses_stake() {
+        let state = mock_chain_state("stake-paused");
+        let inner = state.inner.read().await;
+        let mut chain = MockChain::new(31337);
+        chain.paused = Some(true);
+
+        let info = staking_info(&inner, &chain, dev_address(), true)
+            .await
+            .unwrap();
+        assert!(info.is_paused);
+
+        let result = run_stake(&inner, &chain, &eth_stake_request(ONE_ETH, true), false).await;
+        assert!(matches!(result, Err(StakingError::ContractPaused)));
+        assert!(chain.sent.lock().is_empty());
+
+        // Contracts without a pause switch are never treated as paused
+        chain.paused = None;
+        let info = staking_info(&inner, &chain, dev_address(), true)
+            .await
+            .unwrap();
+        assert!(!info.is_paused);
+        assert!(
+            run_stake(&inner, &chain, &eth_stake_request(ONE_ETH, true), false)
+                .await
+                .unwrap()
+                .success
+        );
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_sums_valid_addresses() {
+        let state = mock_chain_state("aggregate");
+        let inner = state.inner.read().await;
+        let chain = MockChain::new(31337);
+        let (first, second) = (dev_address(), Address::repeat_byte(0x33));
+        chain.set_stake(MOCK_COMPUTE_STAKING, first, U256::from(2 * ONE_ETH), 1);
+        chain.set_stake(MOCK_COMPUTE_STAKING, second, U256::from(ONE_ETH), 1);
+
+        let addresses = vec![
+            format!("{:?}", first),
+            "not-an-address".to_string(),
+            format!("{:?}", second),
+            format!("{:?}", first).to_uppercase().replace("0X", "0x"),
+        ];
+        let aggregate = aggregate_staking_info(&inner, &chain, &addresses, true).await;
+
+        assert_eq!(aggregate.addresses.len(), 4);
+        assert!(aggregate.addresses[0].info.is_some());
+        assert!(aggregate.addresses[1].error.is_some());
+        assert!(aggregate.addresses[2].info.is_some());
+        assert!(aggregate.addresses[3]
+            .error
+            .as_ref()
+            .unwrap()
+            .contains("Duplicate"));
+
+        assert_eq!(aggregate.staked_by_token.len(), 1);
+        assert_eq!(aggregate.staked_by_token[0].token, "ETH");
+        assert_eq!(
+            aggregate.staked_by_token[0].amount_wei,
+            (3 * ONE_ETH).to_string()
+        );
+    }
+
+    /// Answers just the RPC calls a contract write makes, rejecting the first
+    /// `eth_sendRawTransaction` with `first_send_error`
+    #[derive(Clone)]
+    struct ScriptedSendTransport {
+        first_send_error: &'static str,
+        sent: std::sync::Arc<parking_lot::Mutex<Vec<TxEnvelope>>>,
+        nonce_queries: std::sync::Arc<parking_lot::Mutex<u64>>,
+    }
+
+    impl ScriptedSendTransport {
+        fn new(first_send_error: &'static str) -> Self {
+            Self {
+                first_send_error,
+                sent: Default::default(),
+                nonce_queries: Default::default(),
+            }
+        }
+
+        fn respond(&self, method: &str, params: &str) -> Result<serde_json::Value, String> {
+            match method {
+                "eth_chainId" => Ok(serde_json::json!("0x7a69")),
+                // The filler's nonce is stale; the node's pending count is not
+                "eth_getTransactionCount" => {
+                    let mut queries = self.nonce_queries.lock();
+                    *queries += 1;
+                    Ok(serde_json::json!(if *queries == 1 { "0x0" } else { "0x5" }))
+                }
+                "eth_sendRawTransaction" => {
+                    let (raw,): (Bytes,) = serde_json::from_str(params).unwrap();
+                    let tx = TxEnvelope::decode_2718(&mut raw.as_ref()).unwrap();
+                    let tx_hash = *tx.tx_hash();
+                    let mut sent = self.sent.lock();
+                    sent.push(tx);
+                    if sent.len() == 1 {
+                        Err(self.first_send_error.to_string())
+                    } else {
+                        Ok(serde_json::json!(tx_hash))
+                    }
+                }
+                "eth_getTransactionReceipt" => {
+                    let tx_hash = *self.sent.lock().last().unwrap().tx_hash();
+                    Ok(serde_json::json!({
+                        "type": "0x2",
+                        "status": "0x1",
+                        "cumulativeGasUsed": "0x5208",
+                        "logs": [],
+                        "logsBloom": format!("0x{}", "00".repeat(256)),
+                        "transactionHash": tx_hash,
+                        "transactionIndex": "0x0",
+                        "blockHash": B256::repeat_byte(0x22),
+                        "blockNumber": "0x10",
+                        "gasUsed": "0x5208",
+                        "effectiveGasPrice": "0x1",
+                        "from": dev_address(),
+                        "to": MOCK_COMPUTE_STAKING,
+                        "contractAddress": null,
+                    }))
+                }
+                other => Err(format!("unexpected call to {}", other)),
+            }
+        }
+    }
+
+    impl tower::Service<alloy::rpc::json_rpc::RequestPacket> for ScriptedSendTransport {
+        type Response = alloy::rpc::json_rpc::ResponsePacket;
+        type Error = alloy::transports::TransportError;
+        type Future = alloy::transports::TransportFut<'static>;
+
+        fn poll_ready(
+            &mut self,
+            _: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Result<(), Self::Error>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, packet: alloy::rpc::json_rpc::RequestPacket) -> Self::Future {
+            use alloy::rpc::json_rpc::{
+                ErrorPayload, RequestPacket, Response, ResponsePacket, ResponsePayload,
+            };
+
+            let RequestPacket::Single(request) = packet else {
+                panic!("unexpected batch request");
+            };
+            let params = request.params().map(|params| params.get()).unwrap_or("[]");
+            let payload = match self.respond(request.method(), params) {
+                Ok(value) => {
+                    ResponsePayload::Success(serde_json::value::to_raw_value(&value).unwrap())
+                }
+                Err(message) => ResponsePayload::Failure(ErrorPayload {
+                    code: -32000,
+                    message,
+                    data: None,
+                }),
+            };
+            let id = request.id().clone();
+            Box::pin(async move { Ok(ResponsePacket::Single(Response { id, payload })) })
+        }
+    }
+
+    async fn send_increase_stake(
+        inner: &AppStateInner,
+        transport: &ScriptedSendTransport,
+    ) -> Result<SubmittedTx, StakingError> {
+        let signer: PrivateKeySigner = DEV_PRIVATE_KEY.parse().unwrap();
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(signer))
+            .on_client(alloy::rpc::client::RpcClient::new(transport.clone(), true));
+        let compute_staking = IComputeStaking::new(MOCK_COMPUTE_STAKING, &provider);
+        // Gas and fees are fixed so the fillers only ask for the chain id and nonce
+        let call = compute_staking
+            .increaseStake()
+            .value(U256::from(ONE_ETH))
+            .gas(100_000);
+        let fees = FeeOverrides {
+            max_fee_per_gas: Some("2000000000".to_string()),
+            max_priority_fee_per_gas: Some("1000000000".to_string()),
+            deadline_unix: None,
+        };
+        send_and_confirm(inner, &call, &fees, false).await
+    }
+
+    #[tokio::test]
+    async fn test_stale_nonce_is_resent_with_fresh_nonce() {
+        let state = mock_chain_state("stale-nonce");
+        state
+            .inner
+            .write()
+            .await
+            .config
+            .network
+            .required_confirmations = 1;
+        let inner = state.inner.read().await;
+
+        let transport = ScriptedSendTransport::new("nonce too low: next nonce 5, tx nonce 0");
+        let submitted = send_increase_stake(&inner, &transport).await.unwrap();
+        assert!(submitted.error.is_none());
+
+        let sent = transport.sent.lock();
+        let nonces: Vec<_> = sent.iter().map(|tx| tx.nonce()).collect();
+        assert_eq!(nonces, [0, 5]);
+        assert_eq!(submitted.tx_hash, *sent[1].tx_hash());
+    }
+
+    #[tokio::test]
+    async fn test_other_send_errors_are_not_resent() {
+        let state = mock_chain_state("send-error");
+        let inner = state.inner.read().await;
+
+        let transport = ScriptedSendTransport::new("insufficient funds for gas * price + value");
+        let result = send_increase_stake(&inner, &transport).await;
+        assert!(result.is_err());
+        assert_eq!(transport.sent.lock().len(), 1);
+    }
+
+    #[test]
+    fn test_stale_nonce_wordings() {
+        let rejection = |message: &str| {
+            alloy::contract::Error::TransportError(RpcError::ErrorResp(
+                alloy::rpc::json_rpc::ErrorPayload {
+                    code: -32000,
+                    message: message.to_string(),
+                    data: None,
+                },
+            ))
+        };
+
+        for message in [
+            "nonce too low",
+            "Nonce too low: next nonce 5, tx nonce 4",
+            "Transaction nonce is too low. Try incrementing the nonce.",
+            "OldNonce, Current nonce: 5, nonce of rejected tx: 4",
+            "NONCE_TOO_LOW",
+        ] {
+            assert!(is_stale_nonce(&rejection(message)), "{}", message);
+        }
+        assert!(!is_stale_nonce(&rejection("nonce too high")));
+        assert!(!is_stale_nonce(&rejection(
+            "replacement transaction underpriced"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_read_as_overrides_default_read_address() {
+        let state = mock_chain_state("read-as");
+        let staked = Address::repeat_byte(0x44);
+        state.inner.write().await.dev_overrides.read_as = Some(staked);
+        let inner = state.inner.read().await;
+
+        assert_eq!(query_address(&inner, None).await.unwrap(), staked);
+        let explicit = Address::repeat_byte(0x55);
+        assert_eq!(
+            query_address(&inner, Some(&format!("{:?}", explicit)))
+                .await
+                .unwrap(),
+            explicit
+        );
+        // Writes still act as the wallet
+        assert_eq!(wallet_signer(&inner).unwrap().address(), dev_address());
+    }
+
+    #[tokio::test]
+    async fn test_simulate_stake_projects_top_up_from_cache() {
+        let state = mock_chain_state("simulate");
+        let inner = state.inner.read().await;
+        let mut chain = MockChain::new(31337);
+        chain.set_stake(
+            MOCK_COMPUTE_STAKING,
+            dev_address(),
+            U256::from(2 * ONE_ETH),
+            1,
+        );
+
+        let request = eth_stake_request(ONE_ETH, true);
+        let diff = simulate_stake(&inner, &chain, &request).await.unwrap();
+        assert!(diff.top_up);
+        assert_eq!(
+            diff.staked_by_token_before[0].amount_wei,
+            (2 * ONE_ETH).to_string()
+        );
+        assert_eq!(
+            diff.staked_by_token_after[0].amount_wei,
+            (3 * ONE_ETH).to_string()
+        );
+        assert_eq!(
+            diff.gas_cost_wei,
+            Some(super::chain::mock::GAS_COST_WEI.to_string())
+        );
+        assert!(chain.sent.lock().is_empty());
+
+        // Repeat previews are served without touching the chain
+        chain.rpc_error = Some("connection refused".to_string());
+        let again = simulate_stake(&inner, &chain, &eth_stake_request(2 * ONE_ETH, true))
+            .await
+            .unwrap();
+        assert_eq!(
+            again.staked_by_token_after[0].amount_wei,
+            (4 * ONE_ETH).to_string()
+        );
+        assert_eq!(again.gas_cost_wei, diff.gas_cost_wei);
+    }
 }
@@ -0,0 +1,767 @@
+//! Chain access behind the staking commands
+//!
+//! `get_staking_info`, `get_pending_rewards`, the stake and unstake commands
+//! and `exit_stake`'s stake reads and unstake read and write through
+//! `StakingChain` rather than building providers themselves, so their logic
+//! can run against `mock::MockChain` in tests. `AlloyChain` is the real
+//! implementation over the configured RPC endpoints and connected wallet.
+//!
+//! Claims and `build_signed_stake_tx`, which signs offline against the
+//! wallet's next nonce, still go to the RPC endpoints directly.
+
+use alloy::contract::CallBuilder;
+use alloy::eips::BlockId;
+use alloy::network::{EthereumWallet, TransactionBuilder};
+use alloy::primitives::{Address, U256};
+use alloy::providers::{Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionRequest;
+use alloy::sol_types::SolCall;
+use async_trait::async_trait;
+
+use super::{
+    call_error, estimate_gas_cost, fetch_stake_reads, optional_parameter,
+    read_only_contract_client, send_and_confirm, wallet_signer, with_retry, AuthorizationInfo,
+    FeeOverrides, GasEstimate, IComputeStaking, IERC20Permit, IPausable, StakeReads, StakingError,
+    SubmittedTx, TokenPermit, IERC20,
+};
+use crate::config::ServiceDescriptor;
+use crate::contracts::NodeStakeInfo;
+use crate::state::AppStateInner;
+
+#[async_trait]
+pub trait StakingChain: Send + Sync {
+    /// Chain ID reported by the endpoint
+    async fn chain_id(&self) -> Result<u64, StakingError>;
+
+    /// Block reads are pinned to; `None` reads the latest state
+    fn at_block(&self) -> Option<u64>;
+
+    /// Stakes held through the legacy node staking contracts
+    async fn legacy_stakes(&self, operator: Address) -> Result<Vec<NodeStakeInfo>, StakingError>;
+
+    /// Stakes and pending rewards of `operator` on every given contract.
+    /// Failed reads are listed in the result rather than returned as errors.
+    async fn stake_reads(
+        &self,
+        operator: Address,
+        compute_staking: Option<Address>,
+        node_staking: Option<Address>,
+        registered_services: &[(ServiceDescriptor, Address)],
+    ) -> Result<StakeReads, StakingError>;
+
+    /// `getStake` on a compute-style staking contract
+    async fn get_stake(
+        &self,
+        contract: Address,
+        staker: Address,
+    ) -> Result<IComputeStaking::getStakeReturn, StakingError>;
+
+    /// Token `staker`'s stake on a compute-style staking contract is held in;
+    /// `None` for native ETH, or when the contract only takes native stakes
+    async fn stake_token(
+        &self,
+        contract: Address,
+        staker: Address,
+    ) -> Result<Option<Address>, StakingError>;
+
+    /// `pendingRewards` on a compute-style staking contract
+    async fn pending_rewards(
+        &self,
+        contract: Address,
+        staker: Address,
+    ) -> Result<U256, StakingError>;
+
+    /// Whether `contract` is paused, or `None` if it can't be paused
+    async fn paused(&self, contract: Address) -> Result<Option<bool>, StakingError>;
+
+    async fn balance(&self, address: Address) -> Result<U256, StakingError>;
+
+    async fn token_balance(&self, token: Address, owner: Address) -> Result<U256, StakingError>;
+
+    async fn allowance(
+        &self,
+        token: Address,
+        owner: Address,
+        spender: Address,
+    ) -> Result<U256, StakingError>;
+
+    /// The token's EIP-2612 permit details for `owner`, or `None` if it
+    /// doesn't support permits
+    async fn permit_info(
+        &self,
+        token: Address,
+        owner: Address,
+    ) -> Result<Option<TokenPermit>, StakingError>;
+
+    /// Signing domain and `staker`'s next nonce for stakes relayed to
+    /// `contract`; `None` if the contract doesn't take them
+    async fn authorization_info(
+        &self,
+        contract: Address,
+        staker: Address,
+    ) -> Result<Option<AuthorizationInfo>, StakingError>;
+
+    /// The token's `decimals()`
+    async fn token_decimals(&self, token: Address) -> Result<u8, StakingError>;
+
+    /// Worst-case gas cost of `tx` at current fees, in wei
+    async fn estimate_gas_cost(&self, tx: &TransactionRequest)
+        -> Result<GasEstimate, StakingError>;
+
+    /// Sign and broadcast `tx` from the connected wallet and wait for it to
+    /// confirm. With `simulate`, a call that would revert fails without
+    /// being broadcast.
+    async fn send(
+        &self,
+        tx: TransactionRequest,
+        fees: &FeeOverrides,
+        simulate: bool,
+    ) -> Result<SubmittedTx, StakingError>;
+}
+
+/// `StakingChain` over the app's RPC endpoints and connected wallet
+pub struct AlloyChain<'a> {
+    inner: &'a AppStateInner,
+    at_block: Option<u64>,
+}
+
+impl<'a> AlloyChain<'a> {
+    pub fn new(inner: &'a AppStateInner) -> Self {
+        Self::at_block(inner, None)
+    }
+
+    /// Chain whose stake and pause reads see the state at `block`; the
+    /// endpoint must be able to serve it
+    pub fn at_block(inner: &'a AppStateInner, block: Option<u64>) -> Self {
+        Self {
+            inner,
+            at_block: block,
+        }
+    }
+
+    fn block_id(&self) -> BlockId {
+        self.at_block.map_or(BlockId::latest(), BlockId::number)
+    }
+}
+
+/// Transaction from `from` calling `call` on `to`
+pub fn call_tx(from: Address, to: Address, call: impl SolCall, value: U256) -> TransactionRequest {
+    TransactionRequest::default()
+        .with_from(from)
+        .with_to(to)
+        .with_value(value)
+        .with_input(call.abi_encode())
+}
+
+/// Contract call carrying the fields of `tx`
+fn raw_call<T, P>(provider: P, tx: &TransactionRequest) -> CallBuilder<T, P, ()>
+where
+    T: alloy::transports::Transport + Clone,
+    P: Provider<T>,
+{
+    CallBuilder::new_raw(provider, tx.input.input().cloned().unwrap_or_default())
+        .to(tx.to.and_then(|to| to.to().copied()).unwrap_or_default())
+        .value(tx.value.unwrap_or_default())
+        .from(tx.from.unwrap_or_default())
+}
+
+#[async_trait]
+impl StakingChain for AlloyChain<'_> {
+    async fn chain_id(&self) -> Result<u64, StakingError> {
+        self.inner.chain_id().await.map_err(StakingError::RpcError)
+    }
+
+    fn at_block(&self) -> Option<u64> {
+        self.at_block
+    }
+
+    async fn legacy_stakes(&self, operator: Address) -> Result<Vec<NodeStakeInfo>, StakingError> {
+        let read_client;
+        let contract_client = match self.inner.contract_client.as_ref() {
+            Some(client) => client,
+            None => {
+                read_client = read_only_contract_client(self.inner).await?;
+                &read_client
+            }
+        };
+        contract_client
+            .get_staking_info(operator, self.block_id())
+            .await
+            .map_err(StakingError::RpcError)
+    }
+
+    async fn stake_reads(
+        &self,
+        operator: Address,
+        compute_staking: Option<Address>,
+        node_staking: Option<Address>,
+        registered_services: &[(ServiceDescriptor, Address)],
+    ) -> Result<StakeReads, StakingError> {
+        let provider = self.inner.provider().await.map_err(StakingError::Config)?;
+        Ok(fetch_stake_reads(
+            self.inner,
+            &provider,
+            operator,
+            compute_staking,
+            node_staking,
+            registered_services,
+            self.block_id(),
+        )
+        .await)
+    }
+
+    async fn get_stake(
+        &self,
+        contract: Address,
+        staker: Address,
+    ) -> Result<IComputeStaking::getStakeReturn, StakingError> {
+        let provider = self.inner.provider().await.map_err(StakingError::Config)?;
+        let staking = IComputeStaking::new(contract, &*provider);
+        with_retry(self.inner.config.network.rpc_max_attempts, || async {
+            staking.getStake(staker).block(self.block_id()).call().await
+        })
+        .await
+        .map_err(|e| call_error("Failed to read stake", &e))
+    }
+
+    async fn stake_token(
+        &self,
+        contract: Address,
+        staker: Address,
+    ) -> Result<Option<Address>, StakingError> {
+        let provider = self.inner.provider().await.map_err(StakingError::Config)?;
+        let staking = IComputeStaking::new(contract, &*provider);
+        let result = with_retry(self.inner.config.network.rpc_max_attempts, || async {
+            staking
+                .stakeToken(staker)
+                .block(self.block_id())
+                .call()
+                .await
+                .map(|r| r._0)
+        })
+        .await;
+        Ok(optional_parameter("stake token", result)?.filter(|token| !token.is_zero()))
+    }
+
+    async fn pending_rewards(
+        &self,
+        contract: Address,
+        staker: Address,
+    ) -> Result<U256, StakingError> {
+        let provider = self.inner.provider().await.map_err(StakingError::Config)?;
+        let staking = IComputeStaking::new(contract, &*provider);
+        with_retry(self.inner.config.network.rpc_max_attempts, || async {
+            staking
+                .pendingRewards(staker)
+                .block(self.block_id())
+                .call()
+                .await
+        })
+        .await
+        .map(|r| r._0)
+        .map_err(|e| call_error("Failed to read pending rewards", &e))
+    }
+
+    async fn paused(&self, contract: Address) -> Result<Option<bool>, StakingError> {
+        let provider = self.inner.provider().await.map_err(StakingError::Config)?;
+        let pausable = IPausable::new(contract, &*provider);
+        let result = with_retry(self.inner.config.network.rpc_max_attempts, || async {
+            pausable
+                .paused()
+                .block(self.block_id())
+                .call()
+                .await
+                .map(|r| r._0)
+        })
+        .await;
+        optional_parameter("pause state", result)
+    }
+
+    async fn balance(&self, address: Address) -> Result<U256, StakingError> {
+        let provider = self.inner.provider().await.map_err(StakingError::Config)?;
+        provider
+            .get_balance(address)
+            .await
+            .map_err(|e| StakingError::RpcError(format!("Failed to get balance: {}", e)))
+    }
+
+    async fn token_balance(&self, token: Address, owner: Address) -> Result<U256, StakingError> {
+        let provider = self.inner.provider().await.map_err(StakingError::Config)?;
+        IERC20::new(token, &*provider)
+            .balanceOf(owner)
+            .call()
+            .await
+            .map(|r| r._0)
+            .map_err(|e| StakingError::RpcError(format!("Failed to get token balance: {}", e)))
+    }
+
+    async fn allowance(
+        &self,
+        token: Address,
+        owner: Address,
+        spender: Address,
+    ) -> Result<U256, StakingError> {
+        let provider = self.inner.provider().await.map_err(StakingError::Config)?;
+        IERC20::new(token, &*provider)
+            .allowance(owner, spender)
+            .call()
+            .await
+            .map(|r| r._0)
+            .map_err(|e| StakingError::RpcError(format!("Failed to get token allowance: {}", e)))
+    }
+
+    async fn permit_info(
+        &self,
+        token: Address,
+        owner: Address,
+    ) -> Result<Option<TokenPermit>, StakingError> {
+        let provider = self.inner.provider().await.map_err(StakingError::Config)?;
+        let token = IERC20Permit::new(token, &*provider);
+        let (name, nonces, domain_separator) =
+            (token.name(), token.nonces(owner), token.DOMAIN_SEPARATOR());
+
+        // Approving still works if any of these fail, so don't treat it as an error
+        match tokio::try_join!(name.call(), nonces.call(), domain_separator.call()) {
+            Ok((name, nonce, domain_separator)) => Ok(Some(TokenPermit {
+                name: name._0,
+                nonce: nonce._0,
+                domain_separator: domain_separator._0,
+            })),
+            Err(e) => {
+                tracing::debug!("Permit read failed: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    async fn authorization_info(
+        &self,
+        contract: Address,
+        staker: Address,
+    ) -> Result<Option<AuthorizationInfo>, StakingError> {
+        let provider = self.inner.provider().await.map_err(StakingError::Config)?;
+        let staking = IComputeStaking::new(contract, &*provider);
+        let (domain, nonces) = (staking.eip712Domain(), staking.nonces(staker));
+
+        // Contracts without relayed staking don't implement either
+        match tokio::try_join!(domain.call(), nonces.call()) {
+            Ok((domain, nonce)) => Ok(Some(AuthorizationInfo {
+                fields: domain.fields.0[0],
+                name: domain.name,
+                version: domain.version,
+                chain_id: domain.chainId,
+                verifying_contract: domain.verifyingContract,
+                salt: domain.salt,
+                nonce: nonce._0,
+            })),
+            Err(e) => {
+                tracing::debug!("Authorization domain read failed: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    async fn token_decimals(&self, token: Address) -> Result<u8, StakingError> {
+        if let Some(decimals) = self.inner.token_decimals_cache.get(token) {
+            return Ok(decimals);
+        }
+
+        let provider = self.inner.provider().await.map_err(StakingError::Config)?;
+        let token_contract = IERC20::new(token, &*provider);
+        let decimals = with_retry(self.inner.config.network.rpc_max_attempts, || async {
+            token_contract.decimals().call().await
+        })
+        .await
+        .map_err(|e| call_error("Failed to read token decimals", &e))?
+        ._0;
+        self.inner.token_decimals_cache.insert(token, decimals);
+        Ok(decimals)
+    }
+
+    async fn estimate_gas_cost(
+        &self,
+        tx: &TransactionRequest,
+    ) -> Result<GasEstimate, StakingError> {
+        let provider = self.inner.provider().await.map_err(StakingError::Config)?;
+        estimate_gas_cost(&*provider, &raw_call(&*provider, tx)).await
+    }
+
+    async fn send(
+        &self,
+        tx: TransactionRequest,
+        fees: &FeeOverrides,
+        simulate: bool,
+    ) -> Result<SubmittedTx, StakingError> {
+        let signer = wallet_signer(self.inner)?;
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(signer.clone()))
+            .on_client(
+                self.inner
+                    .rpc_client()
+                    .await
+                    .map_err(StakingError::Config)?,
+            );
+
+        let call = raw_call(&provider, &tx.with_from(signer.address()));
+        send_and_confirm(self.inner, &call, fees, simulate).await
+    }
+}
+
+#[cfg(test)]
+pub mod mock {
+    use super::super::INodeStakingManager;
+    use super::*;
+    use alloy::primitives::TxHash;
+    use parking_lot::Mutex;
+    use std::collections::HashMap;
+
+    /// Gas limit and max fee reported for every transaction
+    pub const GAS_LIMIT: u64 = 21_000;
+    pub const MAX_FEE_PER_GAS: u64 = 1_000_000_000;
+    pub const GAS_COST_WEI: u64 = GAS_LIMIT * MAX_FEE_PER_GAS;
+
+    /// In-memory chain for staking command tests. Serves canned stakes and
+    /// pending rewards, records every transaction passed to `send`, credits
+    /// successful ETH stakes to the sender and debits its unstakes. A node staking manager's
+    /// stake and pending rewards are set like any other contract's.
+    pub struct MockChain {
+        chain_id: u64,
+        /// (contract, staker) -> (amount, stakedAt)
+        stakes: Mutex<HashMap<(Address, Address), (U256, U256)>>,
+        pending: Mutex<HashMap<(Address, Address), U256>>,
+        pub balance: U256,
+        /// Fail every read with this RPC error
+        pub rpc_error: Option<String>,
+        /// Fail only the legacy stake read with this RPC error
+        pub legacy_error: Option<String>,
+        /// Revert every transaction with this reason
+        pub revert: Option<String>,
+        /// Permit details served for every token; `None` means no permit support
+        pub permit: Option<TokenPermit>,
+        /// Signing domain served for every contract; `None` means relayed
+        /// stakes aren't supported
+        pub authorization: Option<AuthorizationInfo>,
+        /// Pause state of every contract; `None` means they can't be paused
+        pub paused: Option<bool>,
+        /// Reported as the block reads are pinned to
+        pub at_block: Option<u64>,
+        /// Allowance every owner has granted every spender
+        pub allowance: U256,
+        /// Token every compute stake is held in; `None` means native ETH
+        pub stake_token: Option<Address>,
+        /// Reward token the node staking manager reports for every operator
+        pub node_reward_token: Address,
+        /// Balance per token; tokens not listed hold `balance`
+        pub token_balances: HashMap<Address, U256>,
+        /// Decimals per token; tokens not listed have 18
+        pub decimals: HashMap<Address, u8>,
+        pub sent: Mutex<Vec<TransactionRequest>>,
+        /// Every transaction passed to `estimate_gas_cost`
+        pub estimated: Mutex<Vec<TransactionRequest>>,
+    }
+
+    impl MockChain {
+        pub fn new(chain_id: u64) -> Self {
+            Self {
+                chain_id,
+                stakes: Mutex::default(),
+                pending: Mutex::default(),
+                balance: U256::MAX,
+                rpc_error: None,
+                legacy_error: None,
+                revert: None,
+                permit: None,
+                authorization: None,
+                paused: None,
+                at_block: None,
+                allowance: U256::ZERO,
+                stake_token: None,
+                node_reward_token: Address::ZERO,
+                token_balances: HashMap::new(),
+                decimals: HashMap::new(),
+                sent: Mutex::default(),
+                estimated: Mutex::default(),
+            }
+        }
+
+        pub fn set_stake(&self, contract: Address, staker: Address, amount: U256, staked_at: u64) {
+            self.stakes
+                .lock()
+                .insert((contract, staker), (amount, U256::from(staked_at)));
+        }
+
+        pub fn set_pending(&self, contract: Address, staker: Address, amount: U256) {
+            self.pending.lock().insert((contract, staker), amount);
+        }
+
+        fn check_rpc(&self) -> Result<(), StakingError> {
+            match &self.rpc_error {
+                Some(error) => Err(StakingError::RpcError(error.clone())),
+                None => Ok(()),
+            }
+        }
+
+        fn stake_of(&self, contract: Address, staker: Address) -> IComputeStaking::getStakeReturn {
+            let (amount, staked_at) = self
+                .stakes
+                .lock()
+                .get(&(contract, staker))
+                .copied()
+                .unwrap_or_default();
+            // Every stake this app sends is a provider stake
+            IComputeStaking::getStakeReturn {
+                amount,
+                stakeType: if amount.is_zero() { 0 } else { 2 },
+                stakedAt: staked_at,
+            }
+        }
+
+        fn pending_of(&self, contract: Address, staker: Address) -> U256 {
+            self.pending
+                .lock()
+                .get(&(contract, staker))
+                .copied()
+                .unwrap_or_default()
+        }
+    }
+
+    #[async_trait]
+    impl StakingChain for MockChain {
+        async fn chain_id(&self) -> Result<u64, StakingError> {
+            self.check_rpc()?;
+            Ok(self.chain_id)
+        }
+
+        fn at_block(&self) -> Option<u64> {
+            self.at_block
+        }
+
+        async fn legacy_stakes(
+            &self,
+            _operator: Address,
+        ) -> Result<Vec<NodeStakeInfo>, StakingError> {
+            match &self.legacy_error {
+                Some(error) => Err(StakingError::RpcError(error.clone())),
+                None => Ok(Vec::new()),
+            }
+        }
+
+        async fn stake_reads(
+            &self,
+            operator: Address,
+            compute_staking: Option<Address>,
+            node_staking: Option<Address>,
+            registered_services: &[(ServiceDescriptor, Address)],
+        ) -> Result<StakeReads, StakingError> {
+            let read = |contract| {
+                self.check_rpc()
+                    .map(|_| {
+                        (
+                            self.stake_of(contract, operator),
+                            self.pending_of(contract, operator),
+                        )
+                    })
+                    .map_err(|e| e.to_string())
+            };
+
+            let mut reads = StakeReads::default();
+            if let Some(contract) = compute_staking {
+                let result = read(contract);
+                reads.compute_pending = reads
+                    .note("compute", "pendingRewards", result.clone().map(|(_, p)| p))
+                    .unwrap_or_default();
+                reads.compute_stake = reads.note("compute", "getStake", result.map(|(s, _)| s));
+            }
+            if let Some(contract) = node_staking {
+                let result = read(contract);
+                reads.node_pending = reads
+                    .note("node", "nodePendingRewards", result.clone().map(|(_, p)| p))
+                    .unwrap_or_default();
+                reads.node_info = reads.note(
+                    "node",
+                    "getNodeInfo",
+                    result.map(|(stake, _)| INodeStakingManager::getNodeInfoReturn {
+                        stakeToken: Address::ZERO,
+                        stakeAmount: stake.amount,
+                        rewardToken: self.node_reward_token,
+                        rpcUrl: String::new(),
+                        region: String::new(),
+                        registeredAt: stake.stakedAt,
+                        uptime: U256::ZERO,
+                        requestsServed: U256::ZERO,
+                    }),
+                );
+            }
+            for (service, contract) in registered_services {
+                let result = read(*contract);
+                reads.note_service(
+                    service,
+                    result.clone().map(|(s, _)| s),
+                    result.map(|(_, p)| p),
+                );
+            }
+            Ok(reads)
+        }
+
+        async fn get_stake(
+            &self,
+            contract: Address,
+            staker: Address,
+        ) -> Result<IComputeStaking::getStakeReturn, StakingError> {
+            self.check_rpc()?;
+            Ok(self.stake_of(contract, staker))
+        }
+
+        async fn stake_token(
+            &self,
+            _contract: Address,
+            _staker: Address,
+        ) -> Result<Option<Address>, StakingError> {
+            self.check_rpc()?;
+            Ok(self.stake_token)
+        }
+
+        async fn pending_rewards(
+            &self,
+            contract: Address,
+            staker: Address,
+        ) -> Result<U256, StakingError> {
+            self.check_rpc()?;
+            Ok(self.pending_of(contract, staker))
+        }
+
+        async fn paused(&self, _contract: Address) -> Result<Option<bool>, StakingError> {
+            self.check_rpc()?;
+            Ok(self.paused)
+        }
+
+        async fn balance(&self, _address: Address) -> Result<U256, StakingError> {
+            self.check_rpc()?;
+            Ok(self.balance)
+        }
+
+        async fn token_balance(
+            &self,
+            token: Address,
+            _owner: Address,
+        ) -> Result<U256, StakingError> {
+            self.check_rpc()?;
+            Ok(self
+                .token_balances
+                .get(&token)
+                .copied()
+                .unwrap_or(self.balance))
+        }
+
+        async fn allowance(
+            &self,
+            _token: Address,
+            _owner: Address,
+            _spender: Address,
+        ) -> Result<U256, StakingError> {
+            self.check_rpc()?;
+            Ok(self.allowance)
+        }
+
+        async fn permit_info(
+            &self,
+            _token: Address,
+            _owner: Address,
+        ) -> Result<Option<TokenPermit>, StakingError> {
+            self.check_rpc()?;
+            Ok(self.permit.clone())
+        }
+
+        async fn authorization_info(
+            &self,
+            _contract: Address,
+            _staker: Address,
+        ) -> Result<Option<AuthorizationInfo>, StakingError> {
+            self.check_rpc()?;
+            Ok(self.authorization.clone())
+        }
+
+        async fn token_decimals(&self, token: Address) -> Result<u8, StakingError> {
+            self.check_rpc()?;
+            Ok(self.decimals.get(&token).copied().unwrap_or(18))
+        }
+
+        async fn estimate_gas_cost(
+            &self,
+            tx: &TransactionRequest,
+        ) -> Result<GasEstimate, StakingError> {
+            self.check_rpc()?;
+            self.estimated.lock().push(tx.clone());
+            Ok(GasEstimate {
+                gas_limit: GAS_LIMIT,
+                max_fee_per_gas: MAX_FEE_PER_GAS.to_string(),
+                estimated_cost_wei: GAS_COST_WEI.to_string(),
+            })
+        }
+
+        async fn send(
+            &self,
+            tx: TransactionRequest,
+            fees: &FeeOverrides,
+            simulate: bool,
+        ) -> Result<SubmittedTx, StakingError> {
+            self.check_rpc()?;
+            fees.check_deadline(chrono::Utc::now().timestamp() as u64)?;
+            if let (true, Some(reason)) = (simulate, &self.revert) {
+                return Err(StakingError::TransactionReverted(format!(
+                    "Transaction would revert: {}",
+                    reason
+                )));
+            }
+
+            let mut sent = self.sent.lock();
+            sent.push(tx.clone());
+            let tx_hash = TxHash::with_last_byte(sent.len() as u8);
+            if let Some(reason) = &self.revert {
+                return Ok(SubmittedTx {
+                    tx_hash,
+                    error: Some(format!("Transaction reverted: {}", reason)),
+                    logs: Vec::new(),
+                    gas_cost: None,
+                });
+            }
+
+            let input = tx.input.input().cloned().unwrap_or_default();
+            let is_eth_stake = [
+                IComputeStaking::stakeAsProviderCall::SELECTOR,
+                IComputeStaking::increaseStakeCall::SELECTOR,
+            ]
+            .iter()
+            .any(|selector| input.starts_with(selector));
+            if let (true, Some(contract), Some(from)) =
+                (is_eth_stake, tx.to.and_then(|to| to.to().copied()), tx.from)
+            {
+                let mut stakes = self.stakes.lock();
+                let stake = stakes.entry((contract, from)).or_default();
+                stake.0 += tx.value.unwrap_or_default();
+            }
+            if let (Some(contract), Some(from)) = (tx.to.and_then(|to| to.to().copied()), tx.from) {
+                let withdrawn = if input.starts_with(&IComputeStaking::unstake_0Call::SELECTOR) {
+                    Some(U256::MAX)
+                } else {
+                    IComputeStaking::unstake_1Call::abi_decode(&input, true)
+                        .ok()
+                        .map(|call| call.amount)
+                };
+                if let Some(withdrawn) = withdrawn {
+                    let mut stakes = self.stakes.lock();
+                    let stake = stakes.entry((contract, from)).or_default();
+                    stake.0 = stake.0.saturating_sub(withdrawn);
+                }
+            }
+
+            Ok(SubmittedTx {
+                tx_hash,
+                error: None,
+                logs: Vec::new(),
+                gas_cost: None,
+            })
+        }
+    }
+}
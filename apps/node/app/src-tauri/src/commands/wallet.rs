@@ -1,12 +1,17 @@
 //! Wallet management commands
 
+use crate::commands::staking::parse_address;
 use crate::contracts::ContractClient;
-use crate::state::AppState;
-use crate::wallet::{BalanceInfo, TransactionResult, WalletInfo, WalletManager};
+use crate::state::{AppState, AppStateInner};
+use crate::wallet::{
+    BalanceInfo, SigningPromptEvent, TransactionResult, WalletInfo, WalletManager,
+    HARDWARE_SIGNING_TIMEOUT, SIGNING_PROMPT_EVENT,
+};
+use alloy::eips::BlockId;
 use alloy::primitives::Address;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
-use tauri::State;
+use tauri::{Emitter, State};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateWalletRequest {
@@ -20,11 +25,36 @@ pub struct ImportWalletRequest {
     pub password: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConnectLedgerRequest {
+    /// Ledger Live account index; defaults to the first account
+    pub account_index: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchWalletRequest {
+    pub address: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SignMessageRequest {
     pub message: String,
 }
 
+/// An EIP-191 signed staking authentication message, with the fields it
+/// was built from so a verifier can check them without parsing the text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedMessage {
+    pub address: String,
+    pub chain_id: u64,
+    pub domain: String,
+    pub nonce: String,
+    pub issued_at: String,
+    /// The exact text that was signed
+    pub message: String,
+    pub signature: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SendTransactionRequest {
     pub to: String,
@@ -39,25 +69,27 @@ pub async fn create_wallet(
 ) -> Result<WalletInfo, String> {
     let mut inner = state.inner.write().await;
 
-    let rpc_url = inner.config.network.rpc_url.clone();
+    let rpc_urls = inner.rpc_urls();
     let chain_id = inner.config.network.chain_id;
+    let rpc_timeout = inner.config.network.rpc_timeout();
 
-    let mut manager = WalletManager::new(&rpc_url, chain_id);
+    let mut manager = WalletManager::new(rpc_urls.clone(), chain_id, rpc_timeout);
     let info = manager.create_wallet(&request.password)?;
 
     // Initialize contract client
-    let contract_client = ContractClient::new(&rpc_url, chain_id)
+    let contract_client = ContractClient::new(&rpc_urls, chain_id, rpc_timeout)
         .await
         .map_err(|e| format!("Failed to create contract client: {}", e))?;
 
+    // Update config
+    inner.config.update(|config| {
+        config.wallet.wallet_type = crate::config::WalletType::Embedded;
+        config.wallet.address = Some(info.address.clone());
+    })?;
+
     inner.wallet_manager = Some(manager);
     inner.contract_client = Some(contract_client);
 
-    // Update config
-    inner.config.wallet.wallet_type = crate::config::WalletType::Embedded;
-    inner.config.wallet.address = Some(info.address.clone());
-    inner.config.save().map_err(|e| e.to_string())?;
-
     Ok(info)
 }
 
@@ -68,10 +100,11 @@ pub async fn import_wallet(
 ) -> Result<WalletInfo, String> {
     let mut inner = state.inner.write().await;
 
-    let rpc_url = inner.config.network.rpc_url.clone();
+    let rpc_urls = inner.rpc_urls();
     let chain_id = inner.config.network.chain_id;
+    let rpc_timeout = inner.config.network.rpc_timeout();
 
-    let mut manager = WalletManager::new(&rpc_url, chain_id);
+    let mut manager = WalletManager::new(rpc_urls.clone(), chain_id, rpc_timeout);
 
     let info = if let Some(pk) = request.private_key {
         manager.import_wallet(&pk, &request.password)?
@@ -82,17 +115,88 @@ pub async fn import_wallet(
     };
 
     // Initialize contract client
-    let contract_client = ContractClient::new(&rpc_url, chain_id)
+    let contract_client = ContractClient::new(&rpc_urls, chain_id, rpc_timeout)
         .await
         .map_err(|e| format!("Failed to create contract client: {}", e))?;
 
+    // Update config
+    inner.config.update(|config| {
+        config.wallet.wallet_type = crate::config::WalletType::Embedded;
+        config.wallet.address = Some(info.address.clone());
+    })?;
+
     inner.wallet_manager = Some(manager);
     inner.contract_client = Some(contract_client);
 
-    // Update config
-    inner.config.wallet.wallet_type = crate::config::WalletType::Embedded;
-    inner.config.wallet.address = Some(info.address.clone());
-    inner.config.save().map_err(|e| e.to_string())?;
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn connect_ledger_wallet(
+    state: State<'_, AppState>,
+    request: ConnectLedgerRequest,
+) -> Result<WalletInfo, String> {
+    let mut inner = state.inner.write().await;
+
+    let rpc_urls = inner.rpc_urls();
+    let chain_id = inner.config.network.chain_id;
+    let rpc_timeout = inner.config.network.rpc_timeout();
+
+    let mut manager = WalletManager::new(rpc_urls.clone(), chain_id, rpc_timeout);
+    let info = manager
+        .connect_ledger(request.account_index.unwrap_or(0))
+        .await?;
+
+    // Initialize contract client
+    let contract_client = ContractClient::new(&rpc_urls, chain_id, rpc_timeout)
+        .await
+        .map_err(|e| format!("Failed to create contract client: {}", e))?;
+
+    // Update config; the key never leaves the device, so there is nothing to store
+    inner.config.update(|config| {
+        config.wallet.wallet_type = crate::config::WalletType::Ledger;
+        config.wallet.address = Some(info.address.clone());
+        config.wallet.encrypted_key = None;
+    })?;
+
+    inner.wallet_manager = Some(manager);
+    inner.contract_client = Some(contract_client);
+
+    Ok(info)
+}
+
+/// Follow an address without its keys, to monitor its stakes and rewards.
+/// Staking writes fail with `watch_only_wallet` until keys are imported.
+#[tauri::command]
+pub async fn watch_wallet(
+    state: State<'_, AppState>,
+    request: WatchWalletRequest,
+) -> Result<WalletInfo, String> {
+    let address = parse_address(&request.address).map_err(|e| e.to_string())?;
+
+    let mut inner = state.inner.write().await;
+
+    let rpc_urls = inner.rpc_urls();
+    let chain_id = inner.config.network.chain_id;
+    let rpc_timeout = inner.config.network.rpc_timeout();
+
+    let mut manager = WalletManager::new(rpc_urls.clone(), chain_id, rpc_timeout);
+    let info = manager.watch(address);
+
+    // Initialize contract client
+    let contract_client = ContractClient::new(&rpc_urls, chain_id, rpc_timeout)
+        .await
+        .map_err(|e| format!("Failed to create contract client: {}", e))?;
+
+    // Update config; there is no key to store
+    inner.config.update(|config| {
+        config.wallet.wallet_type = crate::config::WalletType::WatchOnly;
+        config.wallet.address = Some(info.address.clone());
+        config.wallet.encrypted_key = None;
+    })?;
+
+    inner.wallet_manager = Some(manager);
+    inner.contract_client = Some(contract_client);
 
     Ok(info)
 }
@@ -140,7 +244,7 @@ pub async fn get_balance(state: State<'_, AppState>) -> Result<BalanceInfo, Stri
 
     // Get staking info for totals
     let stakes = contract_client
-        .get_staking_info(address)
+        .get_staking_info(address, BlockId::latest())
         .await
         .unwrap_or_default();
 
@@ -172,9 +276,61 @@ pub async fn sign_message(
         .as_ref()
         .ok_or("Wallet not initialized")?;
 
+    prompt_hardware_confirmation(&inner);
     manager.sign_message(&request.message).await
 }
 
+/// Identifies this app in auth messages so a signature can't be replayed to
+/// another application asking for a similar message
+const STAKING_AUTH_DOMAIN: &str = "node.jejunetwork.org";
+
+/// Prove ownership of the staking wallet to an off-chain service, e.g. to
+/// open a dashboard session. `nonce` is issued by that service and must be
+/// 8-64 alphanumeric characters.
+#[tauri::command]
+pub async fn sign_staking_auth_message(
+    state: State<'_, AppState>,
+    nonce: String,
+) -> Result<SignedMessage, String> {
+    if !(8..=64).contains(&nonce.len()) || !nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err("Nonce must be 8-64 alphanumeric characters".to_string());
+    }
+
+    let inner = state.inner.read().await;
+    let manager = inner
+        .wallet_manager
+        .as_ref()
+        .ok_or("Wallet not initialized")?;
+    if manager.is_watch_only() {
+        return Err("Wallet is watch-only and cannot sign".to_string());
+    }
+    let address = manager.address().ok_or("Wallet not connected")?;
+
+    let chain_id = inner.config.network.chain_id;
+    let issued_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let message = format!(
+        "{} wants you to sign in with your staking wallet:\n\
+         {:?}\n\n\
+         Chain ID: {}\n\
+         Nonce: {}\n\
+         Issued At: {}",
+        STAKING_AUTH_DOMAIN, address, chain_id, nonce, issued_at
+    );
+
+    prompt_hardware_confirmation(&inner);
+    let signature = manager.sign_message(&message).await?;
+
+    Ok(SignedMessage {
+        address: format!("{:?}", address),
+        chain_id,
+        domain: STAKING_AUTH_DOMAIN.to_string(),
+        nonce,
+        issued_at,
+        message,
+        signature,
+    })
+}
+
 #[tauri::command]
 pub async fn send_transaction(
     state: State<'_, AppState>,
@@ -187,7 +343,34 @@ pub async fn send_transaction(
         .as_ref()
         .ok_or("Wallet not initialized")?;
 
+    let _send_guard = inner.send_lock.lock().await;
+    prompt_hardware_confirmation(&inner);
     manager
         .send_transaction(&request.to, &request.value, request.data.as_deref())
         .await
 }
+
+/// Tell the frontend to ask the user to confirm on their hardware wallet.
+/// No-op for in-memory signers.
+pub(crate) fn prompt_hardware_confirmation(inner: &AppStateInner) {
+    let Some(signer) = inner
+        .wallet_manager
+        .as_ref()
+        .and_then(|manager| manager.get_signer())
+        .filter(|signer| signer.is_hardware())
+    else {
+        return;
+    };
+    let Some(handle) = inner.app_handle.as_ref() else {
+        return;
+    };
+
+    let event = SigningPromptEvent {
+        address: format!("{:?}", signer.address()),
+        wallet_type: signer.wallet_type().to_string(),
+        timeout_secs: HARDWARE_SIGNING_TIMEOUT.as_secs(),
+    };
+    if let Err(e) = handle.emit(SIGNING_PROMPT_EVENT, event) {
+        tracing::warn!("Failed to emit signing prompt: {}", e);
+    }
+}
@@ -1,8 +1,11 @@
 //! Node configuration management
 
+use alloy::primitives::Address;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
 
 /// Service-specific configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +59,74 @@ pub struct NetworkConfig {
     pub rpc_url: String,
     pub ws_url: Option<String>,
     pub explorer_url: String,
+    /// Chain the RPC endpoint must report before anything is sent; defaults to `chain_id`
+    #[serde(default)]
+    pub expected_chain_id: Option<u64>,
+    /// How long write commands wait for a transaction receipt
+    #[serde(default = "default_receipt_timeout_secs")]
+    pub receipt_timeout_secs: u64,
+    /// Attempts made for read calls that fail with a transient RPC error
+    #[serde(default = "default_rpc_max_attempts")]
+    pub rpc_max_attempts: u32,
+    /// How long a single RPC request may take before it fails as a timeout.
+    /// Separate from `receipt_timeout_secs`, which covers waiting for blocks.
+    #[serde(default = "default_rpc_timeout_ms")]
+    pub rpc_timeout_ms: u64,
+    /// Blocks a write must be buried under before it's reported as successful
+    #[serde(default = "default_required_confirmations")]
+    pub required_confirmations: u64,
+    /// Endpoints tried in order when `rpc_url` stops responding
+    #[serde(default)]
+    pub fallback_rpc_urls: Vec<String>,
+    /// Confirmations after which a successful write is considered final and
+    /// no longer checked for reorgs
+    #[serde(default = "default_reorg_watch_depth")]
+    pub reorg_watch_depth: u64,
+    /// ENS registry used to resolve names; defaults to the canonical registry
+    /// on Ethereum chains and to none elsewhere
+    #[serde(default)]
+    pub ens_registry: Option<String>,
+    /// Writes are refused while the network gas price is above this many wei;
+    /// unset means no ceiling
+    #[serde(default)]
+    pub max_gas_price_wei: Option<String>,
+}
+
+impl NetworkConfig {
+    /// `rpc_url` followed by the fallbacks, in failover order
+    pub fn rpc_urls(&self) -> Vec<String> {
+        let mut urls = vec![self.rpc_url.clone()];
+        for url in &self.fallback_rpc_urls {
+            if !urls.contains(url) {
+                urls.push(url.clone());
+            }
+        }
+        urls
+    }
+
+    pub fn rpc_timeout(&self) -> Duration {
+        Duration::from_millis(self.rpc_timeout_ms)
+    }
+}
+
+fn default_receipt_timeout_secs() -> u64 {
+    120
+}
+
+fn default_rpc_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_rpc_max_attempts() -> u32 {
+    3
+}
+
+fn default_required_confirmations() -> u64 {
+    1
+}
+
+fn default_reorg_watch_depth() -> u64 {
+    64
 }
 
 impl Default for NetworkConfig {
@@ -66,6 +137,15 @@ impl Default for NetworkConfig {
             rpc_url: "https://rpc.jejunetwork.org".to_string(),
             ws_url: Some("wss://ws.jejunetwork.org".to_string()),
             explorer_url: "https://explorer.jejunetwork.org".to_string(),
+            expected_chain_id: None,
+            receipt_timeout_secs: default_receipt_timeout_secs(),
+            rpc_max_attempts: default_rpc_max_attempts(),
+            rpc_timeout_ms: default_rpc_timeout_ms(),
+            required_confirmations: default_required_confirmations(),
+            fallback_rpc_urls: Vec::new(),
+            reorg_watch_depth: default_reorg_watch_depth(),
+            ens_registry: None,
+            max_gas_price_wei: None,
         }
     }
 }
@@ -86,6 +166,9 @@ pub enum WalletType {
     Embedded,
     External,
     JejuWallet,
+    Ledger,
+    /// Address only, for monitoring; can't sign
+    WatchOnly,
 }
 
 impl Default for WalletConfig {
@@ -105,22 +188,222 @@ pub struct EarningsConfig {
     pub auto_claim: bool,
     pub auto_claim_threshold_wei: String,
     pub auto_claim_interval_hours: u32,
+    /// Unix time of the last successful auto-claim
+    #[serde(default)]
+    pub last_auto_claim_timestamp: Option<u64>,
+    /// How often the reward alert and auto-claim workers poll pending rewards
+    /// while idle. The default of 5 minutes keeps an always-on node to a few
+    /// hundred RPC calls a day.
+    #[serde(
+        default = "default_poll_interval_seconds",
+        alias = "reward_poll_interval_secs"
+    )]
+    pub poll_interval_seconds: u64,
+    /// Poll interval for the few minutes after a staking transaction, when
+    /// rewards and stakes are changing; 30 seconds by default
+    #[serde(default = "default_active_poll_interval_seconds")]
+    pub active_poll_interval_seconds: u64,
     pub auto_compound: bool,
     pub auto_stake_earnings: bool,
 }
 
+fn default_poll_interval_seconds() -> u64 {
+    300
+}
+
+fn default_active_poll_interval_seconds() -> u64 {
+    30
+}
+
 impl Default for EarningsConfig {
     fn default() -> Self {
         Self {
             auto_claim: true,
             auto_claim_threshold_wei: "1000000000000000000".to_string(), // 1 ETH
             auto_claim_interval_hours: 24,
+            last_auto_claim_timestamp: None,
+            poll_interval_seconds: default_poll_interval_seconds(),
+            active_poll_interval_seconds: default_active_poll_interval_seconds(),
             auto_compound: false,
             auto_stake_earnings: false,
         }
     }
 }
 
+/// Staking behaviour not exposed by the contracts themselves
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakingConfig {
+    /// Lockup after `stakedAt` before a stake can be withdrawn
+    pub unstake_cooldown_secs: u64,
+    /// Smallest compute provider stake the contract accepts, used when the
+    /// contract doesn't expose `minStake()` and the chain has no entry in
+    /// `min_compute_stake_wei_by_chain`
+    #[serde(default = "default_min_compute_stake_wei")]
+    pub min_compute_stake_wei: String,
+    /// Per-chain minimum compute stake, keyed by chain ID
+    #[serde(default)]
+    pub min_compute_stake_wei_by_chain: HashMap<u64, String>,
+    /// How long `get_staking_info` results are reused before re-reading the chain
+    #[serde(default = "default_info_cache_ttl_secs")]
+    pub info_cache_ttl_secs: u64,
+    /// Local log of staking attempts; defaults to `staking-log.json` in the data dir
+    #[serde(default)]
+    pub log_path: Option<String>,
+    /// Oldest log entries are dropped beyond this many
+    #[serde(default = "default_log_max_entries")]
+    pub log_max_entries: usize,
+}
+
+fn default_min_compute_stake_wei() -> String {
+    "100000000000000000".to_string() // 0.1 ETH
+}
+
+fn default_info_cache_ttl_secs() -> u64 {
+    10
+}
+
+fn default_log_max_entries() -> usize {
+    1000
+}
+
+impl Default for StakingConfig {
+    fn default() -> Self {
+        Self {
+            unstake_cooldown_secs: 7 * 24 * 60 * 60, // 7 days
+            min_compute_stake_wei: default_min_compute_stake_wei(),
+            min_compute_stake_wei_by_chain: HashMap::new(),
+            info_cache_ttl_secs: default_info_cache_ttl_secs(),
+            log_path: None,
+            log_max_entries: default_log_max_entries(),
+        }
+    }
+}
+
+impl StakingConfig {
+    /// Configured minimum compute stake for `chain_id`
+    pub fn min_compute_stake_wei(&self, chain_id: u64) -> &str {
+        self.min_compute_stake_wei_by_chain
+            .get(&chain_id)
+            .unwrap_or(&self.min_compute_stake_wei)
+    }
+}
+
+/// Price feed configuration for USD conversions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceConfig {
+    /// CoinGecko-compatible `/simple/price` endpoint
+    pub source_url: String,
+    /// Price source IDs keyed by lowercase token symbol or address
+    pub token_ids: HashMap<String, String>,
+    pub cache_ttl_secs: u64,
+    /// Oldest source price accepted when converting a USD stake amount
+    #[serde(default = "default_max_quote_age_secs")]
+    pub max_quote_age_secs: u64,
+}
+
+fn default_max_quote_age_secs() -> u64 {
+    300
+}
+
+impl Default for PriceConfig {
+    fn default() -> Self {
+        let mut token_ids = HashMap::new();
+        token_ids.insert("eth".to_string(), "ethereum".to_string());
+
+        Self {
+            source_url: "https://api.coingecko.com/api/v3/simple/price".to_string(),
+            token_ids,
+            cache_ttl_secs: 60,
+            max_quote_age_secs: default_max_quote_age_secs(),
+        }
+    }
+}
+
+/// Operational switches for incidents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SafetyConfig {
+    /// Blocks every staking write; reads keep working
+    #[serde(default)]
+    pub writes_disabled: bool,
+}
+
+/// Staking contract deployments for a single chain
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContractsConfig {
+    pub compute_staking: Option<String>,
+    #[serde(default)]
+    pub node_staking_manager: Option<String>,
+    /// Multicall3 deployment used to batch view calls; reads go one by one without it
+    #[serde(default)]
+    pub multicall3: Option<String>,
+    /// ERC20 the chain charges gas in, on chains where that isn't the native
+    /// asset; gas estimates are then taken to be in units of this token
+    #[serde(default)]
+    pub gas_token: Option<String>,
+    /// Stakeable services beyond the built-in compute and node staking
+    #[serde(default)]
+    pub services: Vec<ServiceDescriptor>,
+}
+
+/// A stakeable service. Registered services' contracts must implement the
+/// IComputeStaking stake, reward and claim functions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceDescriptor {
+    pub id: String,
+    pub name: String,
+    pub contract_address: String,
+    /// "ETH" or the ERC20 token address
+    pub stake_token: String,
+    pub min_stake_wei: String,
+}
+
+impl ContractsConfig {
+    /// Check that every configured address is well-formed
+    pub fn validate(&self) -> Result<(), String> {
+        for address in [
+            &self.compute_staking,
+            &self.node_staking_manager,
+            &self.multicall3,
+            &self.gas_token,
+        ]
+        .into_iter()
+        .flatten()
+        .chain(
+            self.services
+                .iter()
+                .map(|service| &service.contract_address),
+        ) {
+            parse_contract_address(address)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a configured contract address.
+/// Mixed-case addresses must carry a valid EIP-55 checksum.
+pub fn parse_contract_address(value: &str) -> Result<Address, String> {
+    let hex_part = value
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("Contract address {} must start with 0x", value))?;
+
+    if hex_part.len() != 40 {
+        return Err(format!(
+            "Contract address {} must be 20 bytes (40 hex characters)",
+            value
+        ));
+    }
+
+    let has_upper = hex_part.chars().any(|c| c.is_ascii_uppercase());
+    let has_lower = hex_part.chars().any(|c| c.is_ascii_lowercase());
+
+    if has_upper && has_lower {
+        Address::parse_checksummed(value, None)
+            .map_err(|_| format!("Contract address {} has an invalid checksum", value))
+    } else {
+        Address::from_str(value).map_err(|e| format!("Invalid contract address {}: {}", value, e))
+    }
+}
+
 /// Main node configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NodeConfig {
@@ -128,6 +411,15 @@ pub struct NodeConfig {
     pub network: NetworkConfig,
     pub wallet: WalletConfig,
     pub earnings: EarningsConfig,
+    /// Contract deployments keyed by chain ID
+    #[serde(default = "default_contracts")]
+    pub contracts: HashMap<u64, ContractsConfig>,
+    #[serde(default)]
+    pub staking: StakingConfig,
+    #[serde(default)]
+    pub prices: PriceConfig,
+    #[serde(default)]
+    pub safety: SafetyConfig,
     pub services: HashMap<String, ServiceConfig>,
     pub bots: HashMap<String, BotConfig>,
     pub start_minimized: bool,
@@ -135,6 +427,14 @@ pub struct NodeConfig {
     pub notifications_enabled: bool,
 }
 
+fn default_contracts() -> HashMap<u64, ContractsConfig> {
+    // Mainnet, testnet and localnet - addresses are filled in once deployed
+    [420690, 420691, 31337]
+        .into_iter()
+        .map(|chain_id| (chain_id, ContractsConfig::default()))
+        .collect()
+}
+
 impl Default for NodeConfig {
     fn default() -> Self {
         let mut services = HashMap::new();
@@ -171,6 +471,10 @@ impl Default for NodeConfig {
             network: NetworkConfig::default(),
             wallet: WalletConfig::default(),
             earnings: EarningsConfig::default(),
+            contracts: default_contracts(),
+            staking: StakingConfig::default(),
+            prices: PriceConfig::default(),
+            safety: SafetyConfig::default(),
             services,
             bots,
             start_minimized: false,
@@ -187,6 +491,7 @@ impl NodeConfig {
         if config_path.exists() {
             let contents = std::fs::read_to_string(&config_path)?;
             let config: NodeConfig = serde_json::from_str(&contents)?;
+            config.validate()?;
             Ok(config)
         } else {
             let config = Self::default();
@@ -195,6 +500,36 @@ impl NodeConfig {
         }
     }
 
+    /// Validate values that serde can't check on its own
+    pub fn validate(&self) -> Result<(), String> {
+        for (chain_id, contracts) in &self.contracts {
+            contracts
+                .validate()
+                .map_err(|e| format!("Invalid contracts config for chain {}: {}", chain_id, e))?;
+        }
+        Ok(())
+    }
+
+    /// Apply `update` to a copy of the config, validate and save the copy,
+    /// and only then replace `self` with it. A change that fails validation
+    /// or can't be written leaves the config as it was, matching the file.
+    pub fn update<T>(&mut self, update: impl FnOnce(&mut NodeConfig) -> T) -> Result<T, String> {
+        self.update_with(update, |config| config.save().map_err(|e| e.to_string()))
+    }
+
+    fn update_with<T>(
+        &mut self,
+        update: impl FnOnce(&mut NodeConfig) -> T,
+        save: impl FnOnce(&NodeConfig) -> Result<(), String>,
+    ) -> Result<T, String> {
+        let mut updated = self.clone();
+        let output = update(&mut updated);
+        updated.validate()?;
+        save(&updated)?;
+        *self = updated;
+        Ok(output)
+    }
+
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
         let config_path = Self::config_path()?;
 
@@ -218,3 +553,42 @@ impl NodeConfig {
         Ok(data_dir.join("jeju-node"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_save_keeps_previous_config() {
+        let mut config = NodeConfig::default();
+        config.earnings.auto_claim = false;
+        let threshold = config.earnings.auto_claim_threshold_wei.clone();
+
+        let result = config.update_with(
+            |config| {
+                config.earnings.auto_claim = true;
+                config.earnings.auto_claim_threshold_wei = "5".to_string();
+            },
+            |_| Err("disk full".to_string()),
+        );
+        assert_eq!(result, Err("disk full".to_string()));
+        assert!(!config.earnings.auto_claim);
+        assert_eq!(config.earnings.auto_claim_threshold_wei, threshold);
+
+        // Nor is an invalid change saved or applied
+        let result = config.update_with(
+            |config| {
+                config.earnings.auto_claim = true;
+                config.contracts.entry(31337).or_default().multicall3 = Some("0x12".to_string());
+            },
+            |_| panic!("invalid config saved"),
+        );
+        assert!(result.is_err());
+        assert!(!config.earnings.auto_claim);
+
+        config
+            .update_with(|config| config.earnings.auto_claim = true, |_| Ok(()))
+            .unwrap();
+        assert!(config.earnings.auto_claim);
+    }
+}
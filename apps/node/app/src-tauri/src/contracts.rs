@@ -2,12 +2,16 @@
 //!
 //! Uses alloy for type-safe contract interactions.
 
+use alloy::eips::BlockId;
 use alloy::primitives::{Address, U256};
 use alloy::providers::{Provider, ProviderBuilder, RootProvider};
 use alloy::sol;
-use alloy::transports::http::{Client, Http};
+use alloy::transports::BoxTransport;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
+
+use crate::transport::FailoverTransport;
 
 // Generate type-safe bindings for NodeStakingManager
 sol! {
@@ -88,7 +92,7 @@ sol! {
 
 /// Client for interacting with Jeju Network contracts
 pub struct ContractClient {
-    provider: Arc<RootProvider<Http<Client>>>,
+    provider: Arc<RootProvider<BoxTransport>>,
     addresses: ContractAddresses,
 }
 
@@ -144,11 +148,15 @@ impl ContractAddresses {
 
 impl ContractClient {
     /// Create a new contract client
-    pub async fn new(rpc_url: &str, chain_id: u64) -> Result<Self, String> {
-        let provider = ProviderBuilder::new().on_http(
-            rpc_url
-                .parse()
-                .map_err(|e| format!("Invalid RPC URL: {}", e))?,
+    pub async fn new(
+        rpc_urls: &[String],
+        chain_id: u64,
+        rpc_timeout: Duration,
+    ) -> Result<Self, String> {
+        let provider = ProviderBuilder::new().on_client(
+            FailoverTransport::connect(rpc_urls, rpc_timeout)
+                .await?
+                .into_client(),
         );
 
         Ok(Self {
@@ -176,23 +184,38 @@ impl ContractClient {
             .map_err(|e| format!("Failed to get JEJU balance: {}", e))
     }
 
-    /// Get staking info for an operator
-    pub async fn get_staking_info(&self, operator: Address) -> Result<Vec<NodeStakeInfo>, String> {
+    /// Get staking info for an operator as of `block`
+    pub async fn get_staking_info(
+        &self,
+        operator: Address,
+        block: BlockId,
+    ) -> Result<Vec<NodeStakeInfo>, String> {
         let staking =
             INodeStakingManager::new(self.addresses.node_staking_manager, &*self.provider);
 
         // Get all node IDs for the operator
-        let node_ids = staking
-            .getOperatorNodes(operator)
-            .call()
-            .await
-            .map(|r| r._0)
-            .map_err(|e| format!("Failed to get operator nodes: {}", e))?;
+        let node_ids = match staking.getOperatorNodes(operator).block(block).call().await {
+            Ok(result) => result._0,
+            Err(e) => {
+                // Chains without the legacy contract have nothing staked in it
+                let code = self
+                    .provider
+                    .get_code_at(self.addresses.node_staking_manager)
+                    .block_id(block)
+                    .await
+                    .map_err(|e| format!("Failed to get contract code: {}", e))?;
+                if code.is_empty() {
+                    return Ok(Vec::new());
+                }
+                return Err(format!("Failed to get operator nodes: {}", e));
+            }
+        };
 
         let mut stakes = Vec::new();
         for node_id in node_ids {
             let stake = staking
                 .getNodeStake(node_id)
+                .block(block)
                 .call()
                 .await
                 .map(|r| r._0)
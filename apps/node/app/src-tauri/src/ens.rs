@@ -0,0 +1,183 @@
+//! ENS name resolution for addresses entered or shown in the UI
+//!
+//! Works on chains with an ENS registry: `network.ens_registry` when set,
+//! otherwise the canonical deployment on Ethereum mainnet and its testnets.
+//! Elsewhere names can't be entered and addresses are simply shown as hex.
+//! Results, including "no name", are cached for `CACHE_TTL`.
+
+use alloy::primitives::{address, hex, keccak256, Address, B256};
+use alloy::sol;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::config::parse_contract_address;
+use crate::state::AppStateInner;
+
+sol! {
+    #[sol(rpc)]
+    interface IEnsRegistry {
+        function resolver(bytes32 node) external view returns (address);
+    }
+}
+
+sol! {
+    #[sol(rpc)]
+    interface IEnsResolver {
+        function addr(bytes32 node) external view returns (address);
+        function name(bytes32 node) external view returns (string);
+    }
+}
+
+/// Registry address shared by Ethereum mainnet, Sepolia and Holesky
+const CANONICAL_REGISTRY: Address = address!("00000000000C2E074eC69A0dFb2997BA6C7d2e1e");
+
+/// Chains the canonical registry is deployed on
+const CANONICAL_REGISTRY_CHAINS: [u64; 3] = [1, 11155111, 17000];
+
+/// How long a resolution is reused before it's looked up again
+const CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// A lookup result and when it was made
+type Cached<T> = (Instant, Option<T>);
+
+/// Recent forward and reverse resolutions, keyed by chain
+#[derive(Default)]
+pub struct EnsCache {
+    names: Mutex<HashMap<(u64, String), Cached<Address>>>,
+    addresses: Mutex<HashMap<(u64, Address), Cached<String>>>,
+}
+
+/// Whether `input` should be treated as an ENS name rather than an address
+pub fn is_name(input: &str) -> bool {
+    !input.starts_with("0x") && input.contains('.')
+}
+
+/// EIP-137 namehash of a (lowercased) name
+pub fn namehash(name: &str) -> B256 {
+    let mut node = B256::ZERO;
+    for label in name.rsplit('.').filter(|label| !label.is_empty()) {
+        let mut preimage = [0u8; 64];
+        preimage[..32].copy_from_slice(node.as_slice());
+        preimage[32..].copy_from_slice(keccak256(label.as_bytes()).as_slice());
+        node = keccak256(preimage);
+    }
+    node
+}
+
+/// ENS registry for the configured network, if it has one
+fn registry(inner: &AppStateInner) -> Option<Address> {
+    match inner.config.network.ens_registry.as_deref() {
+        Some(configured) => parse_contract_address(configured).ok(),
+        None => CANONICAL_REGISTRY_CHAINS
+            .contains(&inner.config.network.chain_id)
+            .then_some(CANONICAL_REGISTRY),
+    }
+}
+
+/// Resolve an ENS name to the address it points at. `Ok(None)` means the
+/// name has no address set; an error means it couldn't be looked up at all.
+pub async fn resolve_name(inner: &AppStateInner, name: &str) -> Result<Option<Address>, String> {
+    let name = name.trim().to_lowercase();
+    let chain_id = inner.config.network.chain_id;
+    let registry = registry(inner).ok_or("ENS is not available on this network")?;
+
+    let key = (chain_id, name.clone());
+    if let Some((resolved_at, address)) = inner.ens_cache.names.lock().get(&key) {
+        if resolved_at.elapsed() < CACHE_TTL {
+            return Ok(*address);
+        }
+    }
+
+    let provider = inner.provider().await?;
+    let node = namehash(&name);
+    let resolver = IEnsRegistry::new(registry, &*provider)
+        .resolver(node)
+        .call()
+        .await
+        .map_err(|e| format!("Failed to look up ENS resolver for {}: {}", name, e))?
+        ._0;
+
+    let address = if resolver.is_zero() {
+        None
+    } else {
+        let address = IEnsResolver::new(resolver, &*provider)
+            .addr(node)
+            .call()
+            .await
+            .map_err(|e| format!("Failed to resolve ENS name {}: {}", name, e))?
+            ._0;
+        (!address.is_zero()).then_some(address)
+    };
+
+    inner
+        .ens_cache
+        .names
+        .lock()
+        .insert(key, (Instant::now(), address));
+    Ok(address)
+}
+
+/// Primary ENS name of `address` for display. `None` when it has none, the
+/// network has no registry, or the lookup fails; callers show the hex instead.
+pub async fn lookup_address(inner: &AppStateInner, address: Address) -> Option<String> {
+    let chain_id = inner.config.network.chain_id;
+    let registry = registry(inner)?;
+
+    let key = (chain_id, address);
+    if let Some((resolved_at, name)) = inner.ens_cache.addresses.lock().get(&key) {
+        if resolved_at.elapsed() < CACHE_TTL {
+            return name.clone();
+        }
+    }
+
+    let name = match reverse_lookup(inner, registry, address).await {
+        Ok(name) => name,
+        Err(e) => {
+            tracing::debug!("ENS reverse lookup for {} failed: {}", address, e);
+            return None;
+        }
+    };
+
+    // Anyone can claim any reverse name, so only trust one that resolves back
+    let name = match name {
+        Some(name) if resolve_name(inner, &name).await.ok().flatten() == Some(address) => {
+            Some(name)
+        }
+        _ => None,
+    };
+
+    inner
+        .ens_cache
+        .addresses
+        .lock()
+        .insert(key, (Instant::now(), name.clone()));
+    name
+}
+
+async fn reverse_lookup(
+    inner: &AppStateInner,
+    registry: Address,
+    address: Address,
+) -> Result<Option<String>, String> {
+    let provider = inner.provider().await?;
+    let node = namehash(&format!("{}.addr.reverse", hex::encode(address)));
+
+    let resolver = IEnsRegistry::new(registry, &*provider)
+        .resolver(node)
+        .call()
+        .await
+        .map_err(|e| e.to_string())?
+        ._0;
+    if resolver.is_zero() {
+        return Ok(None);
+    }
+
+    let name = IEnsResolver::new(resolver, &*provider)
+        .name(node)
+        .call()
+        .await
+        .map_err(|e| e.to_string())?
+        ._0;
+    Ok((!name.is_empty()).then_some(name))
+}
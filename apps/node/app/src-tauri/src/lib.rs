@@ -2,12 +2,23 @@
 //!
 //! Shared library components for the Jeju Node Tauri application.
 
+pub mod auto_claim;
 pub mod commands;
 pub mod config;
 pub mod contracts;
 pub mod earnings;
+pub mod ens;
 pub mod hardware;
+pub mod log_buffer;
+pub mod poll_schedule;
+pub mod price;
+pub mod redact;
+pub mod reorg_watch;
+pub mod reward_alerts;
 pub mod services;
+pub mod shutdown;
+pub mod staking_log;
 pub mod state;
 pub mod tee;
+pub mod transport;
 pub mod wallet;
@@ -0,0 +1,57 @@
+//! In-memory buffer of recent log output
+//!
+//! A second fmt layer writes every log line here as well as to stderr, so a
+//! support bundle can include recent logs without the user hunting for a
+//! terminal. Only the newest `CAPACITY` lines are kept.
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::io;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Lines kept before the oldest are dropped
+const CAPACITY: usize = 2000;
+
+static LINES: Mutex<VecDeque<String>> = parking_lot::const_mutex(VecDeque::new());
+
+/// Writer for `tracing_subscriber::fmt::layer().with_writer(..)`. The fmt
+/// layer writes each formatted event in a single call, so every write is
+/// stored as one line.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogBuffer;
+
+impl<'a> MakeWriter<'a> for LogBuffer {
+    type Writer = LogBuffer;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        *self
+    }
+}
+
+impl io::Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let line = String::from_utf8_lossy(buf).trim_end().to_string();
+
+        let mut lines = LINES.lock();
+        if lines.len() >= CAPACITY {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The newest `limit` lines, oldest first
+pub fn recent(limit: usize) -> Vec<String> {
+    let lines = LINES.lock();
+    lines
+        .iter()
+        .skip(lines.len().saturating_sub(limit))
+        .cloned()
+        .collect()
+}
@@ -8,14 +8,25 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod auto_claim;
 mod commands;
 mod config;
 mod contracts;
 mod earnings;
+mod ens;
 mod hardware;
+mod log_buffer;
+mod poll_schedule;
+mod price;
+mod redact;
+mod reorg_watch;
+mod reward_alerts;
 mod services;
+mod shutdown;
+mod staking_log;
 mod state;
 mod tee;
+mod transport;
 mod wallet;
 
 use tauri::Manager;
@@ -29,6 +40,11 @@ fn main() {
                 .unwrap_or_else(|_| "jeju_node=info,tauri=info".into()),
         )
         .with(tracing_subscriber::fmt::layer())
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(log_buffer::LogBuffer),
+        )
         .init();
 
     tracing::info!("Starting Jeju Node v{}", env!("CARGO_PKG_VERSION"));
@@ -47,9 +63,12 @@ fn main() {
             // Wallet management
             commands::wallet::create_wallet,
             commands::wallet::import_wallet,
+            commands::wallet::connect_ledger_wallet,
+            commands::wallet::watch_wallet,
             commands::wallet::get_wallet_info,
             commands::wallet::get_balance,
             commands::wallet::sign_message,
+            commands::wallet::sign_staking_auth_message,
             commands::wallet::send_transaction,
             // Agent registration (ERC-8004)
             commands::agent::register_agent,
@@ -64,11 +83,44 @@ fn main() {
             commands::services::get_all_service_status,
             // Staking
             commands::staking::get_staking_info,
+            commands::staking::get_aggregate_staking_info,
             commands::staking::stake,
+            commands::staking::increase_stake,
+            commands::staking::retry_pending_write,
+            commands::staking::build_signed_stake_tx,
+            commands::staking::sign_stake_authorization,
+            commands::staking::broadcast_raw_tx,
+            commands::staking::estimate_stake_gas,
+            commands::staking::simulate_staking_action,
+            commands::staking::max_stakeable_amount,
+            commands::staking::get_gas_conditions,
             commands::staking::unstake,
+            commands::staking::exit_stake,
+            commands::staking::preview_unstake,
             commands::staking::claim_rewards,
+            commands::staking::claim_all_rewards,
+            commands::staking::claim_if_worthwhile,
+            commands::staking::claim_break_even_gas_price,
+            commands::staking::bump_transaction,
+            commands::staking::cancel_transaction,
+            commands::staking::get_transaction_status,
+            commands::staking::wait_for_confirmation,
+            commands::staking::get_local_staking_log,
             commands::staking::enable_auto_claim,
+            commands::staking::set_writes_disabled,
             commands::staking::get_pending_rewards,
+            commands::staking::get_block_at_timestamp,
+            commands::staking::get_rewards_projection,
+            commands::staking::estimate_next_auto_claim,
+            commands::staking::get_staking_parameters,
+            commands::staking::get_wallet_balance,
+            commands::staking::get_stake_tokens,
+            commands::staking::list_services,
+            commands::staking::get_contract_info,
+            commands::staking::get_node_metrics,
+            commands::staking::update_node_registration,
+            commands::staking::get_staking_history,
+            commands::staking::check_staking_readiness,
             // Earnings
             commands::earnings::get_earnings_summary,
             commands::earnings::get_earnings_history,
@@ -79,6 +131,12 @@ fn main() {
             commands::config::update_config,
             commands::config::get_network_config,
             commands::config::set_network,
+            commands::config::get_active_rpc_endpoint,
+            commands::config::get_network_status,
+            commands::config::set_contract_addresses_dev,
+            commands::config::read_as,
+            commands::config::get_recent_logs,
+            commands::config::export_diagnostics,
             // Trading bots
             commands::bots::get_available_bots,
             commands::bots::start_bot,
@@ -122,6 +180,22 @@ fn main() {
                 api.prevent_close();
             }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|handle, event| {
+            // Give in-flight staking writes a chance to finish and be logged.
+            // A second exit request, including the one below, goes through.
+            if let tauri::RunEvent::ExitRequested { api, .. } = event {
+                if handle.state::<state::AppState>().shutdown.is_cancelled() {
+                    return;
+                }
+                api.prevent_exit();
+
+                let handle = handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    shutdown::shutdown(&handle.state::<state::AppState>()).await;
+                    handle.exit(0);
+                });
+            }
+        });
 }
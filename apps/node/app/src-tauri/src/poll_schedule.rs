@@ -0,0 +1,85 @@
+//! Adaptive timing for the background reward pollers
+//!
+//! The reward alert and auto-claim workers poll every
+//! `earnings.poll_interval_seconds` while idle, every
+//! `earnings.active_poll_interval_seconds` for a few minutes after a staking
+//! transaction is sent (when stakes and rewards are most likely to change),
+//! and back off exponentially while polls keep failing. Intervals are
+//! re-read from the config before every wait, so changes apply without a
+//! restart.
+
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+use crate::config::EarningsConfig;
+
+/// How long polling stays fast after a transaction
+const ACTIVE_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Longest wait while backing off, unless the idle interval is longer
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// Failures after which the backoff stops growing
+const MAX_BACKOFF_STEPS: u32 = 6;
+
+/// When a staking transaction was last sent. Pollers waiting out a long
+/// interval are woken so they can switch to fast polling.
+#[derive(Default)]
+pub struct PollActivity {
+    last_transaction: Mutex<Option<Instant>>,
+    wake: Notify,
+}
+
+impl PollActivity {
+    pub fn note_transaction(&self) {
+        *self.last_transaction.lock() = Some(Instant::now());
+        self.wake.notify_waiters();
+    }
+
+    fn since_transaction(&self) -> Option<Duration> {
+        self.last_transaction.lock().map(|at| at.elapsed())
+    }
+
+    /// Wait for `delay`, returning early if a transaction is sent meanwhile
+    pub async fn sleep(self: Arc<Self>, delay: Duration) {
+        tokio::select! {
+            _ = self.wake.notified() => {}
+            _ = tokio::time::sleep(delay) => {}
+        }
+    }
+}
+
+/// Delay before the next poll, given how many polls in a row have failed
+pub fn next_poll_delay(
+    config: &EarningsConfig,
+    activity: &PollActivity,
+    consecutive_failures: u32,
+) -> Duration {
+    poll_delay(
+        Duration::from_secs(config.poll_interval_seconds.max(1)),
+        Duration::from_secs(config.active_poll_interval_seconds.max(1)),
+        activity.since_transaction(),
+        consecutive_failures,
+    )
+}
+
+fn poll_delay(
+    idle: Duration,
+    active: Duration,
+    since_transaction: Option<Duration>,
+    consecutive_failures: u32,
+) -> Duration {
+    let base = match since_transaction {
+        Some(elapsed) if elapsed < ACTIVE_WINDOW => active.min(idle),
+        _ => idle,
+    };
+    if consecutive_failures == 0 {
+        return base;
+    }
+
+    let steps = consecutive_failures.min(MAX_BACKOFF_STEPS);
+    base.saturating_mul(2u32.pow(steps))
+        .min(MAX_BACKOFF.max(idle))
+}
@@ -0,0 +1,234 @@
+//! USD price lookups for staked assets
+//!
+//! Prices come from a CoinGecko-compatible `/simple/price` endpoint and are
+//! cached for a short TTL so dashboard polling doesn't hammer the source.
+//! Prices that set a transaction value skip the cache and are checked against
+//! the source's own update time instead.
+
+use alloy::primitives::U256;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::config::PriceConfig;
+
+struct CachedPrice {
+    usd: f64,
+    fetched_at: Instant,
+}
+
+/// Cached price source for converting token amounts to USD
+pub struct PriceOracle {
+    config: PriceConfig,
+    client: reqwest::Client,
+    cache: Mutex<HashMap<String, CachedPrice>>,
+}
+
+impl PriceOracle {
+    pub fn new(config: PriceConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the USD price of one whole token, served from cache while fresh
+    pub async fn usd_price(&self, token: &str) -> Result<f64, String> {
+        let key = token.to_lowercase();
+        let ttl = Duration::from_secs(self.config.cache_ttl_secs);
+
+        if let Some(cached) = self.cache.lock().get(&key) {
+            if cached.fetched_at.elapsed() < ttl {
+                return Ok(cached.usd);
+            }
+        }
+
+        let (usd, _) = self.fetch(token).await?;
+        Ok(usd)
+    }
+
+    /// Get the USD price of one whole token straight from the source, failing
+    /// if the source last updated it more than `max_age` ago
+    pub async fn fresh_usd_price(&self, token: &str, max_age: Duration) -> Result<f64, String> {
+        let (usd, updated_at) = self.fetch(token).await?;
+        let updated_at =
+            updated_at.ok_or_else(|| format!("Price source gave no update time for {}", token))?;
+
+        let age = (chrono::Utc::now().timestamp() as u64).saturating_sub(updated_at);
+        if age > max_age.as_secs() {
+            return Err(format!(
+                "Price for {} is {} seconds old, over the {} second limit",
+                token,
+                age,
+                max_age.as_secs()
+            ));
+        }
+        Ok(usd)
+    }
+
+    /// Fetch a price and the source's update time, refreshing the cache
+    async fn fetch(&self, token: &str) -> Result<(f64, Option<u64>), String> {
+        let key = token.to_lowercase();
+        let price_id = self
+            .config
+            .token_ids
+            .get(&key)
+            .ok_or_else(|| format!("No price source configured for token {}", token))?;
+
+        let response: HashMap<String, HashMap<String, f64>> = self
+            .client
+            .get(&self.config.source_url)
+            .query(&[
+                ("ids", price_id.as_str()),
+                ("vs_currencies", "usd"),
+                ("include_last_updated_at", "true"),
+            ])
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch price for {}: {}", token, e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid price response for {}: {}", token, e))?;
+
+        let prices = response.get(price_id);
+        let usd = prices
+            .and_then(|prices| prices.get("usd"))
+            .copied()
+            .ok_or_else(|| format!("Price source has no USD price for {}", token))?;
+        let updated_at = prices
+            .and_then(|prices| prices.get("last_updated_at"))
+            .map(|updated_at| *updated_at as u64);
+
+        self.cache.lock().insert(
+            key,
+            CachedPrice {
+                usd,
+                fetched_at: Instant::now(),
+            },
+        );
+
+        Ok((usd, updated_at))
+    }
+
+    /// Convert an 18-decimal wei amount of `token` to USD
+    pub async fn to_usd(&self, amount_wei: U256, token: &str) -> Result<f64, String> {
+        self.units_to_usd(amount_wei, token, 18).await
+    }
+
+    /// Convert an amount of `token` in its smallest unit, with `decimals`
+    /// decimal places, to USD
+    pub async fn units_to_usd(
+        &self,
+        amount: U256,
+        token: &str,
+        decimals: u8,
+    ) -> Result<f64, String> {
+        if amount.is_zero() {
+            return Ok(0.0);
+        }
+
+        let price = self.usd_price(token).await?;
+        units_to_usd(amount, decimals, price)
+    }
+}
+
+impl Default for PriceOracle {
+    fn default() -> Self {
+        Self::new(PriceConfig::default())
+    }
+}
+
+/// An 18-decimal amount in whole tokens
+pub fn wei_to_tokens(amount_wei: U256) -> f64 {
+    units_to_tokens(amount_wei, 18)
+}
+
+/// An amount with `decimals` decimal places in whole tokens. The whole and
+/// fractional parts are converted separately, so amounts far beyond `u128`
+/// keep their leading digits and the fraction keeps its precision.
+pub fn units_to_tokens(amount: U256, decimals: u8) -> f64 {
+    let scale = 10f64.powi(decimals.into());
+    match U256::from(10).checked_pow(U256::from(decimals)) {
+        Some(unit) => {
+            let (whole, fraction) = amount.div_rem(unit);
+            u256_to_f64(whole) + u256_to_f64(fraction) / scale
+        }
+        // Past 77 decimals one whole token doesn't fit in a U256
+        None => u256_to_f64(amount) / scale,
+    }
+}
+
+fn u256_to_f64(value: U256) -> f64 {
+    // Every U256 is well within f64 range, so this parse can't overflow
+    value.to_string().parse().unwrap_or(f64::MAX)
+}
+
+/// USD value of an 18-decimal amount at `price` dollars per whole token
+pub fn wei_to_usd(amount_wei: U256, price: f64) -> Result<f64, String> {
+    units_to_usd(amount_wei, 18, price)
+}
+
+/// USD value of an amount with `decimals` decimal places at `price` dollars
+/// per whole token. Fails rather than returning infinity or NaN, which would
+/// serialize as `null` and break the figures shown to the user.
+pub fn units_to_usd(amount: U256, decimals: u8, price: f64) -> Result<f64, String> {
+    if !price.is_finite() || price < 0.0 {
+        return Err(format!("Invalid price: {}", price));
+    }
+
+    let usd = units_to_tokens(amount, decimals) * price;
+    if !usd.is_finite() {
+        return Err(format!(
+            "USD value of {} units at ${} is out of range",
+            amount, price
+        ));
+    }
+    Ok(usd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wei_to_usd_handles_huge_stakes() {
+        // 10^30 wei is 10^12 tokens, beyond what a u64 of wei can hold
+        let amount = U256::from(10u64).pow(U256::from(30));
+        let usd = wei_to_usd(amount, 2000.0).unwrap();
+        assert!(usd.is_finite());
+        assert!((usd / 2e15 - 1.0).abs() < 1e-12);
+
+        let usd = wei_to_usd(U256::MAX, 2000.0).unwrap();
+        assert!(usd.is_finite());
+        assert!((usd / (2000.0 * 1.157920892373162e59) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_wei_to_usd_keeps_fractions() {
+        let amount = U256::from(1_500_000_000_000_000_000u128);
+        assert_eq!(wei_to_usd(amount, 2000.0).unwrap(), 3000.0);
+        assert_eq!(wei_to_usd(U256::from(1), 1e18).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_units_to_usd_uses_token_decimals() {
+        // 1.5 USDC, which has 6 decimals
+        assert_eq!(units_to_usd(U256::from(1_500_000), 6, 1.0).unwrap(), 1.5);
+        assert_eq!(
+            units_to_usd(U256::from(2_500_000_000u64), 6, 0.999).unwrap(),
+            2497.5
+        );
+        assert_eq!(units_to_usd(U256::from(7), 0, 2.0).unwrap(), 14.0);
+        assert!(units_to_usd(U256::MAX, 255, 1.0).unwrap() < 1e-100);
+    }
+
+    #[test]
+    fn test_wei_to_usd_rejects_non_finite_results() {
+        let amount = U256::from(1_000_000_000_000_000_000u64);
+        assert!(wei_to_usd(amount, f64::NAN).is_err());
+        assert!(wei_to_usd(amount, f64::INFINITY).is_err());
+        assert!(wei_to_usd(amount, -1.0).is_err());
+        assert!(wei_to_usd(U256::MAX, f64::MAX).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! Reorg monitoring for confirmed staking transactions
+//!
+//! Write commands report success once a transaction has
+//! `required_confirmations`, but a chain can still reorg it out after that.
+//! Successful writes are tracked here and re-checked until they are
+//! `reorg_watch_depth` blocks deep, after which they are treated as final and
+//! no longer monitored. A transaction that disappears from the canonical chain
+//! within that window is reported with `staking://tx-reorged`, and cached
+//! staking info is invalidated so the UI doesn't show a stake that is gone.
+
+use alloy::primitives::{TxHash, B256};
+use alloy::providers::Provider;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::Emitter;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+
+use crate::state::AppStateInner;
+
+/// Event emitted with a `TxReorgedEvent` payload
+pub const TX_REORGED_EVENT: &str = "staking://tx-reorged";
+
+/// How often tracked transactions are re-checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxReorgedEvent {
+    pub tx_hash: String,
+    /// Block the transaction was confirmed in before it disappeared
+    pub block_number: u64,
+}
+
+/// Where a tracked transaction was last seen
+#[derive(Debug, Clone, Copy)]
+struct WatchedTx {
+    chain_id: u64,
+    block_number: u64,
+    block_hash: Option<B256>,
+}
+
+/// Recently confirmed transactions and the poller re-checking them
+#[derive(Default)]
+pub struct ReorgWatcher {
+    watched: Mutex<HashMap<TxHash, WatchedTx>>,
+    cancel: Option<CancellationToken>,
+}
+
+impl ReorgWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start monitoring a transaction confirmed in `block_number`
+    pub fn track(
+        &self,
+        chain_id: u64,
+        tx_hash: TxHash,
+        block_number: u64,
+        block_hash: Option<B256>,
+    ) {
+        self.watched.lock().insert(
+            tx_hash,
+            WatchedTx {
+                chain_id,
+                block_number,
+                block_hash,
+            },
+        );
+    }
+
+    /// Spawn the poller, replacing any previously running one. It also stops
+    /// when `shutdown` is cancelled.
+    pub fn start(&mut self, state: Arc<RwLock<AppStateInner>>, shutdown: &CancellationToken) {
+        self.stop();
+
+        let cancel = shutdown.child_token();
+        self.cancel = Some(cancel.clone());
+
+        tauri::async_runtime::spawn(async move {
+            tracing::info!("Reorg watcher started");
+
+            let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = ticker.tick() => check_watched(&state).await,
+                }
+            }
+
+            tracing::info!("Reorg watcher stopped");
+        });
+    }
+
+    /// Signal the poller to exit
+    pub fn stop(&mut self) {
+        if let Some(cancel) = self.cancel.take() {
+            cancel.cancel();
+        }
+    }
+}
+
+async fn check_watched(state: &RwLock<AppStateInner>) {
+    let inner = state.read().await;
+
+    let watched: Vec<_> = inner
+        .reorg_watcher
+        .watched
+        .lock()
+        .iter()
+        .map(|(tx_hash, tx)| (*tx_hash, *tx))
+        .collect();
+    if watched.is_empty() {
+        return;
+    }
+
+    // A failed poll is retried on the next tick
+    let Ok(provider) = inner.provider().await else {
+        return;
+    };
+    let Ok(head) = provider.get_block_number().await else {
+        return;
+    };
+    let chain_id = inner.config.network.chain_id;
+    let depth = inner.config.network.reorg_watch_depth.max(1);
+
+    for (tx_hash, tx) in watched {
+        // Sent on a network that is no longer selected, so it can't be checked
+        if tx.chain_id != chain_id {
+            inner.reorg_watcher.watched.lock().remove(&tx_hash);
+            continue;
+        }
+
+        let receipt = match provider.get_transaction_receipt(tx_hash).await {
+            Ok(receipt) => receipt,
+            Err(e) => {
+                tracing::debug!("Failed to re-check receipt for {}: {}", tx_hash, e);
+                continue;
+            }
+        };
+
+        match receipt {
+            Some(receipt) => {
+                let block_number = receipt.block_number.unwrap_or(tx.block_number);
+                if head.saturating_sub(block_number) + 1 >= depth {
+                    inner.reorg_watcher.watched.lock().remove(&tx_hash);
+                } else if receipt.block_hash != tx.block_hash {
+                    // Re-mined in another block; keep watching from there
+                    inner
+                        .reorg_watcher
+                        .track(chain_id, tx_hash, block_number, receipt.block_hash);
+                }
+            }
+            None => {
+                tracing::warn!(
+                    "Transaction {} confirmed in block {} was reorged out",
+                    tx_hash,
+                    tx.block_number
+                );
+                inner.reorg_watcher.watched.lock().remove(&tx_hash);
+                inner.staking_info_cache.invalidate();
+
+                let Some(handle) = inner.app_handle.as_ref() else {
+                    continue;
+                };
+                let event = TxReorgedEvent {
+                    tx_hash: tx_hash.to_string(),
+                    block_number: tx.block_number,
+                };
+                if let Err(e) = handle.emit(TX_REORGED_EVENT, event) {
+                    tracing::warn!("Failed to emit reorg event for {}: {}", tx_hash, e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Notifications when pending rewards become worth claiming
+//!
+//! Polls pending rewards for every stakeable service and emits
+//! `staking://reward-threshold-reached` when a service crosses the auto-claim
+//! threshold, whether or not auto-claim is enabled. Each crossing is reported
+//! once; a service is re-armed after its rewards drop back below the threshold.
+//! Timing follows `poll_schedule`.
+
+use alloy::primitives::U256;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use tauri::Emitter;
+use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+
+use crate::commands::staking::{pending_rewards, service_descriptors};
+use crate::poll_schedule::next_poll_delay;
+use crate::state::AppStateInner;
+
+/// Event emitted with a `RewardThresholdEvent` payload
+pub const REWARD_THRESHOLD_EVENT: &str = "staking://reward-threshold-reached";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewardThresholdEvent {
+    pub service_id: String,
+    pub pending_rewards_wei: String,
+    pub threshold_wei: String,
+}
+
+/// Handle to the running reward poller
+pub struct RewardAlertWorker {
+    cancel: Option<CancellationToken>,
+}
+
+impl RewardAlertWorker {
+    pub fn new() -> Self {
+        Self { cancel: None }
+    }
+
+    /// Spawn the poller, replacing any previously running one. It also stops
+    /// when `shutdown` is cancelled.
+    pub fn start(&mut self, state: Arc<RwLock<AppStateInner>>, shutdown: &CancellationToken) {
+        self.stop();
+
+        let cancel = shutdown.child_token();
+        self.cancel = Some(cancel.clone());
+
+        tauri::async_runtime::spawn(async move {
+            tracing::info!("Reward alert worker started");
+
+            // Services currently above the threshold and already reported
+            let mut notified = HashSet::new();
+            let mut failures = 0;
+
+            loop {
+                // Re-read each round so interval changes apply without a restart
+                let (delay, activity) = {
+                    let inner = state.read().await;
+                    let delay =
+                        next_poll_delay(&inner.config.earnings, &inner.poll_activity, failures);
+                    (delay, inner.poll_activity.clone())
+                };
+
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = activity.sleep(delay) => {
+                        if check_thresholds(&state, &mut notified).await {
+                            failures = 0;
+                        } else {
+                            failures += 1;
+                        }
+                    }
+                }
+            }
+
+            tracing::info!("Reward alert worker stopped");
+        });
+    }
+
+    /// Signal the poller to exit
+    pub fn stop(&mut self) {
+        if let Some(cancel) = self.cancel.take() {
+            cancel.cancel();
+        }
+    }
+}
+
+impl Default for RewardAlertWorker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Poll every service, returning false if any of them couldn't be read
+async fn check_thresholds(state: &RwLock<AppStateInner>, notified: &mut HashSet<String>) -> bool {
+    let inner = state.read().await;
+
+    // Nothing to poll until a wallet is connected
+    if inner.wallet_manager.is_none() {
+        return true;
+    }
+
+    let Ok(threshold) = U256::from_str(&inner.config.earnings.auto_claim_threshold_wei) else {
+        tracing::warn!("Invalid auto-claim threshold, skipping reward alerts");
+        return true;
+    };
+
+    let mut all_polled = true;
+    for service in service_descriptors(&inner).await {
+        let pending = match pending_rewards(&inner, &service.id).await {
+            Ok(pending) => pending,
+            Err(e) => {
+                tracing::debug!("Failed to poll rewards for {}: {}", service.id, e);
+                all_polled = false;
+                continue;
+            }
+        };
+
+        if pending.is_zero() || pending < threshold {
+            notified.remove(&service.id);
+            continue;
+        }
+        if !notified.insert(service.id.clone()) {
+            continue;
+        }
+
+        tracing::info!("Pending rewards for {} reached {} wei", service.id, pending);
+
+        let Some(handle) = inner.app_handle.as_ref() else {
+            continue;
+        };
+        let event = RewardThresholdEvent {
+            service_id: service.id.clone(),
+            pending_rewards_wei: pending.to_string(),
+            threshold_wei: threshold.to_string(),
+        };
+        if let Err(e) = handle.emit(REWARD_THRESHOLD_EVENT, event) {
+            tracing::warn!("Failed to emit reward alert for {}: {}", service.id, e);
+        }
+    }
+
+    all_polled
+}
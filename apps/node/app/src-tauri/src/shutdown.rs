@@ -0,0 +1,79 @@
+//! Graceful shutdown
+//!
+//! On exit the shutdown token is cancelled, which stops the background
+//! workers and makes staking writes refuse new work. Writes already under way
+//! get up to `GRACE_PERIOD` to confirm and record their outcome in the local
+//! staking log. Staking transactions still unconfirmed after that are logged
+//! as pending, so their hashes are not lost when the app closes.
+
+use alloy::primitives::TxHash;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::staking_log::StakingLogEntry;
+use crate::state::AppState;
+
+/// How long in-flight writes get to finish before the app exits anyway
+const GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// Broadcast staking transactions whose outcome isn't known yet, with the
+/// log entry to write for each if the app exits first
+#[derive(Default)]
+pub struct UnconfirmedTxs {
+    txs: Mutex<HashMap<TxHash, StakingLogEntry>>,
+}
+
+impl UnconfirmedTxs {
+    /// Track a transaction until the returned guard is dropped
+    pub fn track(&self, tx_hash: TxHash, entry: StakingLogEntry) -> UnconfirmedTx<'_> {
+        self.txs.lock().insert(tx_hash, entry);
+        UnconfirmedTx { txs: self, tx_hash }
+    }
+}
+
+/// Stops tracking its transaction when dropped, however the wait ended
+pub struct UnconfirmedTx<'a> {
+    txs: &'a UnconfirmedTxs,
+    tx_hash: TxHash,
+}
+
+impl Drop for UnconfirmedTx<'_> {
+    fn drop(&mut self) {
+        self.txs.txs.lock().remove(&self.tx_hash);
+    }
+}
+
+/// Stop background work and wait for in-flight writes, logging any staking
+/// transaction that is still unconfirmed when the grace period runs out
+pub async fn shutdown(state: &AppState) {
+    state.shutdown.cancel();
+
+    let (unconfirmed, staking_log, staking_config) = {
+        let inner = state.inner.read().await;
+        (
+            inner.unconfirmed_txs.clone(),
+            inner.staking_log.clone(),
+            inner.config.staking.clone(),
+        )
+    };
+    tracing::info!("Shutting down");
+
+    // Commands hold a read lock until they have logged their outcome, so the
+    // write lock is only granted once every in-flight write has finished
+    if tokio::time::timeout(GRACE_PERIOD, state.inner.write())
+        .await
+        .is_ok()
+    {
+        return;
+    }
+
+    let entries: Vec<_> = unconfirmed.txs.lock().drain().collect();
+    for (tx_hash, entry) in entries {
+        tracing::warn!(
+            "Exiting before {} was confirmed; logged as pending",
+            tx_hash
+        );
+        staking_log.record(&staking_config, entry);
+    }
+}
@@ -0,0 +1,106 @@
+//! Local log of staking actions started from this app
+//!
+//! Complements on-chain history by also recording attempts that failed
+//! before anything was broadcast.
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config::{NodeConfig, StakingConfig};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakingLogEntry {
+    pub timestamp: i64,
+    pub action: StakingAction,
+    pub service_id: String,
+    pub amount_wei: Option<String>,
+    pub tx_hash: Option<String>,
+    pub outcome: StakingOutcome,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StakingAction {
+    Stake,
+    IncreaseStake,
+    Unstake,
+    Claim,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StakingOutcome {
+    /// Mined successfully
+    Success,
+    /// Broadcast, but reverted or not confirmed in time
+    Failed,
+    /// Rejected before anything was broadcast
+    NotSent,
+    /// Broadcast, but the app closed before it was confirmed
+    Pending,
+}
+
+/// Append-only (bounded) JSON log of staking attempts
+#[derive(Default)]
+pub struct StakingLog {
+    /// Serializes read-modify-write cycles on the log file
+    lock: Mutex<()>,
+}
+
+impl StakingLog {
+    /// Append an entry, dropping the oldest ones beyond `log_max_entries`.
+    /// Failures are logged rather than returned so they never fail the action itself.
+    pub fn record(&self, config: &StakingConfig, entry: StakingLogEntry) {
+        let _guard = self.lock.lock();
+
+        let result = Self::path(config).and_then(|path| {
+            let mut entries = Self::read(&path)?;
+            entries.push(entry);
+            let excess = entries.len().saturating_sub(config.log_max_entries.max(1));
+            entries.drain(..excess);
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, serde_json::to_string_pretty(&entries)?)?;
+            Ok(())
+        });
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to write staking log: {}", e);
+        }
+    }
+
+    /// Most recent entries first
+    pub fn entries(
+        &self,
+        config: &StakingConfig,
+        limit: Option<usize>,
+    ) -> Result<Vec<StakingLogEntry>, Box<dyn std::error::Error>> {
+        let _guard = self.lock.lock();
+
+        let mut entries = Self::read(&Self::path(config)?)?;
+        entries.reverse();
+        if let Some(limit) = limit {
+            entries.truncate(limit);
+        }
+        Ok(entries)
+    }
+
+    fn path(config: &StakingConfig) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        match config.log_path.as_deref() {
+            Some(path) => Ok(PathBuf::from(path)),
+            None => Ok(NodeConfig::data_dir()?.join("staking-log.json")),
+        }
+    }
+
+    fn read(path: &PathBuf) -> Result<Vec<StakingLogEntry>, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
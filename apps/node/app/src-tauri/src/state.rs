@@ -1,15 +1,35 @@
 //! Application state management
 
+use alloy::primitives::Address;
+use alloy::providers::{Provider, ProviderBuilder, RootProvider};
+use alloy::rpc::client::RpcClient;
+use alloy::transports::BoxTransport;
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tauri::AppHandle;
 use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
 
-use crate::config::NodeConfig;
+use crate::auto_claim::AutoClaimWorker;
+use crate::commands::staking::{
+    InFlightWrites, PendingWrite, SimulationGasCache, StakingInfoCache, StakingParametersCache,
+    TokenDecimalsCache,
+};
+use crate::config::{parse_contract_address, NodeConfig, ServiceDescriptor, WalletType};
 use crate::contracts::ContractClient;
 use crate::earnings::EarningsTracker;
+use crate::ens::EnsCache;
+use crate::poll_schedule::PollActivity;
+use crate::price::PriceOracle;
+use crate::reorg_watch::ReorgWatcher;
+use crate::reward_alerts::RewardAlertWorker;
 use crate::services::ServiceManager;
+use crate::shutdown::UnconfirmedTxs;
+use crate::staking_log::StakingLog;
+use crate::transport::FailoverTransport;
 use crate::wallet::WalletManager;
 
 /// Service status
@@ -58,6 +78,31 @@ pub struct NetworkInfo {
     pub gas_price_gwei: f64,
 }
 
+/// Debug-build overrides for testing against forks. Held in memory only, so
+/// they never reach the saved config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DevOverrides {
+    pub compute_staking: Option<String>,
+    pub node_staking_manager: Option<String>,
+    pub rpc_url: Option<String>,
+    /// Account reads report on in place of the connected wallet, e.g. a
+    /// staked address on an Anvil fork. Writes still use the wallet.
+    pub read_as: Option<Address>,
+}
+
+/// Read-only provider shared by commands that only query the chain
+pub type ReadProvider = RootProvider<BoxTransport>;
+
+/// RPC connection along with the endpoints it was built for and the chain it reported
+struct CachedProvider {
+    rpc_urls: Vec<String>,
+    rpc_timeout: Duration,
+    transport: FailoverTransport,
+    client: RpcClient<BoxTransport>,
+    provider: Arc<ReadProvider>,
+    chain_id: Option<u64>,
+}
+
 /// Inner state protected by RwLock
 pub struct AppStateInner {
     pub config: NodeConfig,
@@ -65,19 +110,209 @@ pub struct AppStateInner {
     pub contract_client: Option<ContractClient>,
     pub service_manager: ServiceManager,
     pub earnings_tracker: EarningsTracker,
+    pub price_oracle: PriceOracle,
+    pub auto_claim_worker: AutoClaimWorker,
+    pub reward_alert_worker: RewardAlertWorker,
+    pub reorg_watcher: ReorgWatcher,
+    pub poll_activity: Arc<PollActivity>,
+    pub staking_info_cache: StakingInfoCache,
+    pub staking_parameters_cache: StakingParametersCache,
+    pub simulation_gas_cache: SimulationGasCache,
+    pub token_decimals_cache: TokenDecimalsCache,
+    pub ens_cache: EnsCache,
+    pub staking_log: Arc<StakingLog>,
+    pub in_flight_writes: InFlightWrites,
+    pub pending_write: PendingWrite,
+    pub unconfirmed_txs: Arc<UnconfirmedTxs>,
+    /// Cancelled when the app starts exiting: background workers stop and
+    /// staking writes are refused
+    pub shutdown: CancellationToken,
+    pub dev_overrides: DevOverrides,
+    read_provider: Mutex<Option<CachedProvider>>,
+    /// Held while a write command fills and broadcasts its transaction, so
+    /// concurrent sends from the wallet never pick the same nonce
+    pub send_lock: tokio::sync::Mutex<()>,
+    /// Set once the app is running; used to emit events to the frontend
+    pub app_handle: Option<AppHandle>,
     pub _service_status: HashMap<String, ServiceStatus>,
     pub bot_status: HashMap<String, BotStatus>,
     pub _network_info: NetworkInfo,
     pub initialized: bool,
 }
 
+impl AppStateInner {
+    /// RPC endpoints in failover order, or just the dev override when one is set
+    pub fn rpc_urls(&self) -> Vec<String> {
+        match &self.dev_overrides.rpc_url {
+            Some(rpc_url) => vec![rpc_url.clone()],
+            None => self.config.network.rpc_urls(),
+        }
+    }
+
+    /// Get the shared read provider, reconnecting if the RPC endpoints or timeout changed
+    pub async fn provider(&self) -> Result<Arc<ReadProvider>, String> {
+        Ok(self.connection().await?.1)
+    }
+
+    /// Get the shared RPC client, for building signing providers on the same connection
+    pub async fn rpc_client(&self) -> Result<RpcClient<BoxTransport>, String> {
+        Ok(self.connection().await?.0)
+    }
+
+    /// URL of the endpoint the shared connection is currently using
+    pub async fn active_rpc_url(&self) -> Result<String, String> {
+        self.connection().await?;
+
+        self.connected_rpc_url()
+            .ok_or_else(|| "RPC endpoints changed while connecting".to_string())
+    }
+
+    /// URL of the active endpoint if the shared connection is already open
+    pub fn connected_rpc_url(&self) -> Option<String> {
+        let rpc_urls = self.rpc_urls();
+        self.read_provider
+            .lock()
+            .as_ref()
+            .filter(|entry| entry.rpc_urls == rpc_urls)
+            .map(|entry| entry.transport.active_url().to_string())
+    }
+
+    async fn connection(&self) -> Result<(RpcClient<BoxTransport>, Arc<ReadProvider>), String> {
+        let rpc_urls = self.rpc_urls();
+        let rpc_timeout = self.config.network.rpc_timeout();
+
+        if let Some(entry) = self.read_provider.lock().as_ref() {
+            if entry.rpc_urls == rpc_urls && entry.rpc_timeout == rpc_timeout {
+                return Ok((entry.client.clone(), entry.provider.clone()));
+            }
+        }
+
+        let transport = FailoverTransport::connect(&rpc_urls, rpc_timeout).await?;
+        let client = transport.clone().into_client();
+        let provider = Arc::new(ProviderBuilder::new().on_client(client.clone()));
+        *self.read_provider.lock() = Some(CachedProvider {
+            rpc_urls,
+            rpc_timeout,
+            transport,
+            client: client.clone(),
+            provider: provider.clone(),
+            chain_id: None,
+        });
+
+        Ok((client, provider))
+    }
+
+    /// Chain ID reported by the RPC endpoints, cached with the provider so it
+    /// costs one RPC call per endpoint list
+    pub async fn chain_id(&self) -> Result<u64, String> {
+        let rpc_urls = self.rpc_urls();
+        let provider = self.provider().await?;
+
+        let cached = self
+            .read_provider
+            .lock()
+            .as_ref()
+            .filter(|entry| entry.rpc_urls == rpc_urls)
+            .and_then(|entry| entry.chain_id);
+        if let Some(chain_id) = cached {
+            return Ok(chain_id);
+        }
+
+        let chain_id = provider
+            .get_chain_id()
+            .await
+            .map_err(|e| format!("Failed to get chain ID: {}", e))?;
+        if let Some(entry) = self.read_provider.lock().as_mut() {
+            if entry.rpc_urls == rpc_urls {
+                entry.chain_id = Some(chain_id);
+            }
+        }
+
+        Ok(chain_id)
+    }
+
+    /// Resolve the IComputeStaking deployment for a chain from config
+    pub fn compute_staking_address(&self, chain_id: u64) -> Result<Address, String> {
+        let address = self
+            .dev_overrides
+            .compute_staking
+            .as_deref()
+            .or_else(|| {
+                self.config
+                    .contracts
+                    .get(&chain_id)
+                    .and_then(|contracts| contracts.compute_staking.as_deref())
+            })
+            .ok_or_else(|| {
+                format!(
+                    "No compute staking contract configured for chain {}",
+                    chain_id
+                )
+            })?;
+
+        parse_contract_address(address)
+    }
+
+    /// Resolve the INodeStakingManager deployment for a chain from config
+    pub fn node_staking_address(&self, chain_id: u64) -> Result<Address, String> {
+        let address = self
+            .dev_overrides
+            .node_staking_manager
+            .as_deref()
+            .or_else(|| {
+                self.config
+                    .contracts
+                    .get(&chain_id)
+                    .and_then(|contracts| contracts.node_staking_manager.as_deref())
+            })
+            .ok_or_else(|| format!("No node staking manager configured for chain {}", chain_id))?;
+
+        parse_contract_address(address)
+    }
+
+    /// Stakeable services registered in config for a chain
+    pub fn registered_services(&self, chain_id: u64) -> &[ServiceDescriptor] {
+        self.config
+            .contracts
+            .get(&chain_id)
+            .map(|contracts| contracts.services.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Resolve the Multicall3 deployment for a chain from config
+    /// Token gas is paid in on `chain_id`, or `None` for the native asset
+    pub fn gas_token(&self, chain_id: u64) -> Result<Option<Address>, String> {
+        self.config
+            .contracts
+            .get(&chain_id)
+            .and_then(|contracts| contracts.gas_token.as_deref())
+            .map(parse_contract_address)
+            .transpose()
+    }
+
+    pub fn multicall_address(&self, chain_id: u64) -> Result<Address, String> {
+        let address = self
+            .config
+            .contracts
+            .get(&chain_id)
+            .and_then(|contracts| contracts.multicall3.as_deref())
+            .ok_or_else(|| format!("No multicall contract configured for chain {}", chain_id))?;
+
+        parse_contract_address(address)
+    }
+}
+
 /// Thread-safe application state
 pub struct AppState {
     pub inner: Arc<RwLock<AppStateInner>>,
+    /// Same token as `AppStateInner::shutdown`, usable without the lock
+    pub shutdown: CancellationToken,
 }
 
 impl AppState {
     pub fn new() -> Self {
+        let shutdown = CancellationToken::new();
+
         Self {
             inner: Arc::new(RwLock::new(AppStateInner {
                 config: NodeConfig::default(),
@@ -85,6 +320,25 @@ impl AppState {
                 contract_client: None,
                 service_manager: ServiceManager::new(),
                 earnings_tracker: EarningsTracker::new(),
+                price_oracle: PriceOracle::default(),
+                auto_claim_worker: AutoClaimWorker::new(),
+                reward_alert_worker: RewardAlertWorker::new(),
+                reorg_watcher: ReorgWatcher::new(),
+                poll_activity: Arc::default(),
+                staking_info_cache: StakingInfoCache::default(),
+                staking_parameters_cache: StakingParametersCache::default(),
+                simulation_gas_cache: SimulationGasCache::default(),
+                token_decimals_cache: TokenDecimalsCache::default(),
+                ens_cache: EnsCache::default(),
+                staking_log: Arc::default(),
+                in_flight_writes: InFlightWrites::default(),
+                pending_write: PendingWrite::default(),
+                unconfirmed_txs: Arc::default(),
+                shutdown: shutdown.clone(),
+                dev_overrides: DevOverrides::default(),
+                read_provider: Mutex::new(None),
+                send_lock: tokio::sync::Mutex::new(()),
+                app_handle: None,
                 _service_status: HashMap::new(),
                 bot_status: HashMap::new(),
                 _network_info: NetworkInfo {
@@ -97,11 +351,13 @@ impl AppState {
                 },
                 initialized: false,
             })),
+            shutdown,
         }
     }
 
-    pub fn initialize(&self, _handle: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn initialize(&self, handle: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
         let mut state = self.inner.blocking_write();
+        state.app_handle = Some(handle.clone());
 
         // Load config from disk
         state.config = NodeConfig::load()?;
@@ -113,6 +369,36 @@ impl AppState {
         // Load earnings history
         state.earnings_tracker.load()?;
 
+        state.price_oracle = PriceOracle::new(state.config.prices.clone());
+
+        // A watch-only wallet has no keys to unlock, so it can be restored as is
+        if let (WalletType::WatchOnly, Some(address)) = (
+            &state.config.wallet.wallet_type,
+            &state.config.wallet.address,
+        ) {
+            if let Ok(address) = address.parse::<Address>() {
+                let mut manager = WalletManager::new(
+                    state.rpc_urls(),
+                    state.config.network.chain_id,
+                    state.config.network.rpc_timeout(),
+                );
+                manager.watch(address);
+                state.wallet_manager = Some(manager);
+            }
+        }
+
+        if state.config.earnings.auto_claim {
+            state
+                .auto_claim_worker
+                .start(self.inner.clone(), &self.shutdown);
+        }
+        state
+            .reward_alert_worker
+            .start(self.inner.clone(), &self.shutdown);
+        state
+            .reorg_watcher
+            .start(self.inner.clone(), &self.shutdown);
+
         state.initialized = true;
 
         tracing::info!("Application state initialized");
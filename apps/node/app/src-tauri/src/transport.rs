@@ -0,0 +1,172 @@
+//! RPC connections shared by every provider in the app
+//!
+//! `http`/`https` URLs get an HTTP transport and `ws`/`wss` URLs a WebSocket
+//! connection, so any configured endpoint can use lower-latency WebSockets.
+//! `FailoverTransport` layers several endpoints behind one client so a dead
+//! primary RPC doesn't take the app down with it.
+
+use alloy::rpc::client::{ClientBuilder, RpcClient, WsConnect};
+use alloy::rpc::json_rpc::{RequestPacket, ResponsePacket};
+use alloy::transports::http::reqwest::Url;
+use alloy::transports::{
+    BoxTransport, RpcError, Transport, TransportError, TransportErrorKind, TransportFut,
+    TransportResult,
+};
+use std::future::poll_fn;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower::Service;
+
+/// Connect to an RPC endpoint, picking the transport from the URL scheme.
+/// `timeout` bounds the WebSocket handshake; HTTP connects lazily.
+pub async fn connect_rpc(
+    rpc_url: &str,
+    timeout: Duration,
+) -> Result<RpcClient<BoxTransport>, String> {
+    let url: Url = rpc_url
+        .parse()
+        .map_err(|e| format!("Invalid RPC URL: {}", e))?;
+
+    match url.scheme() {
+        "http" | "https" => Ok(ClientBuilder::default().http(url).boxed()),
+        "ws" | "wss" => tokio::time::timeout(
+            timeout,
+            ClientBuilder::default().ws(WsConnect::new(rpc_url)),
+        )
+        .await
+        .map_err(|_| format!("Failed to connect to {}: timeout", rpc_url))?
+        .map(RpcClient::boxed)
+        .map_err(|e| format!("Failed to connect to {}: {}", rpc_url, e)),
+        scheme => Err(format!(
+            "Unsupported RPC URL scheme \"{}\": expected http, https, ws or wss",
+            scheme
+        )),
+    }
+}
+
+/// Methods that broadcast a transaction. These are never retried on another
+/// endpoint, since the first one may already have broadcast the transaction.
+const BROADCAST_METHODS: [&str; 2] = ["eth_sendRawTransaction", "eth_sendTransaction"];
+
+struct Endpoint {
+    url: String,
+    transport: BoxTransport,
+}
+
+/// Transport over an ordered list of RPC endpoints. Requests go to the active
+/// endpoint; on a connection-level failure or timeout the next endpoint is
+/// tried and becomes active if it answers.
+#[derive(Clone)]
+pub struct FailoverTransport {
+    endpoints: Arc<Vec<Endpoint>>,
+    active: Arc<AtomicUsize>,
+    is_local: bool,
+    timeout: Duration,
+}
+
+impl FailoverTransport {
+    /// Connect to every endpoint that accepts a connection, keeping their order.
+    /// Fails only if none of them do. Each request to an endpoint fails with a
+    /// "timeout" transport error after `timeout`.
+    pub async fn connect(rpc_urls: &[String], timeout: Duration) -> Result<Self, String> {
+        let mut endpoints = Vec::new();
+        let mut is_local = true;
+        let mut last_error = None;
+
+        for url in rpc_urls {
+            match connect_rpc(url, timeout).await {
+                Ok(client) => {
+                    is_local &= client.is_local();
+                    endpoints.push(Endpoint {
+                        url: url.clone(),
+                        transport: client.transport().clone(),
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping RPC endpoint {}: {}", url, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if endpoints.is_empty() {
+            return Err(last_error.unwrap_or_else(|| "No RPC endpoints configured".to_string()));
+        }
+
+        Ok(Self {
+            endpoints: Arc::new(endpoints),
+            active: Arc::new(AtomicUsize::new(0)),
+            is_local,
+            timeout,
+        })
+    }
+
+    /// URL of the endpoint requests currently go to
+    pub fn active_url(&self) -> &str {
+        &self.endpoints[self.active.load(Ordering::Relaxed)].url
+    }
+
+    /// Wrap the transport in a client usable by any provider
+    pub fn into_client(self) -> RpcClient<BoxTransport> {
+        let is_local = self.is_local;
+        RpcClient::new(self.boxed(), is_local)
+    }
+
+    async fn dispatch(self, request: RequestPacket) -> TransportResult<ResponsePacket> {
+        let broadcast = match &request {
+            RequestPacket::Single(req) => BROADCAST_METHODS.contains(&req.method()),
+            RequestPacket::Batch(reqs) => reqs
+                .iter()
+                .any(|req| BROADCAST_METHODS.contains(&req.method())),
+        };
+
+        let start = self.active.load(Ordering::Relaxed);
+        let count = self.endpoints.len();
+        let mut offset = 0;
+        loop {
+            let index = (start + offset) % count;
+            let endpoint = &self.endpoints[index];
+            let mut transport = endpoint.transport.clone();
+
+            let result = tokio::time::timeout(self.timeout, async {
+                poll_fn(|cx| transport.poll_ready(cx)).await?;
+                transport.call(request.clone()).await
+            })
+            .await
+            .unwrap_or_else(|_| Err(TransportErrorKind::custom_str("timeout")));
+
+            match result {
+                // Only a transport failure means the endpoint is unreachable;
+                // error responses come from a working node
+                Err(RpcError::Transport(e)) if !broadcast && offset + 1 < count => {
+                    tracing::warn!("RPC endpoint {} failed: {}", endpoint.url, e);
+                    offset += 1;
+                }
+                Ok(response) => {
+                    if index != start {
+                        tracing::warn!("Failing over to RPC endpoint {}", endpoint.url);
+                        self.active.store(index, Ordering::Relaxed);
+                    }
+                    return Ok(response);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Service<RequestPacket> for FailoverTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        Box::pin(self.clone().dispatch(request))
+    }
+}
@@ -1,10 +1,137 @@
-use alloy::network::{EthereumWallet, TransactionBuilder};
-use alloy::primitives::{Address, Bytes, U256};
+use alloy::consensus::SignableTransaction;
+use alloy::network::{EthereumWallet, TransactionBuilder, TxSigner};
+use alloy::primitives::{Address, Bytes, Signature, U256};
 use alloy::providers::{Provider, ProviderBuilder};
 use alloy::signers::local::PrivateKeySigner;
 use alloy::signers::Signer;
+use alloy::sol_types::{Eip712Domain, SolStruct};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
+use std::time::Duration;
+
+#[cfg(feature = "ledger")]
+use alloy::signers::ledger::{HDPath, LedgerSigner};
+#[cfg(feature = "ledger")]
+use std::sync::Arc;
+
+use crate::commands::staking::parse_address;
+use crate::transport::FailoverTransport;
+
+/// Event emitted when a signature is waiting for confirmation on a hardware wallet
+pub const SIGNING_PROMPT_EVENT: &str = "wallet://confirm-on-device";
+
+/// How long to wait for the user to confirm on a hardware wallet. Generous,
+/// since they may need to unlock the device and open the Ethereum app first.
+pub const HARDWARE_SIGNING_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Message of the signing error returned when the user declines on the device
+pub const SIGNING_REJECTED: &str = "Signature rejected on the hardware wallet";
+
+/// Payload of [`SIGNING_PROMPT_EVENT`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SigningPromptEvent {
+    pub address: String,
+    pub wallet_type: String,
+    pub timeout_secs: u64,
+}
+
+/// Signer backing the loaded wallet
+#[derive(Clone)]
+pub enum WalletSigner {
+    /// Key held in memory by the embedded wallet
+    Local(PrivateKeySigner),
+    /// Key held on a Ledger; every signature needs confirmation on the device
+    #[cfg(feature = "ledger")]
+    Ledger(Arc<LedgerSigner>),
+}
+
+impl WalletSigner {
+    pub fn address(&self) -> Address {
+        match self {
+            Self::Local(signer) => signer.address(),
+            #[cfg(feature = "ledger")]
+            Self::Ledger(signer) => Signer::address(signer.as_ref()),
+        }
+    }
+
+    /// Whether signing waits on the user confirming on a device
+    pub fn is_hardware(&self) -> bool {
+        match self {
+            Self::Local(_) => false,
+            #[cfg(feature = "ledger")]
+            Self::Ledger(_) => true,
+        }
+    }
+
+    pub fn wallet_type(&self) -> &'static str {
+        match self {
+            Self::Local(_) => "embedded",
+            #[cfg(feature = "ledger")]
+            Self::Ledger(_) => "ledger",
+        }
+    }
+
+    pub async fn sign_message(&self, message: &[u8]) -> alloy::signers::Result<Signature> {
+        match self {
+            Self::Local(signer) => signer.sign_message(message).await,
+            #[cfg(feature = "ledger")]
+            Self::Ledger(signer) => await_device(signer.sign_message(message)).await,
+        }
+    }
+
+    /// Sign EIP-712 typed data; a Ledger shows the fields rather than a bare hash
+    pub async fn sign_typed_data<T: SolStruct + Send + Sync>(
+        &self,
+        payload: &T,
+        domain: &Eip712Domain,
+    ) -> alloy::signers::Result<Signature> {
+        match self {
+            Self::Local(signer) => signer.sign_typed_data(payload, domain).await,
+            #[cfg(feature = "ledger")]
+            Self::Ledger(signer) => await_device(signer.sign_typed_data(payload, domain)).await,
+        }
+    }
+}
+
+#[async_trait]
+impl TxSigner<Signature> for WalletSigner {
+    fn address(&self) -> Address {
+        WalletSigner::address(self)
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut dyn SignableTransaction<Signature>,
+    ) -> alloy::signers::Result<Signature> {
+        match self {
+            Self::Local(signer) => signer.sign_transaction(tx).await,
+            #[cfg(feature = "ledger")]
+            Self::Ledger(signer) => await_device(signer.sign_transaction(tx)).await,
+        }
+    }
+}
+
+/// Wait for a signature from a hardware wallet, turning an on-device
+/// rejection or an unattended prompt into a readable error
+#[cfg(feature = "ledger")]
+async fn await_device<T>(
+    signing: impl std::future::Future<Output = alloy::signers::Result<T>>,
+) -> alloy::signers::Result<T> {
+    // APDU status 0x6985, returned by the Ethereum app when the user declines
+    const USER_REJECTED_STATUS: &str = "ConditionsNotSatisfied";
+
+    match tokio::time::timeout(HARDWARE_SIGNING_TIMEOUT, signing).await {
+        Ok(Err(e)) if format!("{:?}", e).contains(USER_REJECTED_STATUS) => {
+            Err(alloy::signers::Error::other(SIGNING_REJECTED))
+        }
+        Ok(result) => result,
+        Err(_) => Err(alloy::signers::Error::other(format!(
+            "Timed out after {}s waiting for confirmation on the hardware wallet",
+            HARDWARE_SIGNING_TIMEOUT.as_secs()
+        ))),
+    }
+}
 
 /// Wallet information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,17 +162,22 @@ pub struct TransactionResult {
 
 /// Wallet manager handles both embedded and external wallets
 pub struct WalletManager {
-    signer: Option<PrivateKeySigner>,
+    signer: Option<WalletSigner>,
+    /// Address followed without its keys; ignored once a signer is loaded
+    watch_address: Option<Address>,
     chain_id: u64,
-    rpc_url: String,
+    rpc_urls: Vec<String>,
+    rpc_timeout: Duration,
 }
 
 impl WalletManager {
-    pub fn new(rpc_url: &str, chain_id: u64) -> Self {
+    pub fn new(rpc_urls: Vec<String>, chain_id: u64, rpc_timeout: Duration) -> Self {
         Self {
             signer: None,
+            watch_address: None,
             chain_id,
-            rpc_url: rpc_url.to_string(),
+            rpc_urls,
+            rpc_timeout,
         }
     }
 
@@ -58,7 +190,7 @@ impl WalletManager {
         // Encrypt private key with password
         let _encrypted = self.encrypt_private_key(&signer, password)?;
 
-        self.signer = Some(signer);
+        self.signer = Some(WalletSigner::Local(signer));
 
         Ok(WalletInfo {
             address,
@@ -82,7 +214,7 @@ impl WalletManager {
         // Encrypt for storage
         let _encrypted = self.encrypt_private_key(&signer, password)?;
 
-        self.signer = Some(signer);
+        self.signer = Some(WalletSigner::Local(signer));
 
         Ok(WalletInfo {
             address,
@@ -118,7 +250,7 @@ impl WalletManager {
         // Encrypt for storage
         let _encrypted = self.encrypt_private_key(&signer, password)?;
 
-        self.signer = Some(signer);
+        self.signer = Some(WalletSigner::Local(signer));
 
         Ok(WalletInfo {
             address,
@@ -128,6 +260,25 @@ impl WalletManager {
         })
     }
 
+    /// Connect a Ledger, using the Ledger Live derivation path for `account_index`.
+    /// The Ethereum app must be open on the device.
+    #[cfg(feature = "ledger")]
+    pub async fn connect_ledger(&mut self, account_index: usize) -> Result<WalletInfo, String> {
+        let signer = LedgerSigner::new(HDPath::LedgerLive(account_index), Some(self.chain_id))
+            .await
+            .map_err(|e| format!("Failed to connect to Ledger: {}", e))?;
+        let signer = WalletSigner::Ledger(Arc::new(signer));
+        let info = Self::info_for(&signer);
+
+        self.signer = Some(signer);
+        Ok(info)
+    }
+
+    #[cfg(not(feature = "ledger"))]
+    pub async fn connect_ledger(&mut self, _account_index: usize) -> Result<WalletInfo, String> {
+        Err("Hardware wallet support is not enabled in this build".to_string())
+    }
+
     /// Load encrypted wallet
     #[allow(dead_code)]
     pub fn load_wallet(
@@ -139,14 +290,57 @@ impl WalletManager {
         self.import_wallet(&private_key, password)
     }
 
+    /// Follow an address without its keys. Reads work as for any wallet;
+    /// anything that needs a signature fails until keys are loaded.
+    pub fn watch(&mut self, address: Address) -> WalletInfo {
+        self.signer = None;
+        self.watch_address = Some(address);
+        Self::watch_info(address)
+    }
+
     /// Get wallet info
     pub fn get_info(&self) -> Option<WalletInfo> {
-        self.signer.as_ref().map(|s| WalletInfo {
-            address: format!("{:?}", s.address()),
-            wallet_type: "embedded".to_string(),
+        match (&self.signer, self.watch_address) {
+            (Some(signer), _) => Some(Self::info_for(signer)),
+            (None, Some(address)) => Some(Self::watch_info(address)),
+            (None, None) => None,
+        }
+    }
+
+    /// Address of the loaded wallet, including a watch-only one
+    pub fn address(&self) -> Option<Address> {
+        self.signer
+            .as_ref()
+            .map(WalletSigner::address)
+            .or(self.watch_address)
+    }
+
+    /// Whether the wallet is known by address only and can't sign
+    pub fn is_watch_only(&self) -> bool {
+        self.signer.is_none() && self.watch_address.is_some()
+    }
+
+    fn watch_info(address: Address) -> WalletInfo {
+        WalletInfo {
+            address: format!("{:?}", address),
+            wallet_type: "watch_only".to_string(),
             agent_id: None,
             is_registered: false,
-        })
+        }
+    }
+
+    fn info_for(signer: &WalletSigner) -> WalletInfo {
+        WalletInfo {
+            address: format!("{:?}", signer.address()),
+            wallet_type: signer.wallet_type().to_string(),
+            agent_id: None,
+            is_registered: false,
+        }
+    }
+
+    /// Get the signer for the loaded wallet, if any
+    pub fn get_signer(&self) -> Option<&WalletSigner> {
+        self.signer.as_ref()
     }
 
     /// Get balances.
@@ -188,7 +382,7 @@ impl WalletManager {
         let signer = self.signer.as_ref().ok_or("Wallet not initialized")?;
         let wallet = EthereumWallet::from(signer.clone());
 
-        let to_address = Address::from_str(to).map_err(|e| format!("Invalid address: {}", e))?;
+        let to_address = parse_address(to).map_err(|e| e.to_string())?;
         let value_wei = U256::from_str(value).map_err(|e| format!("Invalid value: {}", e))?;
 
         let tx_data: Option<Bytes> = if let Some(d) = data {
@@ -199,15 +393,14 @@ impl WalletManager {
             None
         };
 
-        let rpc_url: alloy::transports::http::reqwest::Url = self
-            .rpc_url
-            .parse()
-            .map_err(|e| format!("Invalid RPC URL: {}", e))?;
-
         let provider = ProviderBuilder::new()
             .with_recommended_fillers()
             .wallet(wallet)
-            .on_http(rpc_url);
+            .on_client(
+                FailoverTransport::connect(&self.rpc_urls, self.rpc_timeout)
+                    .await?
+                    .into_client(),
+            );
 
         let mut tx = TransactionRequest::default()
             .with_to(to_address)